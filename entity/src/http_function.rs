@@ -13,10 +13,19 @@ pub struct Model {
     pub method: String,
     pub is_public: bool,
     pub content_hash: String,
+    pub timeout_secs: Option<i64>,
+    pub fuel: Option<i64>,
+    pub max_memory_mb: Option<i64>,
+    pub max_table_elements: Option<i64>,
+    pub revision: Option<i32>,
+    pub pinned: bool,
+    pub world: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_one = "super::function_rollout::Entity")]
+    FunctionRollout,
     #[sea_orm(
         belongs_to = "super::scope::Entity",
         from = "Column::ScopeId",
@@ -27,6 +36,12 @@ pub enum Relation {
     Scope,
 }
 
+impl Related<super::function_rollout::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::FunctionRollout.def()
+    }
+}
+
 impl Related<super::scope::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Scope.def()
@@ -2,8 +2,15 @@
 
 pub mod prelude;
 
+pub mod dead_letter;
+pub mod function_log;
+pub mod function_revision;
+pub mod function_rollout;
 pub mod http_function;
+pub mod key_value;
 pub mod scheduled_function;
+pub mod scheduled_run;
+pub mod scheduler_lease;
 pub mod scope;
 pub mod secret;
 pub mod variable;
@@ -1,7 +1,14 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.2
 
+pub use super::dead_letter::Entity as DeadLetter;
+pub use super::function_log::Entity as FunctionLog;
+pub use super::function_revision::Entity as FunctionRevision;
+pub use super::function_rollout::Entity as FunctionRollout;
 pub use super::http_function::Entity as HttpFunction;
+pub use super::key_value::Entity as KeyValue;
 pub use super::scheduled_function::Entity as ScheduledFunction;
+pub use super::scheduled_run::Entity as ScheduledRun;
+pub use super::scheduler_lease::Entity as SchedulerLease;
 pub use super::scope::Entity as Scope;
 pub use super::secret::Entity as Secret;
 pub use super::variable::Entity as Variable;
@@ -11,6 +11,20 @@ pub struct Model {
     pub name: String,
     pub cron: String,
     pub content_hash: String,
+    pub timeout_secs: Option<i64>,
+    pub fuel: Option<i64>,
+    pub max_memory_mb: Option<i64>,
+    pub max_table_elements: Option<i64>,
+    pub revision: Option<i32>,
+    pub pinned: bool,
+    pub run_history: Option<i32>,
+    pub retry_max_attempts: Option<i32>,
+    pub retry_backoff_secs: Option<i64>,
+    pub retry_max_backoff_secs: Option<i64>,
+    pub retry_jitter: Option<bool>,
+    pub overlap: Option<String>,
+    pub run_timeout_secs: Option<i64>,
+    pub timezone: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -15,6 +15,8 @@ pub struct Model {
 pub enum Relation {
     #[sea_orm(has_many = "super::http_function::Entity")]
     HttpFunction,
+    #[sea_orm(has_many = "super::key_value::Entity")]
+    KeyValue,
     #[sea_orm(has_many = "super::scheduled_function::Entity")]
     ScheduledFunction,
     #[sea_orm(has_many = "super::secret::Entity")]
@@ -29,6 +31,12 @@ impl Related<super::http_function::Entity> for Entity {
     }
 }
 
+impl Related<super::key_value::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::KeyValue.def()
+    }
+}
+
 impl Related<super::scheduled_function::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::ScheduledFunction.def()
@@ -1,11 +1,11 @@
-use wasm_function_sdk::blocking::scheduled::{export, Function};
+use wasm_function_sdk::blocking::scheduled::{export, Function, FunctionError, JobContext};
 
 struct Component;
 
 impl Function for Component {
     /// Say hello!
-    fn run_job() -> Result<(), ()> {
-        println!("Hello, World!");
+    fn run_job(context: JobContext) -> Result<(), FunctionError> {
+        println!("Hello, World! This is run {}", context.run_id);
         Ok(())
     }
 }
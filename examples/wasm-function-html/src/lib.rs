@@ -1,9 +1,11 @@
-use wasm_function_sdk::blocking::http::{export, Function, Header, Request, Response};
+use wasm_function_sdk::blocking::http::{
+    export, Function, FunctionError, Header, Request, Response,
+};
 
 struct Component;
 
 impl Function for Component {
-    fn handle_request(_: Request) -> Result<Response, ()> {
+    fn handle_request(_: Request) -> Result<Response, FunctionError> {
         Ok(Response {
             status_code: 200,
             headers: vec![Header {
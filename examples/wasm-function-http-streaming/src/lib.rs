@@ -0,0 +1,44 @@
+use std::io::{Read, Write};
+
+use wasm_function_sdk::blocking::http_streaming::{
+    export, send_response, Function, FunctionError, Header, Request, ResponseHead,
+};
+
+struct Component;
+
+impl Function for Component {
+    /// Uppercase the request body chunk by chunk while it is received
+    fn handle_request(req: Request) -> Result<(), FunctionError> {
+        let mut body = req.body;
+        let mut response = send_response(&ResponseHead {
+            status_code: 200,
+            headers: vec![Header {
+                name: "Content-Type".to_string(),
+                value: "text/plain".to_string(),
+            }],
+        })?;
+
+        let mut chunk = [0; 4096];
+        loop {
+            // The stream's own `read` takes a length, `Read` fills the buffer
+            let read = Read::read(&mut body, &mut chunk).map_err(stream_error)?;
+            if read == 0 {
+                break;
+            }
+            chunk[..read].make_ascii_uppercase();
+            response.write_all(&chunk[..read]).map_err(stream_error)?;
+        }
+        Ok(())
+    }
+}
+
+fn stream_error(err: std::io::Error) -> FunctionError {
+    FunctionError {
+        code: "stream-failed".to_string(),
+        message: err.to_string(),
+        status: None,
+        retryable: Some(false),
+    }
+}
+
+export!(Component);
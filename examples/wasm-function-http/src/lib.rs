@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
-use wasm_function_sdk::blocking::http::{export, Function, Header, Request, Response};
+use wasm_function_sdk::blocking::http::{
+    export, Function, FunctionError, Header, Request, Response,
+};
 
 use std::collections::HashMap;
 
@@ -17,15 +19,25 @@ struct FunctionResponse {
 struct Component;
 
 impl Function for Component {
-    fn handle_request(_req: Request) -> Result<Response, ()> {
+    fn handle_request(_req: Request) -> Result<Response, FunctionError> {
         let client = waki::Client::new();
         let http_response = client
             .get("https://httpbin.org/get?a=b")
             .headers([("Content-Type", "application/json"), ("Accept", "*/*")])
             .send()
-            .expect("Failed to send request")
+            .map_err(|err| FunctionError {
+                code: "upstream-unavailable".to_string(),
+                message: format!("Failed to send request: {err}"),
+                status: Some(502),
+                retryable: Some(true),
+            })?
             .json::<JsonResponse>()
-            .expect("Failed to parse response");
+            .map_err(|err| FunctionError {
+                code: "upstream-invalid-response".to_string(),
+                message: format!("Failed to parse response: {err}"),
+                status: Some(502),
+                retryable: Some(false),
+            })?;
 
         // Do something with the response
         //...
@@ -1,9 +1,39 @@
-use wasm_function_sdk::blocking::scheduled::{export, Function};
+use wasm_function_sdk::{
+    blocking::scheduled::{export, Function, FunctionError, JobContext},
+    keyvalue, log,
+};
 
 struct Component;
 
 impl Function for Component {
-    fn run_job() -> Result<(), ()> {
+    fn run_job(context: JobContext) -> Result<(), FunctionError> {
+        // Count the runs of the job, the store keeps the value between runs
+        let runs = keyvalue::get("runs")
+            .map_err(|err| FunctionError {
+                code: "keyvalue-unavailable".to_string(),
+                message: err.to_string(),
+                status: None,
+                retryable: Some(true),
+            })?
+            .and_then(|value| value.try_into().ok())
+            .map(u64::from_be_bytes)
+            .unwrap_or_default();
+
+        log::info(&format!(
+            "Run {} of {} (attempt {}), due at {}ms",
+            runs + 1,
+            context.function_name,
+            context.attempt,
+            context.scheduled_at_ms
+        ));
+
+        keyvalue::set("runs", &(runs + 1).to_be_bytes()).map_err(|err| FunctionError {
+            code: "keyvalue-unavailable".to_string(),
+            message: err.to_string(),
+            status: None,
+            retryable: Some(true),
+        })?;
+
         Ok(())
     }
 }
@@ -0,0 +1,47 @@
+// The exported names of the `wasi:http/proxy` world only link for WebAssembly targets
+#![cfg(target_arch = "wasm32")]
+
+use wasi::http::types::{
+    Fields, IncomingRequest, OutgoingBody, OutgoingResponse, ResponseOutparam,
+};
+
+/// Size of the chunks the request body is read and echoed with
+const CHUNK_SIZE: u64 = 64 * 1024;
+
+struct Component;
+
+impl wasi::exports::http::incoming_handler::Guest for Component {
+    /// Echo the request body back while it is still being received
+    fn handle(request: IncomingRequest, response_out: ResponseOutparam) {
+        let content_type = request
+            .headers()
+            .get(&"content-type".to_string())
+            .into_iter()
+            .next()
+            .unwrap_or_else(|| b"application/octet-stream".to_vec());
+        let headers = Fields::from_list(&[("content-type".to_string(), content_type)])
+            .expect("Failed to create headers");
+
+        let response = OutgoingResponse::new(headers);
+        let response_body = response.body().expect("Failed to get response body");
+        ResponseOutparam::set(response_out, Ok(response));
+
+        let request_body = request.consume().expect("Failed to get request body");
+        let input = request_body.stream().expect("Failed to get request stream");
+        let output = response_body
+            .write()
+            .expect("Failed to get response stream");
+        // Stops once the request body is exhausted
+        while let Ok(chunk) = input.blocking_read(CHUNK_SIZE) {
+            output
+                .blocking_write_and_flush(&chunk)
+                .expect("Failed to write response body");
+        }
+        drop(output);
+        drop(input);
+
+        OutgoingBody::finish(response_body, None).expect("Failed to finish response body");
+    }
+}
+
+wasi::http::proxy::export!(Component);
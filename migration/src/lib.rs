@@ -1,12 +1,36 @@
 pub use sea_orm_migration::prelude::*;
 
 mod m20220101_000001_create_table;
+mod m20261018_000001_add_function_limits;
+mod m20261018_000002_create_key_value;
+mod m20261018_000003_create_function_log;
+mod m20261018_000004_create_function_revision;
+mod m20261018_000005_create_function_rollout;
+mod m20261018_000006_create_scheduled_run;
+mod m20261018_000007_create_dead_letter;
+mod m20261018_000008_create_scheduler_lease;
+mod m20261018_000009_add_scheduled_overlap;
+mod m20261018_000010_add_scheduled_timezone;
+mod m20261018_000011_add_http_world;
 
 pub struct Migrator;
 
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
-        vec![Box::new(m20220101_000001_create_table::Migration)]
+        vec![
+            Box::new(m20220101_000001_create_table::Migration),
+            Box::new(m20261018_000001_add_function_limits::Migration),
+            Box::new(m20261018_000002_create_key_value::Migration),
+            Box::new(m20261018_000003_create_function_log::Migration),
+            Box::new(m20261018_000004_create_function_revision::Migration),
+            Box::new(m20261018_000005_create_function_rollout::Migration),
+            Box::new(m20261018_000006_create_scheduled_run::Migration),
+            Box::new(m20261018_000007_create_dead_letter::Migration),
+            Box::new(m20261018_000008_create_scheduler_lease::Migration),
+            Box::new(m20261018_000009_add_scheduled_overlap::Migration),
+            Box::new(m20261018_000010_add_scheduled_timezone::Migration),
+            Box::new(m20261018_000011_add_http_world::Migration),
+        ]
     }
 }
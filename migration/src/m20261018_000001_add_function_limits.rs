@@ -0,0 +1,98 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite only supports a single column per ALTER TABLE statement
+        for column in [
+            big_integer_null(HttpFunction::TimeoutSecs),
+            big_integer_null(HttpFunction::Fuel),
+            big_integer_null(HttpFunction::MaxMemoryMb),
+            big_integer_null(HttpFunction::MaxTableElements),
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(HttpFunction::Table)
+                        .add_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        for column in [
+            big_integer_null(ScheduledFunction::TimeoutSecs),
+            big_integer_null(ScheduledFunction::Fuel),
+            big_integer_null(ScheduledFunction::MaxMemoryMb),
+            big_integer_null(ScheduledFunction::MaxTableElements),
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(ScheduledFunction::Table)
+                        .add_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [
+            HttpFunction::TimeoutSecs,
+            HttpFunction::Fuel,
+            HttpFunction::MaxMemoryMb,
+            HttpFunction::MaxTableElements,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(HttpFunction::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        for column in [
+            ScheduledFunction::TimeoutSecs,
+            ScheduledFunction::Fuel,
+            ScheduledFunction::MaxMemoryMb,
+            ScheduledFunction::MaxTableElements,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(ScheduledFunction::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum HttpFunction {
+    Table,
+    TimeoutSecs,
+    Fuel,
+    MaxMemoryMb,
+    MaxTableElements,
+}
+
+#[derive(DeriveIden)]
+enum ScheduledFunction {
+    Table,
+    TimeoutSecs,
+    Fuel,
+    MaxMemoryMb,
+    MaxTableElements,
+}
@@ -0,0 +1,65 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let mut key_value_scope_id_fk = ForeignKey::create()
+            .from(KeyValue::Table, KeyValue::ScopeId)
+            .to(Scope::Table, Scope::Id)
+            .on_delete(ForeignKeyAction::Cascade)
+            .to_owned();
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(KeyValue::Table)
+                    .if_not_exists()
+                    .col(pk_uuid(KeyValue::Id).not_null().unique_key())
+                    .col(uuid(KeyValue::ScopeId).not_null())
+                    .col(string(KeyValue::Key).not_null())
+                    .col(blob(KeyValue::Value).not_null())
+                    .foreign_key(&mut key_value_scope_id_fk)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Keys are unique per scope, upserts rely on this index
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_key_value_scope_id_key")
+                    .table(KeyValue::Table)
+                    .col(KeyValue::ScopeId)
+                    .col(KeyValue::Key)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().if_exists().table(KeyValue::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum KeyValue {
+    Table,
+    Id,
+    ScopeId,
+    Key,
+    Value,
+}
+
+#[derive(DeriveIden)]
+enum Scope {
+    Table,
+    Id,
+}
@@ -0,0 +1,81 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Logs are kept for HTTP and scheduled functions, so the function id has no foreign key.
+        // They are tailed by insertion order, which the sequence keeps.
+        manager
+            .create_table(
+                Table::create()
+                    .table(FunctionLog::Table)
+                    .if_not_exists()
+                    .col(
+                        big_integer(FunctionLog::Sequence)
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(uuid(FunctionLog::Id).not_null().unique_key())
+                    .col(uuid(FunctionLog::FunctionId).not_null())
+                    .col(string_null(FunctionLog::RequestId))
+                    .col(string(FunctionLog::Source).not_null())
+                    .col(string(FunctionLog::Level).not_null())
+                    .col(text(FunctionLog::Message).not_null())
+                    .col(text_null(FunctionLog::Fields))
+                    .col(timestamp_with_time_zone(FunctionLog::CreatedAt).not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_function_log_function_id_sequence")
+                    .table(FunctionLog::Table)
+                    .col(FunctionLog::FunctionId)
+                    .col(FunctionLog::Sequence)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_function_log_function_id_created_at")
+                    .table(FunctionLog::Table)
+                    .col(FunctionLog::FunctionId)
+                    .col(FunctionLog::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .if_exists()
+                    .table(FunctionLog::Table)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum FunctionLog {
+    Table,
+    Sequence,
+    Id,
+    FunctionId,
+    RequestId,
+    Source,
+    Level,
+    Message,
+    Fields,
+    CreatedAt,
+}
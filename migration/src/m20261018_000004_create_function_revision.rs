@@ -0,0 +1,128 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Revisions are kept for HTTP and scheduled functions, so the function id has no foreign key
+        manager
+            .create_table(
+                Table::create()
+                    .table(FunctionRevision::Table)
+                    .if_not_exists()
+                    .col(pk_uuid(FunctionRevision::Id).not_null().unique_key())
+                    .col(uuid(FunctionRevision::FunctionId).not_null())
+                    .col(integer(FunctionRevision::Revision).not_null())
+                    .col(string(FunctionRevision::ContentHash).not_null())
+                    .col(text(FunctionRevision::Manifest).not_null())
+                    .col(string_null(FunctionRevision::DeployedBy))
+                    .col(timestamp_with_time_zone(FunctionRevision::CreatedAt).not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_function_revision_function_id_revision")
+                    .table(FunctionRevision::Table)
+                    .col(FunctionRevision::FunctionId)
+                    .col(FunctionRevision::Revision)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // SQLite only supports a single column per ALTER TABLE statement
+        for column in [
+            integer_null(HttpFunction::Revision),
+            boolean(HttpFunction::Pinned).default(false).take(),
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(HttpFunction::Table)
+                        .add_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        for column in [
+            integer_null(ScheduledFunction::Revision),
+            boolean(ScheduledFunction::Pinned).default(false).take(),
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(ScheduledFunction::Table)
+                        .add_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [HttpFunction::Revision, HttpFunction::Pinned] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(HttpFunction::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        for column in [ScheduledFunction::Revision, ScheduledFunction::Pinned] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(ScheduledFunction::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        manager
+            .drop_table(
+                Table::drop()
+                    .if_exists()
+                    .table(FunctionRevision::Table)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum FunctionRevision {
+    Table,
+    Id,
+    FunctionId,
+    Revision,
+    ContentHash,
+    Manifest,
+    DeployedBy,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum HttpFunction {
+    Table,
+    Revision,
+    Pinned,
+}
+
+#[derive(DeriveIden)]
+enum ScheduledFunction {
+    Table,
+    Revision,
+    Pinned,
+}
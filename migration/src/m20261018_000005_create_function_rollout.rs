@@ -0,0 +1,66 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let mut rollout_function_id_fk = ForeignKey::create()
+            .from(FunctionRollout::Table, FunctionRollout::FunctionId)
+            .to(HttpFunction::Table, HttpFunction::Id)
+            .on_delete(ForeignKeyAction::Cascade)
+            .to_owned();
+
+        // A function has at most one canary at a time
+        manager
+            .create_table(
+                Table::create()
+                    .table(FunctionRollout::Table)
+                    .if_not_exists()
+                    .col(pk_uuid(FunctionRollout::FunctionId).not_null())
+                    .col(integer(FunctionRollout::Revision).not_null())
+                    .col(integer(FunctionRollout::Weight).not_null())
+                    .col(string_null(FunctionRollout::StickyHeader))
+                    .col(string_null(FunctionRollout::StickyCookie))
+                    .col(double_null(FunctionRollout::ErrorThreshold))
+                    .col(integer(FunctionRollout::MinRequests).not_null())
+                    .col(integer_null(FunctionRollout::PromoteAfter))
+                    .col(timestamp_with_time_zone(FunctionRollout::CreatedAt).not_null())
+                    .foreign_key(&mut rollout_function_id_fk)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .if_exists()
+                    .table(FunctionRollout::Table)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum FunctionRollout {
+    Table,
+    FunctionId,
+    Revision,
+    Weight,
+    StickyHeader,
+    StickyCookie,
+    ErrorThreshold,
+    MinRequests,
+    PromoteAfter,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum HttpFunction {
+    Table,
+    Id,
+}
@@ -0,0 +1,88 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Runs are removed together with the function, like its logs and revisions
+        manager
+            .create_table(
+                Table::create()
+                    .table(ScheduledRun::Table)
+                    .if_not_exists()
+                    .col(pk_uuid(ScheduledRun::Id).not_null().unique_key())
+                    .col(uuid(ScheduledRun::FunctionId).not_null())
+                    .col(integer_null(ScheduledRun::Revision))
+                    .col(timestamp_with_time_zone(ScheduledRun::StartedAt).not_null())
+                    .col(timestamp_with_time_zone(ScheduledRun::FinishedAt).not_null())
+                    .col(big_integer(ScheduledRun::DurationMs).not_null())
+                    .col(string(ScheduledRun::Outcome).not_null())
+                    .col(text_null(ScheduledRun::Error))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_scheduled_run_function_id_started_at")
+                    .table(ScheduledRun::Table)
+                    .col(ScheduledRun::FunctionId)
+                    .col(ScheduledRun::StartedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ScheduledFunction::Table)
+                    .add_column(integer_null(ScheduledFunction::RunHistory))
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ScheduledFunction::Table)
+                    .drop_column(ScheduledFunction::RunHistory)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(
+                Table::drop()
+                    .if_exists()
+                    .table(ScheduledRun::Table)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum ScheduledRun {
+    Table,
+    Id,
+    FunctionId,
+    Revision,
+    StartedAt,
+    FinishedAt,
+    DurationMs,
+    Outcome,
+    Error,
+}
+
+#[derive(DeriveIden)]
+enum ScheduledFunction {
+    Table,
+    RunHistory,
+}
@@ -0,0 +1,129 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite only supports a single column per ALTER TABLE statement
+        for column in [
+            integer_null(ScheduledFunction::RetryMaxAttempts),
+            big_integer_null(ScheduledFunction::RetryBackoffSecs),
+            big_integer_null(ScheduledFunction::RetryMaxBackoffSecs),
+            boolean_null(ScheduledFunction::RetryJitter),
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(ScheduledFunction::Table)
+                        .add_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ScheduledRun::Table)
+                    .add_column(integer(ScheduledRun::Attempt).not_null().default(1))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(DeadLetter::Table)
+                    .if_not_exists()
+                    .col(pk_uuid(DeadLetter::Id).not_null().unique_key())
+                    .col(uuid(DeadLetter::FunctionId).not_null())
+                    .col(integer_null(DeadLetter::Revision))
+                    .col(uuid(DeadLetter::RunId).not_null())
+                    .col(integer(DeadLetter::Attempts).not_null())
+                    .col(text_null(DeadLetter::Error))
+                    .col(timestamp_with_time_zone(DeadLetter::CreatedAt).not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_dead_letter_function_id_created_at")
+                    .table(DeadLetter::Table)
+                    .col(DeadLetter::FunctionId)
+                    .col(DeadLetter::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .if_exists()
+                    .table(DeadLetter::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ScheduledRun::Table)
+                    .drop_column(ScheduledRun::Attempt)
+                    .to_owned(),
+            )
+            .await?;
+
+        for column in [
+            ScheduledFunction::RetryMaxAttempts,
+            ScheduledFunction::RetryBackoffSecs,
+            ScheduledFunction::RetryMaxBackoffSecs,
+            ScheduledFunction::RetryJitter,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(ScheduledFunction::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum ScheduledFunction {
+    Table,
+    RetryMaxAttempts,
+    RetryBackoffSecs,
+    RetryMaxBackoffSecs,
+    RetryJitter,
+}
+
+#[derive(DeriveIden)]
+enum ScheduledRun {
+    Table,
+    Attempt,
+}
+
+#[derive(DeriveIden)]
+enum DeadLetter {
+    Table,
+    Id,
+    FunctionId,
+    Revision,
+    RunId,
+    Attempts,
+    Error,
+    CreatedAt,
+}
@@ -0,0 +1,42 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(SchedulerLease::Table)
+                    .if_not_exists()
+                    .col(string(SchedulerLease::Name).not_null().primary_key())
+                    .col(string(SchedulerLease::Holder).not_null())
+                    .col(big_integer(SchedulerLease::Token).not_null())
+                    .col(timestamp_with_time_zone(SchedulerLease::ExpiresAt).not_null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .if_exists()
+                    .table(SchedulerLease::Table)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum SchedulerLease {
+    Table,
+    Name,
+    Holder,
+    Token,
+    ExpiresAt,
+}
@@ -0,0 +1,51 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite only supports a single column per ALTER TABLE statement
+        for column in [
+            string_null(ScheduledFunction::Overlap),
+            big_integer_null(ScheduledFunction::RunTimeoutSecs),
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(ScheduledFunction::Table)
+                        .add_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [
+            ScheduledFunction::Overlap,
+            ScheduledFunction::RunTimeoutSecs,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(ScheduledFunction::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum ScheduledFunction {
+    Table,
+    Overlap,
+    RunTimeoutSecs,
+}
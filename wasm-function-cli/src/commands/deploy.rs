@@ -3,6 +3,11 @@ use reqwest::blocking::{multipart, Client};
 
 const MANIFEST_FILE_NAME: &str = "manifest.toml";
 
+#[derive(serde::Deserialize)]
+struct ErrorResponse {
+    message: String,
+}
+
 pub(super) fn execute(
     token: &str,
     runtime_url: &str,
@@ -23,14 +28,22 @@ pub(super) fn execute(
 
     // Send the request
     let client = Client::new();
-    client
+    let response = client
         .post(format!("{}/api/deploy", runtime_url))
         .bearer_auth(token.to_owned())
         .multipart(form)
         .send()
-        .into_diagnostic()?
-        .error_for_status()
         .into_diagnostic()?;
 
+    // The runtime explains why it can't run the component
+    if response.status() == reqwest::StatusCode::UNPROCESSABLE_ENTITY {
+        let error = response
+            .json::<ErrorResponse>()
+            .expect("Failed to parse response");
+        return Err(miette::miette!("Deploy rejected: {}", error.message));
+    }
+
+    response.error_for_status().into_diagnostic()?;
+
     Ok(())
 }
@@ -0,0 +1,128 @@
+use miette::IntoDiagnostic;
+use serde::Deserialize;
+use tabled::{Table, Tabled};
+
+#[derive(Deserialize)]
+struct DeadLetter {
+    id: String,
+    revision: Option<i32>,
+    run_id: String,
+    attempts: u32,
+    error: Option<String>,
+    created_at: String,
+}
+
+#[derive(Deserialize)]
+struct DeadLetterListResponse {
+    dead_letters: Vec<DeadLetter>,
+}
+
+#[derive(Tabled)]
+struct OutputTableRow {
+    id: String,
+    revision: String,
+    run_id: String,
+    attempts: u32,
+    parked_at: String,
+    error: String,
+}
+
+impl From<DeadLetter> for OutputTableRow {
+    fn from(dead_letter: DeadLetter) -> Self {
+        Self {
+            id: dead_letter.id,
+            revision: dead_letter
+                .revision
+                .map(|revision| revision.to_string())
+                .unwrap_or_default(),
+            run_id: dead_letter.run_id,
+            attempts: dead_letter.attempts,
+            parked_at: dead_letter.created_at,
+            error: dead_letter.error.unwrap_or_default(),
+        }
+    }
+}
+
+fn dead_letters_url(runtime_url: &str, scope_name: &str, function_id: &str) -> String {
+    format!("{runtime_url}/api/scope/{scope_name}/function/{function_id}/dead-letters")
+}
+
+pub(super) fn list(
+    token: &str,
+    runtime_url: &str,
+    scope_name: &str,
+    function_id: &str,
+) -> miette::Result<()> {
+    let client = reqwest::blocking::Client::new();
+
+    let response = client
+        .get(dead_letters_url(runtime_url, scope_name, function_id))
+        .bearer_auth(token.to_owned())
+        .send()
+        .into_diagnostic()?
+        .error_for_status()
+        .into_diagnostic()?
+        .json::<DeadLetterListResponse>()
+        .expect("Failed to parse response");
+
+    let rows: Vec<OutputTableRow> = response
+        .dead_letters
+        .into_iter()
+        .map(OutputTableRow::from)
+        .collect();
+
+    let table = Table::new(rows);
+
+    println!("{table}");
+    Ok(())
+}
+
+pub(super) fn replay(
+    token: &str,
+    runtime_url: &str,
+    scope_name: &str,
+    function_id: &str,
+    dead_letter_id: &str,
+) -> miette::Result<()> {
+    let client = reqwest::blocking::Client::new();
+
+    client
+        .post(format!(
+            "{}/{dead_letter_id}/replay",
+            dead_letters_url(runtime_url, scope_name, function_id)
+        ))
+        .bearer_auth(token.to_owned())
+        .send()
+        .into_diagnostic()?
+        .error_for_status()
+        .into_diagnostic()?;
+
+    println!("Run replayed, follow it with `function runs`");
+
+    Ok(())
+}
+
+pub(super) fn discard(
+    token: &str,
+    runtime_url: &str,
+    scope_name: &str,
+    function_id: &str,
+    dead_letter_id: &str,
+) -> miette::Result<()> {
+    let client = reqwest::blocking::Client::new();
+
+    client
+        .delete(format!(
+            "{}/{dead_letter_id}",
+            dead_letters_url(runtime_url, scope_name, function_id)
+        ))
+        .bearer_auth(token.to_owned())
+        .send()
+        .into_diagnostic()?
+        .error_for_status()
+        .into_diagnostic()?;
+
+    println!("Dead letter discarded");
+
+    Ok(())
+}
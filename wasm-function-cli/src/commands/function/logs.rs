@@ -0,0 +1,148 @@
+use std::io::{BufRead, BufReader};
+
+use miette::IntoDiagnostic;
+use serde::{Deserialize, Serialize};
+
+use super::LogLevel;
+
+#[derive(Deserialize)]
+struct LogField {
+    key: String,
+    value: String,
+}
+
+#[derive(Deserialize)]
+pub(super) struct LogEntry {
+    request_id: Option<String>,
+    source: String,
+    level: String,
+    message: String,
+    fields: Vec<LogField>,
+    timestamp: String,
+}
+
+impl std::fmt::Display for LogEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {:<5} [{}]",
+            self.timestamp,
+            self.level.to_uppercase(),
+            self.source
+        )?;
+        if let Some(request_id) = &self.request_id {
+            write!(f, " ({request_id})")?;
+        }
+        write!(f, " {}", self.message)?;
+        for field in &self.fields {
+            write!(f, " {}={}", field.key, field.value)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+struct FunctionLogsResponse {
+    logs: Vec<LogEntry>,
+}
+
+#[derive(Serialize)]
+struct LogsQuery<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    limit: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    level: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    since: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    until: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct LogStreamQuery<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    level: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    after: Option<&'a str>,
+}
+
+pub(super) struct LogsOptions<'a> {
+    pub level: Option<&'a LogLevel>,
+    pub request_id: Option<&'a str>,
+    pub since: Option<&'a str>,
+    pub until: Option<&'a str>,
+    pub limit: Option<u64>,
+    pub follow: bool,
+}
+
+pub(super) fn execute(
+    token: &str,
+    runtime_url: &str,
+    scope_name: &str,
+    function_id: &str,
+    options: LogsOptions,
+) -> miette::Result<()> {
+    // Streaming keeps the connection open, so requests must not time out
+    let client = reqwest::blocking::Client::builder()
+        .timeout(None)
+        .build()
+        .into_diagnostic()?;
+    let logs_url = format!("{runtime_url}/api/scope/{scope_name}/function/{function_id}/logs");
+    let level = options.level.map(LogLevel::as_str);
+
+    let response = client
+        .get(&logs_url)
+        .bearer_auth(token.to_owned())
+        .query(&LogsQuery {
+            limit: options.limit,
+            request_id: options.request_id,
+            level,
+            since: options.since,
+            until: options.until,
+        })
+        .send()
+        .into_diagnostic()?
+        .error_for_status()
+        .into_diagnostic()?
+        .json::<FunctionLogsResponse>()
+        .into_diagnostic()?;
+
+    for entry in &response.logs {
+        println!("{entry}");
+    }
+
+    if !options.follow {
+        return Ok(());
+    }
+
+    let stream = client
+        .get(format!("{logs_url}/stream"))
+        .bearer_auth(token.to_owned())
+        .query(&LogStreamQuery {
+            request_id: options.request_id,
+            level,
+            after: response.logs.last().map(|entry| entry.timestamp.as_str()),
+        })
+        .send()
+        .into_diagnostic()?
+        .error_for_status()
+        .into_diagnostic()?;
+
+    // Each server-sent event carries one log entry as JSON in its data line
+    for line in BufReader::new(stream).lines() {
+        let line = line.into_diagnostic()?;
+        let Some(data) = line.strip_prefix("data:") else {
+            continue;
+        };
+        match serde_json::from_str::<LogEntry>(data.trim()) {
+            Ok(entry) => println!("{entry}"),
+            Err(err) => eprintln!("Skipping malformed log event: {err}"),
+        }
+    }
+
+    Ok(())
+}
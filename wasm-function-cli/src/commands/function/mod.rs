@@ -2,15 +2,77 @@ use clap::{Parser, Subcommand, ValueEnum};
 
 use super::{command_context, command_executor, CredentialStoreTrait};
 
+mod dead_letters;
 mod delete;
 mod list;
+mod logs;
+mod revisions;
+mod rollback;
+mod rollout;
+mod run;
+mod runs;
+mod schedule;
+mod unpin;
 
 #[derive(Subcommand)]
 pub(super) enum FunctionCommand {
+    /// Inspect, replay or discard the runs of a scheduled function that failed for good
+    #[command(subcommand)]
+    DeadLetters(DeadLetterFunctionCommand),
     /// Delete a function by name
     Delete(DeleteFunctionCommand),
     /// List all functions of a scope
     List(ListFunctionCommand),
+    /// Show the logs of a function
+    Logs(LogsFunctionCommand),
+    /// List the deployed revisions of a function
+    Revisions(FunctionRevisionsCommand),
+    /// Activate a previous revision of a function
+    Rollback(RollbackFunctionCommand),
+    /// Split the traffic of an HTTP function with a canary revision
+    #[command(subcommand)]
+    Rollout(RolloutFunctionCommand),
+    /// Run a scheduled function right away and show the outcome with its logs
+    Run(RunFunctionCommand),
+    /// List the runs of a scheduled function or show a single run with its logs
+    Runs(FunctionRunsCommand),
+    /// Preview the next fire times of a scheduled function
+    Schedule(FunctionScheduleCommand),
+    /// Let the next deploy of a pinned function become active again
+    Unpin(UnpinFunctionCommand),
+}
+
+#[derive(Subcommand)]
+pub(super) enum DeadLetterFunctionCommand {
+    /// List the parked runs of a scheduled function
+    List(DeadLetterListCommand),
+    /// Run the function again and remove the dead letter
+    Replay(DeadLetterTargetCommand),
+    /// Remove the dead letter without running the function
+    Discard(DeadLetterTargetCommand),
+}
+
+#[derive(Parser)]
+pub(super) struct DeadLetterListCommand {
+    /// Id of the scheduled function
+    #[clap(short, long)]
+    id: String,
+    /// Name of the scope the function belongs to
+    #[clap(short, long)]
+    scope_name: String,
+}
+
+#[derive(Parser)]
+pub(super) struct DeadLetterTargetCommand {
+    /// Id of the scheduled function
+    #[clap(short, long)]
+    id: String,
+    /// Name of the scope the function belongs to
+    #[clap(short, long)]
+    scope_name: String,
+    /// Id of the dead letter
+    #[clap(short, long)]
+    dead_letter_id: String,
 }
 
 #[derive(Parser)]
@@ -39,6 +101,189 @@ pub(super) struct ListFunctionCommand {
     scope_name: String,
 }
 
+#[derive(Clone, ValueEnum)]
+pub(super) enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl LogLevel {
+    fn as_str(&self) -> &'static str {
+        match self {
+            LogLevel::Trace => "trace",
+            LogLevel::Debug => "debug",
+            LogLevel::Info => "info",
+            LogLevel::Warn => "warn",
+            LogLevel::Error => "error",
+        }
+    }
+}
+
+#[derive(Parser)]
+pub(super) struct LogsFunctionCommand {
+    /// Id of the function to show the logs of
+    #[clap(short, long)]
+    id: String,
+    /// Name of the scope the function belongs to
+    #[clap(short, long)]
+    scope_name: String,
+    /// Only show lines of this level or more severe
+    #[clap(short, long)]
+    level: Option<LogLevel>,
+    /// Only show lines of the invocation with this request id
+    #[clap(short, long)]
+    request_id: Option<String>,
+    /// Only show lines written at or after this time (RFC 3339)
+    #[clap(long)]
+    since: Option<String>,
+    /// Only show lines written at or before this time (RFC 3339)
+    #[clap(long, conflicts_with = "follow")]
+    until: Option<String>,
+    /// Maximum number of lines to show initially
+    #[clap(short = 'n', long)]
+    limit: Option<u64>,
+    /// Keep streaming new lines as they are written
+    #[clap(short, long)]
+    follow: bool,
+}
+
+#[derive(Parser)]
+pub(super) struct FunctionRevisionsCommand {
+    /// Id of the function to list the revisions of
+    #[clap(short, long)]
+    id: String,
+    /// Name of the scope the function belongs to
+    #[clap(short, long)]
+    scope_name: String,
+}
+
+#[derive(Parser)]
+pub(super) struct RollbackFunctionCommand {
+    /// Id of the function to roll back
+    #[clap(short, long)]
+    id: String,
+    /// Name of the scope the function belongs to
+    #[clap(short, long)]
+    scope_name: String,
+    /// Revision to activate
+    #[clap(short, long)]
+    revision: i32,
+    /// Keep the revision active when the function is deployed again
+    #[clap(short, long)]
+    pin: bool,
+}
+
+#[derive(Subcommand)]
+pub(super) enum RolloutFunctionCommand {
+    /// Show the rollout of a function and the canary requests observed so far
+    Show(RolloutTargetCommand),
+    /// Route a share of the traffic to a canary revision
+    Set(SetRolloutCommand),
+    /// Activate the canary revision for all traffic
+    Promote(RolloutTargetCommand),
+    /// Stop the rollout and route all traffic to the active revision
+    Abort(RolloutTargetCommand),
+}
+
+#[derive(Parser)]
+pub(super) struct RolloutTargetCommand {
+    /// Id of the rolled out function
+    #[clap(short, long)]
+    id: String,
+    /// Name of the scope the function belongs to
+    #[clap(short, long)]
+    scope_name: String,
+}
+
+#[derive(Parser)]
+pub(super) struct SetRolloutCommand {
+    /// Id of the function to roll out
+    #[clap(short, long)]
+    id: String,
+    /// Name of the scope the function belongs to
+    #[clap(short, long)]
+    scope_name: String,
+    /// Revision receiving a share of the traffic
+    #[clap(short, long)]
+    revision: i32,
+    /// Percentage of the traffic routed to the revision
+    #[clap(short, long, value_parser = clap::value_parser!(u8).range(0..=100))]
+    weight: u8,
+    /// Route requests with the same value of this header to the same revision
+    #[clap(long)]
+    sticky_header: Option<String>,
+    /// Route requests with the same value of this cookie to the same revision
+    #[clap(long)]
+    sticky_cookie: Option<String>,
+    /// Abort the rollout once the error rate of the canary exceeds this percentage
+    #[clap(short, long)]
+    error_threshold: Option<f64>,
+    /// Canary requests observed before the error rate is evaluated
+    #[clap(long)]
+    min_requests: Option<u32>,
+    /// Promote the canary after this many requests below the error threshold
+    #[clap(long)]
+    promote_after: Option<u32>,
+}
+
+#[derive(Parser)]
+pub(super) struct RunFunctionCommand {
+    /// Id of the scheduled function to run
+    #[clap(short, long)]
+    id: String,
+    /// Name of the scope the function belongs to
+    #[clap(short, long)]
+    scope_name: String,
+    /// Run this revision instead of the active one, e.g. to try it before it goes live
+    #[clap(short, long)]
+    revision: Option<i32>,
+    /// Don't wait for the run to finish
+    #[clap(short, long)]
+    detach: bool,
+}
+
+#[derive(Parser)]
+pub(super) struct FunctionRunsCommand {
+    /// Id of the scheduled function to list the runs of
+    #[clap(short, long)]
+    id: String,
+    /// Name of the scope the function belongs to
+    #[clap(short, long)]
+    scope_name: String,
+    /// Show this run together with its logs
+    #[clap(short, long)]
+    run_id: Option<String>,
+    /// Maximum number of runs to show
+    #[clap(short = 'n', long, conflicts_with = "run_id")]
+    limit: Option<u64>,
+}
+
+#[derive(Parser)]
+pub(super) struct FunctionScheduleCommand {
+    /// Id of the scheduled function to preview
+    #[clap(short, long)]
+    id: String,
+    /// Name of the scope the function belongs to
+    #[clap(short, long)]
+    scope_name: String,
+    /// Number of fire times to show
+    #[clap(short = 'n', long)]
+    count: Option<usize>,
+}
+
+#[derive(Parser)]
+pub(super) struct UnpinFunctionCommand {
+    /// Id of the function to unpin
+    #[clap(short, long)]
+    id: String,
+    /// Name of the scope the function belongs to
+    #[clap(short, long)]
+    scope_name: String,
+}
+
 impl<TCredStore: CredentialStoreTrait> command_executor::CommandExecutorTrait<TCredStore>
     for FunctionCommand
 {
@@ -47,6 +292,32 @@ impl<TCredStore: CredentialStoreTrait> command_executor::CommandExecutorTrait<TC
         let function_runtime_url = &ctx.config.function_runtime_url;
 
         match self {
+            FunctionCommand::DeadLetters(DeadLetterFunctionCommand::List(list_command)) => {
+                dead_letters::list(
+                    &active_token,
+                    function_runtime_url,
+                    &list_command.scope_name,
+                    &list_command.id,
+                )
+            }
+            FunctionCommand::DeadLetters(DeadLetterFunctionCommand::Replay(replay_command)) => {
+                dead_letters::replay(
+                    &active_token,
+                    function_runtime_url,
+                    &replay_command.scope_name,
+                    &replay_command.id,
+                    &replay_command.dead_letter_id,
+                )
+            }
+            FunctionCommand::DeadLetters(DeadLetterFunctionCommand::Discard(discard_command)) => {
+                dead_letters::discard(
+                    &active_token,
+                    function_runtime_url,
+                    &discard_command.scope_name,
+                    &discard_command.id,
+                    &discard_command.dead_letter_id,
+                )
+            }
             FunctionCommand::Delete(delete_command) => delete::execute(
                 &active_token,
                 function_runtime_url,
@@ -59,6 +330,108 @@ impl<TCredStore: CredentialStoreTrait> command_executor::CommandExecutorTrait<TC
                 function_runtime_url,
                 &list_command.scope_name,
             ),
+            FunctionCommand::Logs(logs_command) => logs::execute(
+                &active_token,
+                function_runtime_url,
+                &logs_command.scope_name,
+                &logs_command.id,
+                logs::LogsOptions {
+                    level: logs_command.level.as_ref(),
+                    request_id: logs_command.request_id.as_deref(),
+                    since: logs_command.since.as_deref(),
+                    until: logs_command.until.as_deref(),
+                    limit: logs_command.limit,
+                    follow: logs_command.follow,
+                },
+            ),
+            FunctionCommand::Revisions(revisions_command) => revisions::execute(
+                &active_token,
+                function_runtime_url,
+                &revisions_command.scope_name,
+                &revisions_command.id,
+            ),
+            FunctionCommand::Rollback(rollback_command) => rollback::execute(
+                &active_token,
+                function_runtime_url,
+                &rollback_command.scope_name,
+                &rollback_command.id,
+                rollback_command.revision,
+                rollback_command.pin,
+            ),
+            FunctionCommand::Rollout(RolloutFunctionCommand::Show(show_command)) => rollout::show(
+                &active_token,
+                function_runtime_url,
+                &show_command.scope_name,
+                &show_command.id,
+            ),
+            FunctionCommand::Rollout(RolloutFunctionCommand::Set(set_command)) => rollout::set(
+                &active_token,
+                function_runtime_url,
+                &set_command.scope_name,
+                &set_command.id,
+                rollout::RolloutOptions {
+                    revision: set_command.revision,
+                    weight: set_command.weight,
+                    sticky_header: set_command.sticky_header.as_deref(),
+                    sticky_cookie: set_command.sticky_cookie.as_deref(),
+                    error_threshold: set_command.error_threshold,
+                    min_requests: set_command.min_requests,
+                    promote_after: set_command.promote_after,
+                },
+            ),
+            FunctionCommand::Rollout(RolloutFunctionCommand::Promote(promote_command)) => {
+                rollout::promote(
+                    &active_token,
+                    function_runtime_url,
+                    &promote_command.scope_name,
+                    &promote_command.id,
+                )
+            }
+            FunctionCommand::Rollout(RolloutFunctionCommand::Abort(abort_command)) => {
+                rollout::abort(
+                    &active_token,
+                    function_runtime_url,
+                    &abort_command.scope_name,
+                    &abort_command.id,
+                )
+            }
+            FunctionCommand::Run(run_command) => run::execute(
+                &active_token,
+                function_runtime_url,
+                &run_command.scope_name,
+                &run_command.id,
+                run_command.revision,
+                run_command.detach,
+            ),
+            FunctionCommand::Runs(runs_command) => match &runs_command.run_id {
+                Some(run_id) => runs::show(
+                    &active_token,
+                    function_runtime_url,
+                    &runs_command.scope_name,
+                    &runs_command.id,
+                    run_id,
+                ),
+                None => runs::list(
+                    &active_token,
+                    function_runtime_url,
+                    &runs_command.scope_name,
+                    &runs_command.id,
+                    runs_command.limit,
+                ),
+            },
+            FunctionCommand::Schedule(schedule_command) => schedule::execute(
+                &active_token,
+                function_runtime_url,
+                &schedule_command.scope_name,
+                &schedule_command.id,
+                schedule_command.count,
+            ),
+            FunctionCommand::Unpin(unpin_command) => unpin::execute(
+                &active_token,
+                function_runtime_url,
+                &unpin_command.scope_name,
+                &unpin_command.id,
+            ),
         }
     }
 }
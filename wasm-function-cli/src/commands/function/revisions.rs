@@ -0,0 +1,75 @@
+use miette::IntoDiagnostic;
+use serde::Deserialize;
+use tabled::{Table, Tabled};
+
+#[derive(Deserialize)]
+struct Revision {
+    revision: i32,
+    content_hash: String,
+    deployed_by: Option<String>,
+    created_at: String,
+    active: bool,
+}
+
+#[derive(Deserialize)]
+struct RevisionListResponse {
+    pinned: bool,
+    revisions: Vec<Revision>,
+}
+
+#[derive(Tabled)]
+struct OutputTableRow {
+    revision: i32,
+    active: String,
+    created_at: String,
+    deployed_by: String,
+    content_hash: String,
+}
+
+impl From<RevisionListResponse> for Vec<OutputTableRow> {
+    fn from(response: RevisionListResponse) -> Self {
+        response
+            .revisions
+            .into_iter()
+            .map(|revision| OutputTableRow {
+                revision: revision.revision,
+                active: match (revision.active, response.pinned) {
+                    (true, true) => "pinned".to_owned(),
+                    (true, false) => "active".to_owned(),
+                    (false, _) => String::new(),
+                },
+                created_at: revision.created_at,
+                deployed_by: revision.deployed_by.unwrap_or_default(),
+                content_hash: revision.content_hash.chars().take(12).collect(),
+            })
+            .collect()
+    }
+}
+
+pub(super) fn execute(
+    token: &str,
+    runtime_url: &str,
+    scope_name: &str,
+    function_id: &str,
+) -> miette::Result<()> {
+    let client = reqwest::blocking::Client::new();
+
+    let response = client
+        .get(format!(
+            "{runtime_url}/api/scope/{scope_name}/function/{function_id}/revisions"
+        ))
+        .bearer_auth(token.to_owned())
+        .send()
+        .into_diagnostic()?
+        .error_for_status()
+        .into_diagnostic()?
+        .json::<RevisionListResponse>()
+        .expect("Failed to parse response");
+
+    let rows: Vec<OutputTableRow> = response.into();
+
+    let table = Table::new(rows);
+
+    println!("{table}");
+    Ok(())
+}
@@ -0,0 +1,31 @@
+use miette::IntoDiagnostic;
+
+pub(super) fn execute(
+    token: &str,
+    runtime_url: &str,
+    scope_name: &str,
+    function_id: &str,
+    revision: i32,
+    pin: bool,
+) -> miette::Result<()> {
+    let client = reqwest::blocking::Client::new();
+
+    client
+        .post(format!(
+            "{runtime_url}/api/scope/{scope_name}/function/{function_id}/rollback"
+        ))
+        .bearer_auth(token.to_owned())
+        .json(&serde_json::json!({ "revision": revision, "pin": pin }))
+        .send()
+        .into_diagnostic()?
+        .error_for_status()
+        .into_diagnostic()?;
+
+    if pin {
+        println!("Function rolled back and pinned to revision {revision}");
+    } else {
+        println!("Function rolled back to revision {revision}");
+    }
+
+    Ok(())
+}
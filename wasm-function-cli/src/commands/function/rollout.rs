@@ -0,0 +1,184 @@
+use miette::IntoDiagnostic;
+use serde::{Deserialize, Serialize};
+use tabled::{Table, Tabled};
+
+#[derive(Serialize)]
+pub(super) struct RolloutOptions<'a> {
+    pub revision: i32,
+    pub weight: u8,
+    pub sticky_header: Option<&'a str>,
+    pub sticky_cookie: Option<&'a str>,
+    pub error_threshold: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_requests: Option<u32>,
+    pub promote_after: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct RolloutResponse {
+    revision: i32,
+    weight: u8,
+    sticky_header: Option<String>,
+    sticky_cookie: Option<String>,
+    error_threshold: Option<f64>,
+    min_requests: u32,
+    promote_after: Option<u32>,
+    requests: u32,
+    failures: u32,
+}
+
+#[derive(Deserialize)]
+struct PromoteResponse {
+    revision: i32,
+}
+
+#[derive(Tabled)]
+struct OutputTableRow {
+    revision: i32,
+    weight: String,
+    sticky: String,
+    error_threshold: String,
+    min_requests: u32,
+    promote_after: String,
+    requests: u32,
+    failures: u32,
+}
+
+impl From<RolloutResponse> for OutputTableRow {
+    fn from(rollout: RolloutResponse) -> Self {
+        let sticky = match (rollout.sticky_header, rollout.sticky_cookie) {
+            (Some(header), Some(cookie)) => format!("header {header}, cookie {cookie}"),
+            (Some(header), None) => format!("header {header}"),
+            (None, Some(cookie)) => format!("cookie {cookie}"),
+            (None, None) => String::new(),
+        };
+
+        Self {
+            revision: rollout.revision,
+            weight: format!("{}%", rollout.weight),
+            sticky,
+            error_threshold: rollout
+                .error_threshold
+                .map(|threshold| format!("{threshold}%"))
+                .unwrap_or_default(),
+            min_requests: rollout.min_requests,
+            promote_after: rollout
+                .promote_after
+                .map(|promote_after| promote_after.to_string())
+                .unwrap_or_default(),
+            requests: rollout.requests,
+            failures: rollout.failures,
+        }
+    }
+}
+
+fn rollout_url(runtime_url: &str, scope_name: &str, function_id: &str) -> String {
+    format!("{runtime_url}/api/scope/{scope_name}/function/{function_id}/rollout")
+}
+
+pub(super) fn show(
+    token: &str,
+    runtime_url: &str,
+    scope_name: &str,
+    function_id: &str,
+) -> miette::Result<()> {
+    let client = reqwest::blocking::Client::new();
+
+    let response = client
+        .get(rollout_url(runtime_url, scope_name, function_id))
+        .bearer_auth(token.to_owned())
+        .send()
+        .into_diagnostic()?;
+
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        println!("Function has no rollout");
+        return Ok(());
+    }
+
+    let rollout = response
+        .error_for_status()
+        .into_diagnostic()?
+        .json::<RolloutResponse>()
+        .expect("Failed to parse response");
+
+    let table = Table::new(vec![OutputTableRow::from(rollout)]);
+
+    println!("{table}");
+    Ok(())
+}
+
+pub(super) fn set(
+    token: &str,
+    runtime_url: &str,
+    scope_name: &str,
+    function_id: &str,
+    options: RolloutOptions,
+) -> miette::Result<()> {
+    let client = reqwest::blocking::Client::new();
+
+    client
+        .put(rollout_url(runtime_url, scope_name, function_id))
+        .bearer_auth(token.to_owned())
+        .json(&options)
+        .send()
+        .into_diagnostic()?
+        .error_for_status()
+        .into_diagnostic()?;
+
+    println!(
+        "Revision {} receives {}% of the traffic",
+        options.revision, options.weight
+    );
+
+    Ok(())
+}
+
+pub(super) fn promote(
+    token: &str,
+    runtime_url: &str,
+    scope_name: &str,
+    function_id: &str,
+) -> miette::Result<()> {
+    let client = reqwest::blocking::Client::new();
+
+    let response = client
+        .post(format!(
+            "{}/promote",
+            rollout_url(runtime_url, scope_name, function_id)
+        ))
+        .bearer_auth(token.to_owned())
+        .send()
+        .into_diagnostic()?
+        .error_for_status()
+        .into_diagnostic()?
+        .json::<PromoteResponse>()
+        .expect("Failed to parse response");
+
+    println!(
+        "Revision {} promoted, it receives all traffic",
+        response.revision
+    );
+
+    Ok(())
+}
+
+pub(super) fn abort(
+    token: &str,
+    runtime_url: &str,
+    scope_name: &str,
+    function_id: &str,
+) -> miette::Result<()> {
+    let client = reqwest::blocking::Client::new();
+
+    client
+        .delete(rollout_url(runtime_url, scope_name, function_id))
+        .bearer_auth(token.to_owned())
+        .send()
+        .into_diagnostic()?
+        .error_for_status()
+        .into_diagnostic()?;
+
+    println!("Rollout aborted, the active revision receives all traffic");
+
+    Ok(())
+}
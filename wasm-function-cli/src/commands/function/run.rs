@@ -0,0 +1,60 @@
+use miette::IntoDiagnostic;
+use serde::Deserialize;
+
+use super::runs::{print_run, RunResponse};
+
+#[derive(Deserialize)]
+struct RunStartedResponse {
+    run_id: String,
+}
+
+pub(super) fn execute(
+    token: &str,
+    runtime_url: &str,
+    scope_name: &str,
+    function_id: &str,
+    revision: Option<i32>,
+    detach: bool,
+) -> miette::Result<()> {
+    // Waiting for the run takes as long as the function runs
+    let client = reqwest::blocking::Client::builder()
+        .timeout(None)
+        .build()
+        .into_diagnostic()?;
+
+    let mut request = client
+        .post(format!(
+            "{runtime_url}/api/scope/{scope_name}/function/scheduled/{function_id}/run"
+        ))
+        .bearer_auth(token.to_owned())
+        .query(&[("wait", !detach)]);
+    if let Some(revision) = revision {
+        request = request.query(&[("revision", revision)]);
+    }
+
+    let response = request.send().into_diagnostic()?;
+
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        println!("Function or revision not found");
+        return Ok(());
+    }
+
+    let response = response.error_for_status().into_diagnostic()?;
+    if detach {
+        let started = response
+            .json::<RunStartedResponse>()
+            .expect("Failed to parse response");
+        println!(
+            "Run {} started, follow it with `function runs --run-id {}`",
+            started.run_id, started.run_id
+        );
+    } else {
+        print_run(
+            response
+                .json::<RunResponse>()
+                .expect("Failed to parse response"),
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,139 @@
+use miette::IntoDiagnostic;
+use serde::Deserialize;
+use tabled::{Table, Tabled};
+
+use super::logs::LogEntry;
+
+#[derive(Deserialize)]
+struct Run {
+    id: String,
+    revision: Option<i32>,
+    attempt: u32,
+    started_at: String,
+    duration_ms: i64,
+    outcome: String,
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RunListResponse {
+    runs: Vec<Run>,
+}
+
+#[derive(Deserialize)]
+pub(super) struct RunResponse {
+    #[serde(flatten)]
+    run: Run,
+    logs: Vec<LogEntry>,
+}
+
+#[derive(Tabled)]
+struct OutputTableRow {
+    run_id: String,
+    revision: String,
+    attempt: u32,
+    started_at: String,
+    duration: String,
+    outcome: String,
+    error: String,
+}
+
+impl From<Run> for OutputTableRow {
+    fn from(run: Run) -> Self {
+        Self {
+            run_id: run.id,
+            revision: run
+                .revision
+                .map(|revision| revision.to_string())
+                .unwrap_or_default(),
+            attempt: run.attempt,
+            started_at: run.started_at,
+            duration: format!("{}ms", run.duration_ms),
+            outcome: run.outcome,
+            error: run.error.unwrap_or_default(),
+        }
+    }
+}
+
+fn runs_url(runtime_url: &str, scope_name: &str, function_id: &str) -> String {
+    format!("{runtime_url}/api/scope/{scope_name}/function/{function_id}/runs")
+}
+
+pub(super) fn list(
+    token: &str,
+    runtime_url: &str,
+    scope_name: &str,
+    function_id: &str,
+    limit: Option<u64>,
+) -> miette::Result<()> {
+    let client = reqwest::blocking::Client::new();
+
+    let mut request = client
+        .get(runs_url(runtime_url, scope_name, function_id))
+        .bearer_auth(token.to_owned());
+    if let Some(limit) = limit {
+        request = request.query(&[("limit", limit)]);
+    }
+
+    let response = request
+        .send()
+        .into_diagnostic()?
+        .error_for_status()
+        .into_diagnostic()?
+        .json::<RunListResponse>()
+        .expect("Failed to parse response");
+
+    let rows: Vec<OutputTableRow> = response
+        .runs
+        .into_iter()
+        .map(OutputTableRow::from)
+        .collect();
+
+    let table = Table::new(rows);
+
+    println!("{table}");
+    Ok(())
+}
+
+pub(super) fn show(
+    token: &str,
+    runtime_url: &str,
+    scope_name: &str,
+    function_id: &str,
+    run_id: &str,
+) -> miette::Result<()> {
+    let client = reqwest::blocking::Client::new();
+
+    let response = client
+        .get(format!(
+            "{}/{run_id}",
+            runs_url(runtime_url, scope_name, function_id)
+        ))
+        .bearer_auth(token.to_owned())
+        .send()
+        .into_diagnostic()?;
+
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        println!("Run not found, it may have been removed from the history");
+        return Ok(());
+    }
+
+    let response = response
+        .error_for_status()
+        .into_diagnostic()?
+        .json::<RunResponse>()
+        .expect("Failed to parse response");
+
+    print_run(response);
+    Ok(())
+}
+
+/// Prints the run followed by its logs
+pub(super) fn print_run(response: RunResponse) {
+    let table = Table::new(vec![OutputTableRow::from(response.run)]);
+
+    println!("{table}");
+    for entry in response.logs {
+        println!("{entry}");
+    }
+}
@@ -0,0 +1,47 @@
+use miette::IntoDiagnostic;
+use serde::Deserialize;
+use tabled::{Table, Tabled};
+
+#[derive(Deserialize, Tabled)]
+struct FireTime {
+    local: String,
+    utc: String,
+}
+
+#[derive(Deserialize)]
+struct ScheduleResponse {
+    cron: String,
+    timezone: String,
+    fire_times: Vec<FireTime>,
+}
+
+pub(super) fn execute(
+    token: &str,
+    runtime_url: &str,
+    scope_name: &str,
+    function_id: &str,
+    count: Option<usize>,
+) -> miette::Result<()> {
+    let client = reqwest::blocking::Client::new();
+
+    let mut request = client
+        .get(format!(
+            "{runtime_url}/api/scope/{scope_name}/function/{function_id}/schedule"
+        ))
+        .bearer_auth(token.to_owned());
+    if let Some(count) = count {
+        request = request.query(&[("count", count)]);
+    }
+
+    let response = request
+        .send()
+        .into_diagnostic()?
+        .error_for_status()
+        .into_diagnostic()?
+        .json::<ScheduleResponse>()
+        .expect("Failed to parse response");
+
+    println!("{} ({})", response.cron, response.timezone);
+    println!("{}", Table::new(response.fire_times));
+    Ok(())
+}
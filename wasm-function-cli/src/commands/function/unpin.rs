@@ -0,0 +1,24 @@
+use miette::IntoDiagnostic;
+
+pub(super) fn execute(
+    token: &str,
+    runtime_url: &str,
+    scope_name: &str,
+    function_id: &str,
+) -> miette::Result<()> {
+    let client = reqwest::blocking::Client::new();
+
+    client
+        .delete(format!(
+            "{runtime_url}/api/scope/{scope_name}/function/{function_id}/pin"
+        ))
+        .bearer_auth(token.to_owned())
+        .send()
+        .into_diagnostic()?
+        .error_for_status()
+        .into_diagnostic()?;
+
+    println!("Function unpinned, the next deploy becomes active");
+
+    Ok(())
+}
@@ -8,11 +8,13 @@ mod function;
 mod login;
 mod logout;
 mod scope;
+mod secret;
 mod variable;
 
 pub(crate) use command_context::CommandContext;
 use function::FunctionCommand;
 use scope::ScopeCommand;
+use secret::SecretCommand;
 use variable::VariableCommand;
 
 use crate::cred_store::CredentialStoreTrait;
@@ -38,6 +40,9 @@ enum Command {
     /// Commands to manage scopes
     #[clap(subcommand)]
     Scope(ScopeCommand),
+    /// Commands to manage secrets of a scope
+    #[clap(subcommand)]
+    Secret(SecretCommand),
     /// Commands to manage variables of a scope
     #[clap(subcommand)]
     Variable(VariableCommand),
@@ -79,6 +84,7 @@ impl<TCredStore: CredentialStoreTrait> command_executor::CommandExecutorTrait<TC
             Command::Deploy(deploy_command) => deploy_command.execute(ctx),
             Command::Function(function_command) => function_command.execute(ctx),
             Command::Scope(scope_command) => scope_command.execute(ctx),
+            Command::Secret(secret_command) => secret_command.execute(ctx),
             Command::Variable(variable_command) => variable_command.execute(ctx),
         }
     }
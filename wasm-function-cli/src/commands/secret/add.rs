@@ -0,0 +1,36 @@
+use miette::IntoDiagnostic;
+
+pub(super) fn execute(
+    active_token: &str,
+    function_runtime_url: &str,
+    scope_name: &str,
+    name: &str,
+    value: Option<&str>,
+) -> miette::Result<()> {
+    // Reading the value from stdin keeps it out of the shell history
+    let value = match value {
+        Some(value) => value.to_owned(),
+        None => {
+            let mut value = String::new();
+            std::io::stdin().read_line(&mut value).into_diagnostic()?;
+            value.trim_end_matches(['\r', '\n']).to_owned()
+        }
+    };
+
+    let client = reqwest::blocking::Client::new();
+
+    client
+        .post(format!(
+            "{function_runtime_url}/api/scope/{scope_name}/secret"
+        ))
+        .bearer_auth(active_token.to_owned())
+        .json(&serde_json::json!({ "name": name, "value": value }))
+        .send()
+        .into_diagnostic()?
+        .error_for_status()
+        .into_diagnostic()?;
+
+    println!("Secret added successfully");
+
+    Ok(())
+}
@@ -0,0 +1,60 @@
+use miette::IntoDiagnostic;
+use serde::Deserialize;
+use tabled::{Table, Tabled};
+
+#[derive(Deserialize)]
+struct Secret {
+    uuid: String,
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct SecretListResponse {
+    secrets: Vec<Secret>,
+}
+
+#[derive(Tabled)]
+struct OutputTableRow {
+    name: String,
+    uuid: String,
+}
+
+impl From<SecretListResponse> for Vec<OutputTableRow> {
+    fn from(response: SecretListResponse) -> Self {
+        response
+            .secrets
+            .into_iter()
+            .map(|secret| OutputTableRow {
+                name: secret.name,
+                uuid: secret.uuid,
+            })
+            .collect()
+    }
+}
+
+pub(super) fn execute(
+    active_token: &str,
+    function_runtime_url: &str,
+    scope_name: &str,
+) -> miette::Result<()> {
+    let client = reqwest::blocking::Client::new();
+
+    let response = client
+        .get(format!(
+            "{function_runtime_url}/api/scope/{scope_name}/secret"
+        ))
+        .bearer_auth(active_token.to_owned())
+        .send()
+        .into_diagnostic()?
+        .error_for_status()
+        .into_diagnostic()?
+        .json::<SecretListResponse>()
+        .expect("Failed to parse response");
+
+    let rows: Vec<OutputTableRow> = response.into();
+
+    let table = Table::new(rows);
+    println!("{table}");
+
+    Ok(())
+}
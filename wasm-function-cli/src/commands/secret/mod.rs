@@ -0,0 +1,78 @@
+use clap::{Parser, Subcommand};
+
+use super::{command_context, command_executor, CredentialStoreTrait};
+
+mod add;
+mod delete;
+mod list;
+
+#[derive(Subcommand)]
+pub(super) enum SecretCommand {
+    /// Add a secret to a scope, an existing secret with the same name is overwritten
+    Add(AddSecretCommand),
+    /// Delete a secret from a scope
+    Delete(DeleteSecretCommand),
+    /// List the names of all secrets of a scope
+    List(ListSecretCommand),
+}
+
+#[derive(Parser)]
+pub(super) struct AddSecretCommand {
+    /// Name of the scope to add the secret to
+    #[clap(short, long)]
+    scope_name: String,
+    /// Name of the secret to add, exposed to functions as `SECRET_<name>`
+    #[clap(short, long)]
+    name: String,
+    /// Value of the secret to add
+    /// If not provided, the value is read from stdin
+    #[clap(short, long)]
+    value: Option<String>,
+}
+
+#[derive(Parser)]
+pub(super) struct DeleteSecretCommand {
+    /// Name of the scope the secret belongs to
+    #[clap(short, long)]
+    scope_name: String,
+    /// Unique identifier of the secret to delete
+    #[clap(short, long)]
+    id: String,
+}
+
+#[derive(Parser)]
+pub(super) struct ListSecretCommand {
+    /// Name of the scope the secrets belong to
+    #[clap(short, long)]
+    scope_name: String,
+}
+
+impl<TCredStore: CredentialStoreTrait> command_executor::CommandExecutorTrait<TCredStore>
+    for SecretCommand
+{
+    fn execute(&self, ctx: &mut command_context::CommandContext<TCredStore>) -> miette::Result<()> {
+        let active_token = crate::auth::token_refresh::get_active_token(ctx)?;
+        let function_runtime_url = &ctx.config.function_runtime_url;
+
+        match self {
+            SecretCommand::Add(add_command) => add::execute(
+                &active_token,
+                function_runtime_url,
+                &add_command.scope_name,
+                &add_command.name,
+                add_command.value.as_deref(),
+            ),
+            SecretCommand::Delete(delete_command) => delete::execute(
+                &active_token,
+                function_runtime_url,
+                &delete_command.scope_name,
+                &delete_command.id,
+            ),
+            SecretCommand::List(list_command) => list::execute(
+                &active_token,
+                function_runtime_url,
+                &list_command.scope_name,
+            ),
+        }
+    }
+}
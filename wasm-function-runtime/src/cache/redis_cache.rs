@@ -47,3 +47,163 @@ impl super::CacheBackend for RedisCache {
         Ok(())
     }
 }
+
+/// Prefix of all key-value entries of a scope, keeps them apart from cached binaries
+fn key_value_prefix(scope_id: &uuid::Uuid) -> String {
+    format!("kv:{scope_id}:")
+}
+
+/// Escapes glob characters, so the key matches literally in `SCAN MATCH`
+fn escape_pattern(key: &str) -> String {
+    let mut escaped = String::with_capacity(key.len());
+    for c in key.chars() {
+        if matches!(c, '*' | '?' | '[' | ']' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+impl RedisCache {
+    async fn scan_keys(&self, pattern: &str) -> Result<Vec<String>, redis::RedisError> {
+        let mut con = self.client.clone();
+        let mut iter: redis::AsyncIter<String> = con.scan_match(pattern).await?;
+        let mut keys = vec![];
+        while let Some(key) = iter.next_item().await {
+            keys.push(key?);
+        }
+        Ok(keys)
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::keyvalue::KeyValueBackend for RedisCache {
+    async fn get(
+        &self,
+        scope_id: &uuid::Uuid,
+        key: &str,
+    ) -> Result<Option<Vec<u8>>, crate::keyvalue::KeyValueError> {
+        let mut con = self.client.clone();
+        Ok(con
+            .get(format!("{}{key}", key_value_prefix(scope_id)))
+            .await?)
+    }
+
+    async fn set(
+        &self,
+        scope_id: &uuid::Uuid,
+        key: &str,
+        value: Vec<u8>,
+    ) -> Result<(), crate::keyvalue::KeyValueError> {
+        let mut con = self.client.clone();
+        let _: () = con
+            .set(format!("{}{key}", key_value_prefix(scope_id)), value)
+            .await?;
+        Ok(())
+    }
+
+    async fn delete(
+        &self,
+        scope_id: &uuid::Uuid,
+        key: &str,
+    ) -> Result<(), crate::keyvalue::KeyValueError> {
+        let mut con = self.client.clone();
+        let _: () = con
+            .del(format!("{}{key}", key_value_prefix(scope_id)))
+            .await?;
+        Ok(())
+    }
+
+    async fn list_keys(
+        &self,
+        scope_id: &uuid::Uuid,
+        prefix: Option<&str>,
+    ) -> Result<Vec<String>, crate::keyvalue::KeyValueError> {
+        let scope_prefix = key_value_prefix(scope_id);
+        let pattern = format!(
+            "{}{}*",
+            escape_pattern(&scope_prefix),
+            escape_pattern(prefix.unwrap_or_default())
+        );
+
+        let mut keys = self
+            .scan_keys(&pattern)
+            .await?
+            .into_iter()
+            .filter_map(|key| key.strip_prefix(&scope_prefix).map(str::to_string))
+            .collect::<Vec<String>>();
+        keys.sort();
+        Ok(keys)
+    }
+
+    async fn clear(&self, scope_id: &uuid::Uuid) -> Result<(), crate::keyvalue::KeyValueError> {
+        let pattern = format!("{}*", escape_pattern(&key_value_prefix(scope_id)));
+        let keys = self.scan_keys(&pattern).await?;
+        if !keys.is_empty() {
+            let mut con = self.client.clone();
+            let _: () = con.del(keys).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Key of the scheduler lease, holding the holder and fencing token as `{holder}:{token}`
+const SCHEDULER_LEASE_KEY: &str = "scheduler:lease";
+/// Counter the fencing tokens of the scheduler lease are taken from
+const SCHEDULER_LEASE_TOKEN_KEY: &str = "scheduler:lease:token";
+
+/// Extends the lease of its holder or hands out a new token if the lease expired
+const ACQUIRE_LEASE_SCRIPT: &str = r#"
+local current = redis.call('GET', KEYS[1])
+if current then
+    local holder, token = string.match(current, '^(.*):(%d+)$')
+    if holder == ARGV[1] then
+        redis.call('PEXPIRE', KEYS[1], ARGV[2])
+        return tonumber(token)
+    end
+    return false
+end
+local token = redis.call('INCR', KEYS[2])
+redis.call('SET', KEYS[1], ARGV[1] .. ':' .. token, 'PX', ARGV[2])
+return token
+"#;
+
+#[async_trait::async_trait]
+impl crate::scheduler::lease::LeaseBackend for RedisCache {
+    async fn acquire(
+        &self,
+        holder: &str,
+        ttl: std::time::Duration,
+    ) -> Result<Option<u64>, crate::scheduler::lease::LeaseError> {
+        let mut con = self.client.clone();
+        Ok(redis::Script::new(ACQUIRE_LEASE_SCRIPT)
+            .key(SCHEDULER_LEASE_KEY)
+            .key(SCHEDULER_LEASE_TOKEN_KEY)
+            .arg(holder)
+            .arg(u64::try_from(ttl.as_millis()).unwrap_or(u64::MAX).max(1))
+            .invoke_async(&mut con)
+            .await?)
+    }
+
+    async fn is_held(
+        &self,
+        holder: &str,
+        token: u64,
+    ) -> Result<bool, crate::scheduler::lease::LeaseError> {
+        let mut con = self.client.clone();
+        let lease: Option<String> = con.get(SCHEDULER_LEASE_KEY).await?;
+        Ok(lease.is_some_and(|lease| lease == format!("{holder}:{token}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_glob_characters() {
+        assert_eq!(escape_pattern("cursor"), "cursor");
+        assert_eq!(escape_pattern("a*b?[c]\\"), "a\\*b\\?\\[c\\]\\\\");
+    }
+}
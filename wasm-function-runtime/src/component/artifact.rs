@@ -0,0 +1,201 @@
+use std::{
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
+
+use sha2::{Digest, Sha256};
+use tracing::{debug, warn};
+use wasmtime::{component::Component, Engine, Precompiled};
+
+use super::errors::InvocationError;
+use crate::{cache::CacheBackend, services::errors::ServiceError, storage::StorageBackend};
+
+/// Compiled components shared by HTTP and scheduled functions.
+///
+/// Artifacts are keyed by the stored binary and the compatibility hash of the engine,
+/// so an artifact compiled by another wasmtime version or configuration is never deserialized.
+pub(crate) struct ArtifactCache {
+    engine: Engine,
+    fingerprint: String,
+    cache_backend: Arc<dyn CacheBackend>,
+    storage_backend: Arc<dyn StorageBackend>,
+    /// Keep artifacts as `.cwasm` next to the binaries, so restarts don't recompile
+    persist: bool,
+}
+
+impl ArtifactCache {
+    pub(crate) fn new(
+        engine: Engine,
+        cache_backend: Arc<dyn CacheBackend>,
+        storage_backend: Arc<dyn StorageBackend>,
+        persist: bool,
+    ) -> Self {
+        Self {
+            fingerprint: engine_fingerprint(&engine),
+            engine,
+            cache_backend,
+            storage_backend,
+            persist,
+        }
+    }
+
+    /// Name of the artifact of a stored binary compiled by this engine
+    fn artifact_name(&self, related_wasm: &str) -> String {
+        format!(
+            "{}.{}.cwasm",
+            related_wasm.trim_end_matches(".wasm"),
+            self.fingerprint
+        )
+    }
+
+    /// Returns the compiled component of the stored binary, compiling and caching it if necessary
+    pub(crate) async fn load(&self, related_wasm: &str) -> Result<Component, InvocationError> {
+        let artifact_name = self.artifact_name(related_wasm);
+
+        if let Some(component) = self.lookup(&artifact_name).await? {
+            return Ok(component);
+        }
+
+        let bytes = self
+            .storage_backend
+            .extract_file_bytes(related_wasm)
+            .await?;
+        let component =
+            Component::from_binary(&self.engine, &bytes).map_err(InvocationError::Component)?;
+        self.store(related_wasm, &component).await;
+
+        Ok(component)
+    }
+
+    /// Caches the compiled component of the stored binary.
+    /// Failures are only logged, the component is compiled again on the next load.
+    pub(crate) async fn store(&self, related_wasm: &str, component: &Component) {
+        let artifact_name = self.artifact_name(related_wasm);
+
+        let artifact = match component.serialize() {
+            Ok(artifact) => artifact,
+            Err(err) => {
+                warn!("Failed to serialize compiled function '{related_wasm}': {err}");
+                return;
+            }
+        };
+
+        if self.persist {
+            if let Err(err) = self
+                .storage_backend
+                .store_file(artifact.clone(), &artifact_name)
+                .await
+            {
+                warn!("Failed to store compiled function '{artifact_name}': {err:?}");
+            }
+        }
+        if let Err(err) = self.cache_backend.insert(&artifact_name, artifact).await {
+            warn!("Failed to cache compiled function '{artifact_name}': {err:?}");
+        }
+    }
+
+    /// Drops the artifact of a removed binary
+    pub(crate) async fn remove(&self, related_wasm: &str) -> Result<(), ServiceError> {
+        let artifact_name = self.artifact_name(related_wasm);
+
+        self.cache_backend.invalidate(&artifact_name).await?;
+        if self.persist {
+            self.storage_backend.delete_file(&artifact_name).await?;
+        }
+        Ok(())
+    }
+
+    async fn lookup(&self, artifact_name: &str) -> Result<Option<Component>, InvocationError> {
+        let artifact = match self.cache_backend.get(artifact_name).await? {
+            Some(artifact) => Some(artifact),
+            None if self.persist => self
+                .storage_backend
+                .extract_file_bytes(artifact_name)
+                .await
+                .inspect_err(|err| debug!("No stored artifact '{artifact_name}': {err:?}"))
+                .ok(),
+            None => None,
+        };
+
+        Ok(artifact.and_then(|artifact| self.deserialize(artifact_name, &artifact)))
+    }
+
+    fn deserialize(&self, artifact_name: &str, artifact: &[u8]) -> Option<Component> {
+        if Engine::detect_precompiled(artifact) != Some(Precompiled::Component) {
+            warn!("Discarding cached function '{artifact_name}', it is no compiled component");
+            return None;
+        }
+
+        // SAFETY: Artifacts are only looked up under the compatibility hash of this engine
+        // and are only written by `store`, after the engine compiled them.
+        unsafe { Component::deserialize(&self.engine, artifact) }
+            .inspect_err(|err| warn!("Discarding cached function '{artifact_name}': {err}"))
+            .ok()
+    }
+}
+
+/// Identifies the wasmtime version and the configuration compiled code depends on.
+/// The fingerprint is persisted with the artifacts, so it must not change with the Rust release.
+fn engine_fingerprint(engine: &Engine) -> String {
+    let mut hasher = Sha256Hasher(Sha256::new());
+    engine.precompile_compatibility_hash().hash(&mut hasher);
+    hex::encode(&hasher.0.finalize()[..8])
+}
+
+/// Feeds a hashable value into sha256, unlike the std hashers its output is stable
+struct Sha256Hasher(Sha256);
+
+impl Hasher for Sha256Hasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        let digest = self.0.clone().finalize();
+        u64::from_be_bytes(digest[..8].try_into().expect("sha256 digest has 32 bytes"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fingerprint_depends_on_engine_config() {
+        let engine = super::super::setup_engine(false);
+        let same_engine = super::super::setup_engine(false);
+        let other_engine = Engine::new(wasmtime::Config::new().consume_fuel(false)).unwrap();
+
+        assert_eq!(
+            engine_fingerprint(&engine),
+            engine_fingerprint(&same_engine)
+        );
+        assert_ne!(
+            engine_fingerprint(&engine),
+            engine_fingerprint(&other_engine)
+        );
+    }
+
+    #[test]
+    fn reject_foreign_artifacts() {
+        let engine = super::super::setup_engine(false);
+        let artifacts = ArtifactCache::new(
+            engine.clone(),
+            Arc::new(crate::cache::LocalCache::default()),
+            Arc::new(crate::storage::file_system::FileSystemStorage::default()),
+            false,
+        );
+
+        let artifact_name = artifacts.artifact_name("http_function.wasm");
+        assert!(artifact_name.starts_with("http_function."));
+        assert!(artifact_name.ends_with(".cwasm"));
+
+        assert!(artifacts
+            .deserialize(&artifact_name, b"not compiled")
+            .is_none());
+        let module = wasmtime::Module::new(&engine, wat::parse_str("(module)").unwrap()).unwrap();
+        assert!(artifacts
+            .deserialize(&artifact_name, &module.serialize().unwrap())
+            .is_none());
+    }
+}
@@ -0,0 +1,332 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use thiserror::Error;
+use tracing::{error, warn};
+use wasmtime::Store;
+
+use super::{
+    limits::{self, LimitViolation},
+    ComponentState,
+};
+use crate::{
+    bindings_function_http, bindings_function_http_streaming, bindings_function_scheduled,
+    bindings_function_scheduled_v0_2, utils::ErrorResponse,
+};
+
+/// Failure details a function returned through its `function-error` record
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct GuestError {
+    pub code: String,
+    pub message: String,
+    pub status: Option<u16>,
+    pub retryable: Option<bool>,
+}
+
+impl GuestError {
+    /// The status requested by the function, if it denotes an error
+    fn status_code(&self) -> Option<StatusCode> {
+        self.status
+            .and_then(|status| StatusCode::from_u16(status).ok())
+            .filter(|status| status.is_client_error() || status.is_server_error())
+    }
+
+    /// Functions of the unversioned worlds fail without describing why
+    pub(crate) fn undescribed() -> Self {
+        Self {
+            code: "function-error".to_string(),
+            message: "Function returned a failure".to_string(),
+            status: None,
+            retryable: None,
+        }
+    }
+}
+
+impl From<bindings_function_http::FunctionError> for GuestError {
+    fn from(error: bindings_function_http::FunctionError) -> Self {
+        Self {
+            code: error.code,
+            message: error.message,
+            status: error.status,
+            retryable: error.retryable,
+        }
+    }
+}
+
+impl From<bindings_function_http_streaming::FunctionError> for GuestError {
+    fn from(error: bindings_function_http_streaming::FunctionError) -> Self {
+        Self {
+            code: error.code,
+            message: error.message,
+            status: error.status,
+            retryable: error.retryable,
+        }
+    }
+}
+
+impl From<bindings_function_scheduled::FunctionError> for GuestError {
+    fn from(error: bindings_function_scheduled::FunctionError) -> Self {
+        Self {
+            code: error.code,
+            message: error.message,
+            status: error.status,
+            retryable: error.retryable,
+        }
+    }
+}
+
+impl From<bindings_function_scheduled_v0_2::FunctionError> for GuestError {
+    fn from(error: bindings_function_scheduled_v0_2::FunctionError) -> Self {
+        Self {
+            code: error.code,
+            message: error.message,
+            status: error.status,
+            retryable: error.retryable,
+        }
+    }
+}
+
+impl From<wasmtime_wasi_http::bindings::http::types::ErrorCode> for GuestError {
+    /// A `wasi:http` function answered with an error instead of a response
+    fn from(error_code: wasmtime_wasi_http::bindings::http::types::ErrorCode) -> Self {
+        Self {
+            code: "wasi-http-error".to_string(),
+            message: format!("{error_code:?}"),
+            status: None,
+            retryable: None,
+        }
+    }
+}
+
+/// Failures that can occur while resolving, instantiating or running a function
+#[derive(Debug, Error)]
+pub(crate) enum InvocationError {
+    #[error("Function not found")]
+    NotFound,
+    #[error("Method not allowed")]
+    MethodNotAllowed,
+    #[error("Authentication required")]
+    Unauthorized(StatusCode),
+    #[error("Unable to read request body")]
+    InvalidBody(#[source] axum::Error),
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+    #[error("Request body exceeds the limit of {0} bytes")]
+    PayloadTooLarge(usize),
+    #[error("Failed to load function")]
+    Service(#[source] crate::services::errors::ServiceError),
+    #[error("Secret '{name}' of the function can't be decrypted")]
+    Secret {
+        name: String,
+        #[source]
+        source: crate::secrets::SecretError,
+    },
+    #[error("Failed to load function")]
+    Storage(#[from] crate::storage::errors::StorageError),
+    #[error("Failed to load function")]
+    Cache(#[from] crate::cache::CacheError),
+    #[error("Failed to prepare function")]
+    Component(#[source] wasmtime::Error),
+    #[error("Function failed with '{}': {}", .0.code, .0.message)]
+    Guest(GuestError),
+    #[error("Function returned an invalid response: {0}")]
+    InvalidResponse(String),
+    #[error(transparent)]
+    Limit(#[from] LimitViolation),
+    #[error("Function trapped")]
+    Trap(#[source] wasmtime::Error),
+}
+
+impl From<crate::services::errors::ServiceError> for InvocationError {
+    fn from(error: crate::services::errors::ServiceError) -> Self {
+        match error {
+            crate::services::errors::ServiceError::UndecryptableSecret { name, source } => {
+                InvocationError::Secret { name, source }
+            }
+            error => InvocationError::Service(error),
+        }
+    }
+}
+
+impl InvocationError {
+    /// Classifies an error raised while calling into the guest
+    pub(crate) fn from_call_error(store: &Store<ComponentState>, error: wasmtime::Error) -> Self {
+        match limits::violation_of(store, &error) {
+            Some(violation) => InvocationError::Limit(violation),
+            None => InvocationError::Trap(error),
+        }
+    }
+
+    pub(crate) fn status_code(&self) -> StatusCode {
+        match self {
+            InvocationError::NotFound => StatusCode::NOT_FOUND,
+            InvocationError::MethodNotAllowed => StatusCode::METHOD_NOT_ALLOWED,
+            InvocationError::Unauthorized(status_code) => *status_code,
+            InvocationError::InvalidBody(_) | InvocationError::InvalidRequest(_) => {
+                StatusCode::BAD_REQUEST
+            }
+            InvocationError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            InvocationError::Guest(guest_error) => {
+                guest_error.status_code().unwrap_or(StatusCode::BAD_GATEWAY)
+            }
+            InvocationError::InvalidResponse(_) => StatusCode::BAD_GATEWAY,
+            InvocationError::Limit(violation) => violation.status_code(),
+            InvocationError::Service(_)
+            | InvocationError::Secret { .. }
+            | InvocationError::Storage(_)
+            | InvocationError::Cache(_)
+            | InvocationError::Component(_)
+            | InvocationError::Trap(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// Whether running the function again might succeed.
+    /// Functions decide through the `retryable` hint of their error, which defaults to retrying.
+    pub(crate) fn is_retryable(&self) -> bool {
+        match self {
+            InvocationError::Guest(guest_error) => guest_error.retryable.unwrap_or(true),
+            InvocationError::Limit(violation) => matches!(violation, LimitViolation::Timeout),
+            InvocationError::Service(_)
+            | InvocationError::Storage(_)
+            | InvocationError::Cache(_)
+            | InvocationError::Trap(_) => true,
+            InvocationError::NotFound
+            | InvocationError::MethodNotAllowed
+            | InvocationError::Unauthorized(_)
+            | InvocationError::InvalidBody(_)
+            | InvocationError::InvalidRequest(_)
+            | InvocationError::PayloadTooLarge(_)
+            | InvocationError::Secret { .. }
+            | InvocationError::Component(_)
+            | InvocationError::InvalidResponse(_) => false,
+        }
+    }
+
+    /// Logs the error with a severity matching its cause
+    pub(crate) fn log(&self) {
+        match self {
+            // Debug output contains the wasm backtrace of the trap
+            InvocationError::Trap(err) | InvocationError::Component(err) => {
+                error!("{self}: {err:?}")
+            }
+            InvocationError::Service(_)
+            | InvocationError::Secret { .. }
+            | InvocationError::Storage(_)
+            | InvocationError::Cache(_) => error!("{self}: {self:?}"),
+            InvocationError::Guest(_)
+            | InvocationError::InvalidResponse(_)
+            | InvocationError::Limit(_) => warn!("{self}"),
+            InvocationError::NotFound
+            | InvocationError::MethodNotAllowed
+            | InvocationError::Unauthorized(_)
+            | InvocationError::InvalidBody(_)
+            | InvocationError::InvalidRequest(_)
+            | InvocationError::PayloadTooLarge(_) => {}
+        }
+    }
+}
+
+impl IntoResponse for InvocationError {
+    fn into_response(self) -> Response {
+        self.log();
+        if let InvocationError::Guest(guest_error) = &self {
+            return (self.status_code(), Json(guest_error)).into_response();
+        }
+        (
+            self.status_code(),
+            Json(ErrorResponse {
+                message: &self.to_string(),
+            }),
+        )
+            .into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn map_errors_to_status_codes() {
+        assert_eq!(
+            InvocationError::NotFound.status_code(),
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            InvocationError::Limit(LimitViolation::Memory).status_code(),
+            StatusCode::INSUFFICIENT_STORAGE
+        );
+        assert_eq!(
+            InvocationError::Limit(LimitViolation::Timeout).status_code(),
+            StatusCode::GATEWAY_TIMEOUT
+        );
+        assert_eq!(
+            InvocationError::PayloadTooLarge(1024).status_code(),
+            StatusCode::PAYLOAD_TOO_LARGE
+        );
+        assert_eq!(
+            InvocationError::Trap(wasmtime::Error::msg("unreachable")).status_code(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+
+    #[test]
+    fn name_secret_that_fails_to_decrypt() {
+        let error =
+            InvocationError::from(crate::services::errors::ServiceError::UndecryptableSecret {
+                name: "TOKEN".to_string(),
+                source: crate::secrets::SecretError::Crypto,
+            });
+
+        assert_eq!(error.status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(
+            error.to_string(),
+            "Secret 'TOKEN' of the function can't be decrypted"
+        );
+        assert!(!error.is_retryable());
+    }
+
+    #[test]
+    fn respond_with_status_requested_by_guest() {
+        let guest_error = |status| {
+            InvocationError::Guest(GuestError {
+                code: "invalid-input".to_string(),
+                message: "Missing name".to_string(),
+                status,
+                retryable: None,
+            })
+        };
+
+        assert_eq!(
+            guest_error(Some(422)).status_code(),
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+        assert_eq!(guest_error(None).status_code(), StatusCode::BAD_GATEWAY);
+        // Success codes can't signal a failure
+        assert_eq!(
+            guest_error(Some(200)).status_code(),
+            StatusCode::BAD_GATEWAY
+        );
+    }
+
+    #[test]
+    fn honour_retryable_hint_of_guest() {
+        let guest_error = |retryable| {
+            InvocationError::Guest(GuestError {
+                code: "upstream-unavailable".to_string(),
+                message: "Upstream returned 503".to_string(),
+                status: None,
+                retryable,
+            })
+        };
+
+        assert!(guest_error(None).is_retryable());
+        assert!(guest_error(Some(true)).is_retryable());
+        assert!(!guest_error(Some(false)).is_retryable());
+        assert!(InvocationError::Limit(LimitViolation::Timeout).is_retryable());
+        assert!(!InvocationError::Limit(LimitViolation::Fuel).is_retryable());
+    }
+}
@@ -1,86 +1,390 @@
 use wasmtime::{
-    component::{Component, Linker},
+    component::{
+        types::{ComponentItem, Type},
+        Component, InstancePre, Linker,
+    },
     Engine, Store,
 };
+use wasmtime_wasi_http::bindings::{Proxy, ProxyPre};
 
 use crate::{
-    bindings_function_http::{self, FunctionHttp},
-    domain,
+    bindings_function_http,
+    bindings_function_http_streaming::{FunctionHttpStreaming, FunctionHttpStreamingPre},
+    bindings_function_http_v0_1,
+    domain::{self, http_world::HttpWorld},
+    keyvalue::ScopedKeyValue,
 };
 
-use super::{ComponentState, ComponentStateBuilder};
+use super::{errors::GuestError, ComponentState, ComponentStateBuilder};
 
-pub(crate) struct FunctionHttpBuilder<'a> {
-    state_builder: ComponentStateBuilder,
-    component: Component,
-    linker: Linker<ComponentState>,
-    engine: &'a Engine,
+/// A function of the `function-http` world, built against the current world or one it replaced
+pub(crate) enum FunctionHttp {
+    Current(bindings_function_http::FunctionHttp),
+    /// Built against the unversioned world, it knows neither path parameters nor describes its failures
+    V0_1(bindings_function_http_v0_1::FunctionHttp),
+}
+
+/// Whether the component exports the handler of the unversioned `function-http` world.
+/// Both worlds export `handle-request`, only the error case of its result tells them apart.
+fn handles_request_without_error(engine: &Engine, component: &Component) -> bool {
+    matches!(
+        component.component_type().get_export(engine, "handle-request"),
+        Some(ComponentItem::ComponentFunc(handle_request))
+            if handle_request
+                .results()
+                .any(|result| matches!(result, Type::Result(result) if result.err().is_none()))
+    )
+}
+
+/// A function of the `function-http` world ready to be instantiated, in the world its component was built against
+pub(crate) enum FunctionHttpPre {
+    Current(bindings_function_http::FunctionHttpPre<ComponentState>),
+    V0_1(bindings_function_http_v0_1::FunctionHttpPre<ComponentState>),
+}
+
+impl FunctionHttpPre {
+    /// Checks that the component exports the handler of one of the supported worlds
+    pub(crate) fn new(
+        engine: &Engine,
+        component: &Component,
+        instance_pre: InstancePre<ComponentState>,
+    ) -> wasmtime::Result<Self> {
+        // Components deployed before the versioned world keep running with the requests they know
+        if handles_request_without_error(engine, component) {
+            return Ok(Self::V0_1(
+                bindings_function_http_v0_1::FunctionHttpPre::new(instance_pre)?,
+            ));
+        }
+        Ok(Self::Current(bindings_function_http::FunctionHttpPre::new(
+            instance_pre,
+        )?))
+    }
+
+    fn engine(&self) -> &Engine {
+        match self {
+            Self::Current(pre) => pre.engine(),
+            Self::V0_1(pre) => pre.engine(),
+        }
+    }
+
+    async fn instantiate_async(
+        &self,
+        store: &mut Store<ComponentState>,
+    ) -> wasmtime::Result<FunctionHttp> {
+        Ok(match self {
+            Self::Current(pre) => FunctionHttp::Current(pre.instantiate_async(store).await?),
+            Self::V0_1(pre) => FunctionHttp::V0_1(pre.instantiate_async(store).await?),
+        })
+    }
+}
+
+impl FunctionHttp {
+    /// Whether the world of the function knows the method
+    pub(crate) fn supports(&self, method: bindings_function_http::Method) -> bool {
+        match self {
+            Self::Current(_) => true,
+            Self::V0_1(_) => method_v0_1(method).is_some(),
+        }
+    }
+
+    /// Handles the request, functions of older worlds get as much of it as their export takes
+    pub(crate) async fn call_handle_request(
+        &self,
+        store: &mut Store<ComponentState>,
+        req: bindings_function_http::Request,
+    ) -> wasmtime::Result<Result<bindings_function_http::Response, GuestError>> {
+        match self {
+            Self::Current(function) => Ok(function
+                .call_handle_request(store, &req)
+                .await?
+                .map_err(GuestError::from)),
+            Self::V0_1(function) => {
+                let method = method_v0_1(req.method).ok_or_else(|| {
+                    wasmtime::Error::msg(format!(
+                        "method {:?} is unknown to the world of the function",
+                        req.method
+                    ))
+                })?;
+                let req = bindings_function_http_v0_1::Request {
+                    method,
+                    path: req.path,
+                    query_params: req
+                        .query_params
+                        .into_iter()
+                        .map(|param| bindings_function_http_v0_1::QueryParam {
+                            name: param.name,
+                            value: param.value,
+                        })
+                        .collect(),
+                    headers: req
+                        .headers
+                        .into_iter()
+                        .map(|header| bindings_function_http_v0_1::Header {
+                            name: header.name,
+                            value: header.value,
+                        })
+                        .collect(),
+                    body: req.body,
+                };
+                Ok(function
+                    .call_handle_request(store, &req)
+                    .await?
+                    .map(|response| bindings_function_http::Response {
+                        status_code: response.status_code,
+                        headers: response
+                            .headers
+                            .into_iter()
+                            .map(|header| bindings_function_http::Header {
+                                name: header.name,
+                                value: header.value,
+                            })
+                            .collect(),
+                        body: response.body,
+                    })
+                    .map_err(|()| GuestError::undescribed()))
+            }
+        }
+    }
 }
 
-impl<'a> FunctionHttpBuilder<'a> {
-    pub fn from_binary(engine: &'a Engine, bytes: &[u8]) -> Self {
-        let state_builder = ComponentStateBuilder::new();
+fn method_v0_1(
+    method: bindings_function_http::Method,
+) -> Option<bindings_function_http_v0_1::Method> {
+    match method {
+        bindings_function_http::Method::Get => Some(bindings_function_http_v0_1::Method::Get),
+        bindings_function_http::Method::Post => Some(bindings_function_http_v0_1::Method::Post),
+        _ => None,
+    }
+}
 
-        let component = Component::from_binary(engine, bytes).expect("Failed to create component");
+pub(crate) struct FunctionHttpBuilder {
+    envs: Vec<(String, String)>,
+    limits: domain::limits::ResourceLimits,
+    key_value: Option<ScopedKeyValue>,
+    component: Component,
+    linker: Linker<ComponentState>,
+}
 
-        let mut linker: Linker<ComponentState> = Linker::new(engine);
-        wasmtime_wasi::add_to_linker_async(&mut linker).expect("Failed to add WASI to linker");
-        wasmtime_wasi_http::add_only_http_to_linker_async(&mut linker)
-            .expect("Failed to add WASI HTTP to linker");
+impl FunctionHttpBuilder {
+    pub fn from_component(engine: &Engine, component: Component) -> wasmtime::Result<Self> {
+        let linker: Linker<ComponentState> = super::setup_linker(engine)?;
 
-        Self {
-            state_builder,
+        Ok(Self {
+            envs: vec![],
+            limits: domain::limits::ResourceLimits::default(),
+            key_value: None,
             component,
             linker,
-            engine,
-        }
+        })
     }
 
     pub fn with_variables(mut self, vars: &[domain::variable::Variable]) -> Self {
-        let vars = vars
-            .iter()
-            .map(|v| (format!("VAR_{}", v.name), &v.value))
-            .collect::<Vec<(String, &String)>>();
-        self.state_builder.with_envs(&vars);
+        self.envs = super::variable_envs(vars);
         self
     }
 
-    pub fn serialize(&self) -> Vec<u8> {
-        self.component
-            .serialize()
-            .expect("Failed to serialize component")
+    pub fn with_secrets(mut self, secrets: &[domain::secret::SecretValue]) -> Self {
+        self.envs.extend(super::secret_envs(secrets));
+        self
     }
 
-    pub unsafe fn deserialize(engine: &'a Engine, bytes: &[u8]) -> Self {
-        let state_builder = ComponentStateBuilder::new();
+    pub fn with_limits(mut self, limits: &domain::limits::ResourceLimits) -> Self {
+        self.limits = limits.clone();
+        self
+    }
 
-        let component =
-            Component::deserialize(engine, bytes).expect("Failed to deserialize component");
+    pub fn with_key_value(mut self, key_value: ScopedKeyValue) -> Self {
+        self.key_value = Some(key_value);
+        self
+    }
 
-        let mut linker: Linker<ComponentState> = Linker::new(engine);
-        wasmtime_wasi::add_to_linker_async(&mut linker).expect("Failed to add WASI to linker");
-        wasmtime_wasi_http::add_only_http_to_linker_async(&mut linker)
-            .expect("Failed to add WASI HTTP to linker");
+    /// Links the component once, so it can be instantiated repeatedly without relinking
+    pub fn prepare(self, world: HttpWorld) -> wasmtime::Result<PreparedFunctionHttp> {
+        let instance_pre = self.linker.instantiate_pre(&self.component)?;
+        let function_pre = match world {
+            HttpWorld::FunctionHttp => HttpFunctionPre::FunctionHttp(FunctionHttpPre::new(
+                self.component.engine(),
+                &self.component,
+                instance_pre,
+            )?),
+            HttpWorld::FunctionHttpStreaming => {
+                HttpFunctionPre::FunctionHttpStreaming(FunctionHttpStreamingPre::new(instance_pre)?)
+            }
+            HttpWorld::WasiHttp => HttpFunctionPre::WasiHttp(ProxyPre::new(instance_pre)?),
+        };
 
-        Self {
-            state_builder,
-            component,
-            linker,
-            engine,
+        Ok(PreparedFunctionHttp {
+            function_pre,
+            envs: self.envs,
+            limits: self.limits,
+            key_value: self.key_value,
+        })
+    }
+}
+
+/// The exports of a linked HTTP function, typed by the world it serves requests through
+enum HttpFunctionPre {
+    FunctionHttp(FunctionHttpPre),
+    FunctionHttpStreaming(FunctionHttpStreamingPre<ComponentState>),
+    WasiHttp(ProxyPre<ComponentState>),
+}
+
+/// An HTTP function instantiated for a single request
+pub(crate) enum HttpFunctionInstance {
+    FunctionHttp(FunctionHttp),
+    FunctionHttpStreaming(FunctionHttpStreaming),
+    WasiHttp(Proxy),
+}
+
+/// A linked HTTP function, ready to be instantiated per request
+pub(crate) struct PreparedFunctionHttp {
+    function_pre: HttpFunctionPre,
+    envs: Vec<(String, String)>,
+    limits: domain::limits::ResourceLimits,
+    key_value: Option<ScopedKeyValue>,
+}
+
+impl PreparedFunctionHttp {
+    pub async fn instantiate(
+        &self,
+        logs: super::logs::LogCollector,
+    ) -> wasmtime::Result<(HttpFunctionInstance, Store<ComponentState>)> {
+        let mut state_builder = ComponentStateBuilder::new();
+        state_builder
+            .with_envs(&self.envs)
+            .with_limits(&self.limits)
+            .with_log_collector(logs);
+        if let Some(key_value) = &self.key_value {
+            state_builder.with_key_value(key_value.clone());
         }
+
+        let engine = match &self.function_pre {
+            HttpFunctionPre::FunctionHttp(function_pre) => function_pre.engine(),
+            HttpFunctionPre::FunctionHttpStreaming(function_pre) => function_pre.engine(),
+            HttpFunctionPre::WasiHttp(proxy_pre) => proxy_pre.engine(),
+        };
+        let mut store = Store::new(engine, state_builder.build());
+        super::limits::apply_to_store(&mut store, &self.limits)?;
+
+        let func_instance = match &self.function_pre {
+            HttpFunctionPre::FunctionHttp(function_pre) => HttpFunctionInstance::FunctionHttp(
+                function_pre.instantiate_async(&mut store).await?,
+            ),
+            HttpFunctionPre::FunctionHttpStreaming(function_pre) => {
+                HttpFunctionInstance::FunctionHttpStreaming(
+                    function_pre.instantiate_async(&mut store).await?,
+                )
+            }
+            HttpFunctionPre::WasiHttp(proxy_pre) => {
+                HttpFunctionInstance::WasiHttp(proxy_pre.instantiate_async(&mut store).await?)
+            }
+        };
+        Ok((func_instance, store))
     }
+}
 
-    pub async fn build(self) -> (FunctionHttp, Store<ComponentState>) {
-        let component_state = self.state_builder.build();
-        let mut store = Store::new(self.engine, component_state);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{component::validation, domain::manifest::FuncKind};
 
-        let func_instance = bindings_function_http::FunctionHttp::instantiate_async(
-            &mut store,
-            &self.component,
-            &self.linker,
+    /// Implements the unversioned `function-http` world, it responds with 200 plus the index of the method
+    const V0_1_COMPONENT: &str = r#"(component
+        (core module $handler
+            (memory (export "memory") 1)
+            (global $next (mut i32) (i32.const 1024))
+            (func (export "realloc") (param i32 i32) (param $align i32) (param $size i32) (result i32)
+                (local $ptr i32)
+                (local.set $ptr
+                    (i32.and
+                        (i32.add (global.get $next) (i32.sub (local.get $align) (i32.const 1)))
+                        (i32.sub (i32.const 0) (local.get $align))))
+                (global.set $next (i32.add (local.get $ptr) (local.get $size)))
+                (local.get $ptr)
+            )
+            ;; The ok case of the result is at offset 0, the status code of the response at offset 4
+            (func (export "handle-request")
+                (param $method i32) (param i32 i32 i32 i32 i32 i32 i32 i32) (result i32)
+                (i32.store16 (i32.const 4) (i32.add (i32.const 200) (local.get $method)))
+                (i32.const 0)
+            )
+        )
+        (core instance $handler (instantiate $handler))
+        (type $header (record (field "name" string) (field "value" string)))
+        (export $exported-header "header" (type $header))
+        (type $query-param (record (field "name" string) (field "value" string)))
+        (export $exported-query-param "query-param" (type $query-param))
+        (type $method (enum "GET" "POST"))
+        (export $exported-method "method" (type $method))
+        (type $request (record
+            (field "method" $exported-method)
+            (field "path" string)
+            (field "query-params" (list $exported-query-param))
+            (field "headers" (list $exported-header))
+            (field "body" (list u8))
+        ))
+        (export $exported-request "request" (type $request))
+        (type $response (record
+            (field "status-code" u16)
+            (field "headers" (list $exported-header))
+            (field "body" (list u8))
+        ))
+        (export $exported-response "response" (type $response))
+        (func $handle-request (param "req" $exported-request) (result (result $exported-response))
+            (canon lift (core func $handler "handle-request")
+                (memory $handler "memory") (realloc (func $handler "realloc")))
         )
-        .await
-        .expect("Failed to instantiate component");
-        (func_instance, store)
+        (export "handle-request" (func $handle-request))
+    )"#;
+
+    fn request(method: bindings_function_http::Method) -> bindings_function_http::Request {
+        bindings_function_http::Request {
+            method,
+            path: "/legacy".to_string(),
+            path_params: vec![bindings_function_http::PathParam {
+                name: "id".to_string(),
+                value: "42".to_string(),
+            }],
+            query_params: vec![],
+            headers: vec![bindings_function_http::Header {
+                name: "accept".to_string(),
+                value: "text/plain".to_string(),
+            }],
+            body: b"body".to_vec(),
+        }
+    }
+
+    #[tokio::test]
+    async fn serve_component_of_unversioned_world() {
+        let engine = super::super::setup_engine(false);
+        let bytes = wat::parse_str(V0_1_COMPONENT).unwrap();
+
+        // Redeploys of the unchanged binary pass validation
+        let component = validation::validate_component(&engine, &FuncKind::Http, &bytes)
+            .expect("component of the unversioned function-http world is valid");
+
+        let prepared_function = FunctionHttpBuilder::from_component(&engine, component)
+            .unwrap()
+            .prepare(HttpWorld::FunctionHttp)
+            .unwrap();
+        let (function, mut store) = prepared_function
+            .instantiate(super::super::logs::LogCollector::new(
+                uuid::Uuid::new_v4(),
+                None,
+            ))
+            .await
+            .unwrap();
+        let HttpFunctionInstance::FunctionHttp(function) = function else {
+            panic!("function serves the function-http world");
+        };
+        assert!(matches!(function, FunctionHttp::V0_1(_)));
+        assert!(!function.supports(bindings_function_http::Method::Put));
+
+        let response = function
+            .call_handle_request(&mut store, request(bindings_function_http::Method::Post))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(response.status_code, 201);
     }
 }
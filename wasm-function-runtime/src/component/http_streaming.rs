@@ -0,0 +1,489 @@
+use axum::body::Body;
+use bytes::Bytes;
+use http_body_util::BodyExt;
+use tokio::sync::{mpsc, oneshot};
+use wasmtime::{component::Resource, Store};
+use wasmtime_wasi::{
+    DynInputStream, DynOutputStream, InputStream, IoView, OutputStream, Pollable, StreamError,
+};
+
+use super::{errors::InvocationError, limits, ComponentState};
+use crate::bindings_function_http_streaming::{
+    FunctionError, FunctionHttpStreaming, FunctionHttpStreamingImports, Request, ResponseHead,
+};
+
+/// Largest chunk of the response body the function may write at once
+const MAX_CHUNK_BYTES: usize = 64 * 1024;
+/// Chunks of the response body buffered for the client, further writes wait until it caught up
+const BUFFERED_CHUNKS: usize = 4;
+
+/// Hands the response head and the chunks of its body to the request once the function sent it
+pub(crate) type ResponseSender = oneshot::Sender<(ResponseHead, mpsc::Receiver<Bytes>)>;
+
+/// The function failed after it sent the response head, so its body is cut short
+#[derive(Debug, thiserror::Error)]
+#[error("Function failed before it finished the response body")]
+struct ResponseBodyError;
+
+/// Passes the request to a function of the `function-http-streaming` world.
+/// Resolves as soon as the function sent its response, whose body streams while the function keeps running.
+/// `finished` is called once the function returned, e.g. to persist its logs.
+/// Functions failing to read an oversized body without having responded are answered as payload too large.
+pub(crate) async fn serve(
+    function: FunctionHttpStreaming,
+    mut store: Store<ComponentState>,
+    request: Request,
+    finished: impl FnOnce() + Send + 'static,
+) -> Result<(ResponseHead, Body), InvocationError> {
+    let (sender, receiver) = oneshot::channel();
+    store.data_mut().streaming_response = Some(sender);
+    let exceeded_body_limit = store.data().exceeded_body_limit.clone();
+    let (ended, call_ended) = oneshot::channel();
+
+    let timeout = store.data().limits().timeout();
+    let call = tokio::spawn(async move {
+        let call_result =
+            limits::with_timeout(timeout, function.call_handle_request(&mut store, &request)).await;
+        finished();
+
+        let result = call_result
+            .map_err(InvocationError::from)
+            .and_then(|call_result| {
+                call_result.map_err(|err| InvocationError::from_call_error(&store, err))
+            })
+            .and_then(|function_result| {
+                function_result.map_err(|err| InvocationError::Guest(err.into()))
+            });
+        // Told before the store and with it the response body stream are dropped
+        let _ = ended.send(result.is_ok());
+        result
+    });
+
+    match receiver.await {
+        Ok((head, chunks)) => {
+            // The response is on its way, later failures can only cut its body short
+            tokio::spawn(async move {
+                if let Ok(Err(err)) = call.await {
+                    err.log();
+                }
+            });
+            Ok((head, response_body(chunks, call_ended)))
+        }
+        // The function returned or failed without sending a response
+        Err(_) => match (call.await, exceeded_body_limit.get()) {
+            // Whatever the function made of the failed read, the client sent too much
+            (Ok(_), Some(max_body_bytes)) => Err(InvocationError::PayloadTooLarge(*max_body_bytes)),
+            (Ok(Err(err)), None) => Err(err),
+            (Ok(Ok(())), None) => Err(InvocationError::InvalidResponse(
+                "the function returned without sending a response".to_string(),
+            )),
+            (Err(err), _) => Err(InvocationError::Trap(err.into())),
+        },
+    }
+}
+
+/// Passes the request body to the function as stream, which reads it while it is received.
+/// `max_body_bytes` is the limit the body is wrapped with, recorded on the store once the body exceeds it.
+pub(crate) fn request_body(
+    store: &mut Store<ComponentState>,
+    body: Body,
+    max_body_bytes: usize,
+) -> Result<Resource<DynInputStream>, InvocationError> {
+    let stream: DynInputStream = Box::new(RequestBodyStream {
+        body,
+        buffered: None,
+        closed: false,
+        body_limit: Some((max_body_bytes, store.data().exceeded_body_limit.clone())),
+    });
+    store
+        .data_mut()
+        .table()
+        .push(stream)
+        .map_err(|err| InvocationError::Component(err.into()))
+}
+
+/// Streams the chunks the function writes to the client.
+/// Once the function dropped the stream, the body ends, unless the call failed and took the stream with it.
+/// The body then fails, so the client sees the connection aborted instead of a complete response.
+fn response_body(chunks: mpsc::Receiver<Bytes>, call_ended: oneshot::Receiver<bool>) -> Body {
+    Body::from_stream(futures::stream::unfold(
+        (chunks, Some(call_ended)),
+        |(mut chunks, call_ended)| async move {
+            if let Some(chunk) = chunks.recv().await {
+                return Some((Ok(chunk), (chunks, call_ended)));
+            }
+            match call_ended?.try_recv() {
+                // Dropped by the function while it keeps running, or after it returned
+                Ok(true) | Err(oneshot::error::TryRecvError::Empty) => None,
+                Ok(false) | Err(oneshot::error::TryRecvError::Closed) => {
+                    Some((Err(ResponseBodyError), (chunks, None)))
+                }
+            }
+        },
+    ))
+}
+
+impl FunctionHttpStreamingImports for ComponentState {
+    async fn send_response(
+        &mut self,
+        head: ResponseHead,
+    ) -> Result<Resource<DynOutputStream>, FunctionError> {
+        let sender = self.streaming_response.take().ok_or_else(|| {
+            runtime_error("response-already-sent", "The response was already sent")
+        })?;
+
+        let (chunks, receiver) = mpsc::channel(BUFFERED_CHUNKS);
+        // Writes fail once the client is gone, so the function can stop early
+        let _ = sender.send((head, receiver));
+
+        let stream: DynOutputStream = Box::new(ResponseBodyStream { chunks });
+        self.table()
+            .push(stream)
+            .map_err(|err| runtime_error("internal-error", &err.to_string()))
+    }
+}
+
+impl From<crate::bindings_function_http::Method>
+    for crate::bindings_function_http_streaming::Method
+{
+    fn from(method: crate::bindings_function_http::Method) -> Self {
+        use crate::bindings_function_http::Method;
+        match method {
+            Method::Get => Self::Get,
+            Method::Post => Self::Post,
+            Method::Put => Self::Put,
+            Method::Patch => Self::Patch,
+            Method::Delete => Self::Delete,
+            Method::Head => Self::Head,
+            Method::Options => Self::Options,
+        }
+    }
+}
+
+fn runtime_error(code: &str, message: &str) -> FunctionError {
+    FunctionError {
+        code: code.to_string(),
+        message: message.to_string(),
+        status: None,
+        retryable: Some(false),
+    }
+}
+
+/// WASI input stream reading the request body frame by frame
+struct RequestBodyStream {
+    body: Body,
+    buffered: Option<Result<Bytes, StreamError>>,
+    closed: bool,
+    /// The maximum request size and where to record that the body exceeded it
+    body_limit: Option<(usize, std::sync::Arc<std::sync::OnceLock<usize>>)>,
+}
+
+#[async_trait::async_trait]
+impl InputStream for RequestBodyStream {
+    fn read(&mut self, size: usize) -> Result<Bytes, StreamError> {
+        match self.buffered.take() {
+            Some(Ok(mut bytes)) => {
+                if bytes.len() > size {
+                    self.buffered = Some(Ok(bytes.split_off(size)));
+                }
+                Ok(bytes)
+            }
+            Some(Err(err)) => {
+                self.closed = true;
+                Err(err)
+            }
+            None if self.closed => Err(StreamError::Closed),
+            None => Ok(Bytes::new()),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Pollable for RequestBodyStream {
+    async fn ready(&mut self) {
+        while self.buffered.is_none() && !self.closed {
+            self.buffered = match self.body.frame().await {
+                // Trailers are not passed to the function
+                Some(Ok(frame)) => frame
+                    .into_data()
+                    .ok()
+                    .filter(|data| !data.is_empty())
+                    .map(Ok),
+                Some(Err(err)) => {
+                    if let Some((max_body_bytes, exceeded_body_limit)) = &self.body_limit {
+                        if limits::exceeds_body_limit(&err) {
+                            let _ = exceeded_body_limit.set(*max_body_bytes);
+                        }
+                    }
+                    Some(Err(StreamError::LastOperationFailed(err.into())))
+                }
+                None => Some(Err(StreamError::Closed)),
+            };
+        }
+    }
+}
+
+/// WASI output stream passing the written chunks on to the response body
+struct ResponseBodyStream {
+    chunks: mpsc::Sender<Bytes>,
+}
+
+#[async_trait::async_trait]
+impl OutputStream for ResponseBodyStream {
+    fn write(&mut self, bytes: Bytes) -> Result<(), StreamError> {
+        if bytes.len() > MAX_CHUNK_BYTES {
+            return Err(StreamError::trap("write exceeded the permitted size"));
+        }
+        self.chunks.try_send(bytes).map_err(|err| match err {
+            mpsc::error::TrySendError::Full(_) => {
+                StreamError::trap("write without waiting for the stream to be ready")
+            }
+            mpsc::error::TrySendError::Closed(_) => StreamError::Closed,
+        })
+    }
+
+    fn flush(&mut self) -> Result<(), StreamError> {
+        // Written chunks are handed over right away
+        Ok(())
+    }
+
+    fn check_write(&mut self) -> Result<usize, StreamError> {
+        if self.chunks.is_closed() {
+            return Err(StreamError::Closed);
+        }
+        Ok(if self.chunks.capacity() > 0 {
+            MAX_CHUNK_BYTES
+        } else {
+            0
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl Pollable for ResponseBodyStream {
+    async fn ready(&mut self) {
+        // Ready once the client caught up, or gone
+        let _ = self.chunks.reserve().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bindings_function_http_streaming::Method;
+    use futures::StreamExt;
+
+    /// Reads the request body until reading fails, then fails without sending a response
+    const READ_BODY_COMPONENT: &str = r#"(component $function
+        (import "wasi:io/error@0.2.3" (instance $io-error
+            (export "error" (type (sub resource)))
+        ))
+        (alias export $io-error "error" (type $error))
+        (import "wasi:io/streams@0.2.3" (instance $streams
+            (alias outer $function $error (type $outer-error))
+            (export $stream-io-error "error" (type (eq $outer-error)))
+            (export $input-stream "input-stream" (type (sub resource)))
+            (type $stream-error-type (variant
+                (case "last-operation-failed" (own $stream-io-error))
+                (case "closed")
+            ))
+            (export $stream-error "stream-error" (type (eq $stream-error-type)))
+            (export "[method]input-stream.blocking-read" (func
+                (param "self" (borrow $input-stream))
+                (param "len" u64)
+                (result (result (list u8) (error $stream-error)))
+            ))
+        ))
+        (alias export $streams "input-stream" (type $input-stream))
+        (alias export $streams "[method]input-stream.blocking-read" (func $blocking-read))
+
+        (core module $heap
+            (memory (export "memory") 1)
+            (global $next (mut i32) (i32.const 1024))
+            (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+                (local $ptr i32)
+                (local.set $ptr (i32.and
+                    (i32.add (global.get $next) (i32.sub (local.get 2) (i32.const 1)))
+                    (i32.sub (i32.const 0) (local.get 2))
+                ))
+                (global.set $next (i32.add (local.get $ptr) (local.get 3)))
+                (local.get $ptr)
+            )
+        )
+        (core instance $heap (instantiate $heap))
+        (alias core export $heap "memory" (core memory $memory))
+        (alias core export $heap "realloc" (core func $realloc))
+        (core func $read (canon lower (func $blocking-read) (memory $memory) (realloc $realloc)))
+
+        (core module $handler
+            (import "heap" "memory" (memory 1))
+            (import "streams" "blocking-read" (func $read (param i32 i64 i32)))
+            (func (export "handle-request")
+                ;; Method, path, path params, query params and headers precede the body
+                (param i32 i32 i32 i32 i32 i32 i32 i32 i32)
+                (param $body i32)
+                (result i32)
+                (block $failed
+                    (loop $next
+                        (call $read (local.get $body) (i64.const 16) (i32.const 16))
+                        (br_if $failed (i32.load8_u (i32.const 16)))
+                        (br $next)
+                    )
+                )
+                ;; The error case of the result, with empty strings and no status
+                (i32.store8 (i32.const 64) (i32.const 1))
+                (i32.const 64)
+            )
+        )
+        (core instance $handler (instantiate $handler
+            (with "heap" (instance $heap))
+            (with "streams" (instance (export "blocking-read" (func $read))))
+        ))
+
+        (type $method-type (enum "GET" "POST" "PUT" "PATCH" "DELETE" "HEAD" "OPTIONS"))
+        (export $method "method" (type $method-type))
+        (type $header-type (record (field "name" string) (field "value" string)))
+        (export $header "header" (type $header-type))
+        (type $query-param-type (record (field "name" string) (field "value" string)))
+        (export $query-param "query-param" (type $query-param-type))
+        (type $path-param-type (record (field "name" string) (field "value" string)))
+        (export $path-param "path-param" (type $path-param-type))
+        (type $request-type (record
+            (field "method" $method)
+            (field "path" string)
+            (field "path-params" (list $path-param))
+            (field "query-params" (list $query-param))
+            (field "headers" (list $header))
+            (field "body" (own $input-stream))
+        ))
+        (export $request "request" (type $request-type))
+        (type $function-error-type (record
+            (field "code" string)
+            (field "message" string)
+            (field "status" (option u16))
+            (field "retryable" (option bool))
+        ))
+        (export $function-error "function-error" (type $function-error-type))
+        (func $handle-request (param "req" $request) (result (result (error $function-error)))
+            (canon lift (core func $handler "handle-request") (memory $memory) (realloc $realloc))
+        )
+        (export "handle-request" (func $handle-request))
+    )"#;
+
+    #[tokio::test]
+    async fn read_request_body_in_chunks() {
+        let mut stream = RequestBodyStream {
+            body: Body::from("hello world"),
+            buffered: None,
+            closed: false,
+            body_limit: None,
+        };
+
+        stream.ready().await;
+        assert_eq!(stream.read(5).unwrap(), Bytes::from("hello"));
+        stream.ready().await;
+        assert_eq!(stream.read(64).unwrap(), Bytes::from(" world"));
+        stream.ready().await;
+        assert!(matches!(stream.read(64), Err(StreamError::Closed)));
+    }
+
+    #[tokio::test]
+    async fn answer_oversized_chunked_body_as_payload_too_large() {
+        let engine = super::super::setup_engine(false);
+        let component = wasmtime::component::Component::new(
+            &engine,
+            wat::parse_str(READ_BODY_COMPONENT).unwrap(),
+        )
+        .unwrap();
+        let linker = super::super::setup_linker(&engine).unwrap();
+        let mut store = Store::new(&engine, super::super::ComponentStateBuilder::new().build());
+        limits::apply_to_store(&mut store, &Default::default()).unwrap();
+        let function = FunctionHttpStreaming::instantiate_async(&mut store, &component, &linker)
+            .await
+            .unwrap();
+
+        // Chunked without content length, wrapped as the function handler does
+        let chunks = futures::stream::iter(
+            ["0123", "4567", "89ab"].map(|chunk| Ok::<_, std::io::Error>(Bytes::from(chunk))),
+        );
+        let body = Body::new(http_body_util::Limited::new(Body::from_stream(chunks), 8));
+        let request = Request {
+            method: Method::Post,
+            path: "/upload".to_string(),
+            path_params: Vec::new(),
+            query_params: Vec::new(),
+            headers: Vec::new(),
+            body: request_body(&mut store, body, 8).unwrap(),
+        };
+
+        let result = serve(function, store, request, || {}).await;
+        assert!(matches!(result, Err(InvocationError::PayloadTooLarge(8))));
+    }
+
+    #[tokio::test]
+    async fn cut_response_body_short_once_the_call_failed() {
+        let (chunks, receiver) = mpsc::channel(BUFFERED_CHUNKS);
+        let (ended, call_ended) = oneshot::channel();
+        let body = response_body(receiver, call_ended);
+
+        chunks.send(Bytes::from("partial")).await.unwrap();
+        // The store drops the stream after the failed call ended
+        ended.send(false).unwrap();
+        drop(chunks);
+
+        let mut frames = body.into_data_stream();
+        assert_eq!(frames.next().await.unwrap().unwrap(), "partial");
+        assert!(frames.next().await.unwrap().is_err());
+        assert!(frames.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn end_response_body_once_the_function_dropped_the_stream() {
+        let (chunks, receiver) = mpsc::channel(BUFFERED_CHUNKS);
+        let (_ended, call_ended) = oneshot::channel::<bool>();
+        let body = response_body(receiver, call_ended);
+
+        chunks.send(Bytes::from("complete")).await.unwrap();
+        // Dropped by the function, which is still running
+        drop(chunks);
+
+        assert_eq!(body.collect().await.unwrap().to_bytes(), "complete");
+    }
+
+    #[tokio::test]
+    async fn fail_reading_request_body_over_the_limit() {
+        let mut stream = RequestBodyStream {
+            body: Body::new(http_body_util::Limited::new(Body::from("hello world"), 4)),
+            buffered: None,
+            closed: false,
+            body_limit: None,
+        };
+
+        stream.ready().await;
+        assert!(matches!(
+            stream.read(64),
+            Err(StreamError::LastOperationFailed(_))
+        ));
+        assert!(matches!(stream.read(64), Err(StreamError::Closed)));
+    }
+
+    #[tokio::test]
+    async fn wait_for_the_client_before_writing_more() {
+        let (chunks, mut receiver) = mpsc::channel(BUFFERED_CHUNKS);
+        let mut stream = ResponseBodyStream { chunks };
+
+        for _ in 0..BUFFERED_CHUNKS {
+            assert_eq!(stream.check_write().unwrap(), MAX_CHUNK_BYTES);
+            stream.write(Bytes::from("chunk")).unwrap();
+        }
+        assert_eq!(stream.check_write().unwrap(), 0);
+        assert!(stream.write(Bytes::from("chunk")).is_err());
+
+        receiver.recv().await.unwrap();
+        stream.ready().await;
+        assert_eq!(stream.check_write().unwrap(), MAX_CHUNK_BYTES);
+
+        drop(receiver);
+        assert!(matches!(stream.check_write(), Err(StreamError::Closed)));
+    }
+}
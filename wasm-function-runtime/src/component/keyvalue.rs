@@ -0,0 +1,46 @@
+use tracing::error;
+
+use super::ComponentState;
+use crate::{
+    bindings_function_http::jontze::keyvalue::store::{Error, Host},
+    keyvalue::{KeyValueError, ScopedKeyValue},
+};
+
+impl From<KeyValueError> for Error {
+    fn from(err: KeyValueError) -> Self {
+        match err {
+            KeyValueError::InvalidKey => Error::InvalidKey,
+            KeyValueError::ValueTooLarge => Error::ValueTooLarge,
+            KeyValueError::Db(_) | KeyValueError::Redis(_) => {
+                error!("Key-value store failed: {err:?}");
+                Error::Unavailable(err.to_string())
+            }
+        }
+    }
+}
+
+impl ComponentState {
+    fn key_value(&self) -> Result<&ScopedKeyValue, Error> {
+        self.key_value
+            .as_ref()
+            .ok_or_else(|| Error::Unavailable("No key-value store configured".to_string()))
+    }
+}
+
+impl Host for ComponentState {
+    async fn get(&mut self, key: String) -> Result<Option<Vec<u8>>, Error> {
+        Ok(self.key_value()?.get(&key).await?)
+    }
+
+    async fn set(&mut self, key: String, value: Vec<u8>) -> Result<(), Error> {
+        Ok(self.key_value()?.set(&key, value).await?)
+    }
+
+    async fn delete(&mut self, key: String) -> Result<(), Error> {
+        Ok(self.key_value()?.delete(&key).await?)
+    }
+
+    async fn list_keys(&mut self, prefix: Option<String>) -> Result<Vec<String>, Error> {
+        Ok(self.key_value()?.list_keys(prefix.as_deref()).await?)
+    }
+}
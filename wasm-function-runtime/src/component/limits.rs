@@ -0,0 +1,202 @@
+use axum::http::StatusCode;
+use thiserror::Error;
+use wasmtime::{ResourceLimiter, Store};
+
+use super::ComponentState;
+use crate::domain::limits::ResourceLimits;
+
+/// Interval in which the engine epoch is incremented.
+/// Running guests yield back to the async executor on every tick.
+pub(crate) const EPOCH_TICK: std::time::Duration = std::time::Duration::from_millis(10);
+
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LimitViolation {
+    #[error("Function exceeded its execution time limit")]
+    Timeout,
+    #[error("Function ran out of fuel")]
+    Fuel,
+    #[error("Function exceeded its memory limit")]
+    Memory,
+    #[error("Function exceeded its table elements limit")]
+    TableElements,
+}
+
+impl LimitViolation {
+    pub(crate) fn status_code(&self) -> StatusCode {
+        match self {
+            LimitViolation::Timeout | LimitViolation::Fuel => StatusCode::GATEWAY_TIMEOUT,
+            LimitViolation::Memory | LimitViolation::TableElements => {
+                StatusCode::INSUFFICIENT_STORAGE
+            }
+        }
+    }
+}
+
+/// Denies memory and table growth beyond the configured limits and
+/// remembers which limit was hit, so a subsequent trap can be attributed
+#[derive(Default)]
+pub(crate) struct FunctionLimiter {
+    max_memory_bytes: Option<usize>,
+    max_table_elements: Option<usize>,
+    violation: Option<LimitViolation>,
+}
+
+impl FunctionLimiter {
+    pub(crate) fn new(limits: &ResourceLimits) -> Self {
+        Self {
+            max_memory_bytes: limits.max_memory_bytes(),
+            max_table_elements: limits.max_table_elements(),
+            violation: None,
+        }
+    }
+
+    pub(crate) fn violation(&self) -> Option<LimitViolation> {
+        self.violation
+    }
+}
+
+impl ResourceLimiter for FunctionLimiter {
+    fn memory_growing(
+        &mut self,
+        _current: usize,
+        desired: usize,
+        maximum: Option<usize>,
+    ) -> wasmtime::Result<bool> {
+        if self.max_memory_bytes.is_some_and(|max| desired > max) {
+            self.violation = Some(LimitViolation::Memory);
+            return Ok(false);
+        }
+        Ok(maximum.is_none_or(|max| desired <= max))
+    }
+
+    fn table_growing(
+        &mut self,
+        _current: usize,
+        desired: usize,
+        maximum: Option<usize>,
+    ) -> wasmtime::Result<bool> {
+        if self.max_table_elements.is_some_and(|max| desired > max) {
+            self.violation = Some(LimitViolation::TableElements);
+            return Ok(false);
+        }
+        Ok(maximum.is_none_or(|max| desired <= max))
+    }
+}
+
+/// Configures fuel, epoch yielding and the resource limiter of a fresh store
+pub(crate) fn apply_to_store(
+    store: &mut Store<ComponentState>,
+    limits: &ResourceLimits,
+) -> wasmtime::Result<()> {
+    store.limiter(|state| &mut state.limiter);
+    store.set_fuel(limits.fuel.unwrap_or(u64::MAX))?;
+    store.epoch_deadline_async_yield_and_update(1);
+    Ok(())
+}
+
+/// Runs a guest call while enforcing the wall-clock timeout of the store's limits
+pub(crate) async fn with_timeout<T>(
+    timeout: Option<std::time::Duration>,
+    call: impl std::future::Future<Output = wasmtime::Result<T>>,
+) -> Result<wasmtime::Result<T>, LimitViolation> {
+    match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, call)
+            .await
+            .map_err(|_| LimitViolation::Timeout),
+        None => Ok(call.await),
+    }
+}
+
+/// Attributes a failed guest call to the violated limit, if any
+pub(crate) fn violation_of(
+    store: &Store<ComponentState>,
+    error: &wasmtime::Error,
+) -> Option<LimitViolation> {
+    if let Some(violation) = store.data().limiter.violation() {
+        return Some(violation);
+    }
+
+    match error.downcast_ref::<wasmtime::Trap>() {
+        Some(wasmtime::Trap::OutOfFuel) => Some(LimitViolation::Fuel),
+        Some(wasmtime::Trap::Interrupt) => Some(LimitViolation::Timeout),
+        _ => None,
+    }
+}
+
+/// Whether reading a request body failed because it exceeded the maximum request size
+pub(crate) fn exceeds_body_limit(error: &(dyn std::error::Error + 'static)) -> bool {
+    std::iter::successors(Some(error), |error| error.source())
+        .any(|error| error.is::<http_body_util::LengthLimitError>())
+}
+
+/// Periodically increments the engine epoch, so running guests yield regularly
+pub(crate) fn spawn_epoch_ticker(engine: wasmtime::Engine) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(EPOCH_TICK);
+        loop {
+            interval.tick().await;
+            engine.increment_epoch();
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deny_memory_growth_beyond_limit() {
+        let mut limiter = FunctionLimiter::new(&ResourceLimits {
+            max_memory_mb: Some(1),
+            ..Default::default()
+        });
+
+        assert!(limiter.memory_growing(0, 1024 * 1024, None).unwrap());
+        assert_eq!(limiter.violation(), None);
+
+        assert!(!limiter.memory_growing(0, 2 * 1024 * 1024, None).unwrap());
+        assert_eq!(limiter.violation(), Some(LimitViolation::Memory));
+    }
+
+    #[test]
+    fn deny_table_growth_beyond_limit() {
+        let mut limiter = FunctionLimiter::new(&ResourceLimits {
+            max_table_elements: Some(10),
+            ..Default::default()
+        });
+
+        assert!(limiter.table_growing(0, 10, None).unwrap());
+        assert!(!limiter.table_growing(10, 11, None).unwrap());
+        assert_eq!(limiter.violation(), Some(LimitViolation::TableElements));
+    }
+
+    #[tokio::test]
+    async fn abort_calls_exceeding_the_timeout() {
+        let result = with_timeout(Some(std::time::Duration::from_millis(10)), async {
+            tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+            Ok(())
+        })
+        .await;
+
+        assert_eq!(result.unwrap_err(), LimitViolation::Timeout);
+    }
+
+    #[tokio::test]
+    async fn detect_bodies_exceeding_the_limit() {
+        let limited = |bytes: &'static [u8]| {
+            axum::body::Body::new(http_body_util::Limited::new(
+                axum::body::Body::from(bytes),
+                4,
+            ))
+        };
+
+        let err = axum::body::to_bytes(limited(b"too large"), usize::MAX)
+            .await
+            .unwrap_err();
+        assert!(exceeds_body_limit(&err));
+
+        let body = axum::body::to_bytes(limited(b"fits"), usize::MAX).await;
+        assert_eq!(body.unwrap(), "fits");
+        assert!(!exceeds_body_limit(&std::io::Error::other("reset")));
+    }
+}
@@ -0,0 +1,273 @@
+use std::sync::{Arc, Mutex};
+
+use bytes::Bytes;
+use wasmtime_wasi::{OutputStream, Pollable, StdoutStream, StreamError};
+
+use super::ComponentState;
+use crate::{
+    bindings_function_http::jontze::log::logging::{Field, Host, Level},
+    domain::log::{LogEntry, LogField, LogLevel, LogSource},
+};
+
+/// Lines captured per invocation, further lines are dropped
+pub(crate) const MAX_LINES_PER_INVOCATION: usize = 1000;
+/// Output without a line break is split into lines of this size
+const MAX_LINE_BYTES: usize = 16 * 1024;
+
+#[derive(Default)]
+struct CollectedLogs {
+    entries: Vec<LogEntry>,
+    dropped: usize,
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+}
+
+/// Collects the logs and output of a single invocation
+#[derive(Clone)]
+pub(crate) struct LogCollector {
+    function_id: uuid::Uuid,
+    request_id: Option<String>,
+    logs: Arc<Mutex<CollectedLogs>>,
+}
+
+impl LogCollector {
+    pub(crate) fn new(function_id: uuid::Uuid, request_id: Option<String>) -> Self {
+        Self {
+            function_id,
+            request_id,
+            logs: Arc::default(),
+        }
+    }
+
+    pub(crate) fn function_id(&self) -> uuid::Uuid {
+        self.function_id
+    }
+
+    pub(crate) fn stdout(&self) -> CapturedOutput {
+        CapturedOutput {
+            collector: self.clone(),
+            source: LogSource::Stdout,
+        }
+    }
+
+    pub(crate) fn stderr(&self) -> CapturedOutput {
+        CapturedOutput {
+            collector: self.clone(),
+            source: LogSource::Stderr,
+        }
+    }
+
+    pub(crate) fn log(&self, level: LogLevel, message: String, fields: Vec<LogField>) {
+        let mut logs = self.logs.lock().unwrap();
+        self.push(&mut logs, LogSource::Log, level, message, fields);
+    }
+
+    /// Flushes unterminated output and returns all collected lines
+    pub(crate) fn finish(&self) -> Vec<LogEntry> {
+        let mut logs = self.logs.lock().unwrap();
+
+        for source in [LogSource::Stdout, LogSource::Stderr] {
+            let line = std::mem::take(output_buffer(&mut logs, source));
+            if !line.is_empty() {
+                self.push_line(&mut logs, source, &line);
+            }
+        }
+
+        if logs.dropped > 0 {
+            // Bypasses the limit, so the truncation is always visible
+            let message = format!("{} log lines were dropped", logs.dropped);
+            logs.entries
+                .push(self.entry(LogSource::Log, LogLevel::Warn, message, vec![]));
+            logs.dropped = 0;
+        }
+
+        std::mem::take(&mut logs.entries)
+    }
+
+    fn write(&self, source: LogSource, bytes: &[u8]) {
+        let mut logs = self.logs.lock().unwrap();
+        let mut buffer = std::mem::take(output_buffer(&mut logs, source));
+        buffer.extend_from_slice(bytes);
+
+        let mut rest = buffer.as_slice();
+        while let Some(line_end) = rest.iter().position(|byte| *byte == b'\n') {
+            self.push_line(&mut logs, source, &rest[..line_end]);
+            rest = &rest[line_end + 1..];
+        }
+        while rest.len() >= MAX_LINE_BYTES {
+            self.push_line(&mut logs, source, &rest[..MAX_LINE_BYTES]);
+            rest = &rest[MAX_LINE_BYTES..];
+        }
+
+        *output_buffer(&mut logs, source) = rest.to_vec();
+    }
+
+    fn push_line(&self, logs: &mut CollectedLogs, source: LogSource, line: &[u8]) {
+        let line = String::from_utf8_lossy(line);
+        let level = match source {
+            LogSource::Stderr => LogLevel::Error,
+            LogSource::Log | LogSource::Stdout => LogLevel::Info,
+        };
+        self.push(
+            logs,
+            source,
+            level,
+            line.trim_end_matches('\r').to_string(),
+            vec![],
+        );
+    }
+
+    fn push(
+        &self,
+        logs: &mut CollectedLogs,
+        source: LogSource,
+        level: LogLevel,
+        message: String,
+        fields: Vec<LogField>,
+    ) {
+        if logs.entries.len() >= MAX_LINES_PER_INVOCATION {
+            logs.dropped += 1;
+            return;
+        }
+        logs.entries
+            .push(self.entry(source, level, message, fields));
+    }
+
+    fn entry(
+        &self,
+        source: LogSource,
+        level: LogLevel,
+        message: String,
+        fields: Vec<LogField>,
+    ) -> LogEntry {
+        LogEntry {
+            function_id: self.function_id,
+            request_id: self.request_id.clone(),
+            source,
+            level,
+            message,
+            fields,
+            timestamp: chrono::Utc::now(),
+        }
+    }
+}
+
+fn output_buffer(logs: &mut CollectedLogs, source: LogSource) -> &mut Vec<u8> {
+    match source {
+        LogSource::Stderr => &mut logs.stderr,
+        LogSource::Log | LogSource::Stdout => &mut logs.stdout,
+    }
+}
+
+/// WASI output stream that turns the written output into log lines
+#[derive(Clone)]
+pub(crate) struct CapturedOutput {
+    collector: LogCollector,
+    source: LogSource,
+}
+
+impl StdoutStream for CapturedOutput {
+    fn stream(&self) -> Box<dyn OutputStream> {
+        Box::new(self.clone())
+    }
+
+    fn isatty(&self) -> bool {
+        false
+    }
+}
+
+#[async_trait::async_trait]
+impl OutputStream for CapturedOutput {
+    fn write(&mut self, bytes: Bytes) -> Result<(), StreamError> {
+        self.collector.write(self.source, &bytes);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), StreamError> {
+        Ok(())
+    }
+
+    fn check_write(&mut self) -> Result<usize, StreamError> {
+        // Lines beyond the limit are dropped, so writing never blocks
+        Ok(MAX_LINE_BYTES)
+    }
+}
+
+#[async_trait::async_trait]
+impl Pollable for CapturedOutput {
+    async fn ready(&mut self) {}
+}
+
+impl From<Level> for LogLevel {
+    fn from(level: Level) -> Self {
+        match level {
+            Level::Trace => LogLevel::Trace,
+            Level::Debug => LogLevel::Debug,
+            Level::Info => LogLevel::Info,
+            Level::Warn => LogLevel::Warn,
+            Level::Error => LogLevel::Error,
+        }
+    }
+}
+
+impl Host for ComponentState {
+    async fn log(&mut self, level: Level, message: String, fields: Vec<Field>) {
+        if let Some(logs) = &self.logs {
+            logs.log(
+                level.into(),
+                message,
+                fields
+                    .into_iter()
+                    .map(|field| LogField {
+                        key: field.key,
+                        value: field.value,
+                    })
+                    .collect(),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_output_into_lines() {
+        let collector = LogCollector::new(uuid::Uuid::new_v4(), Some("1".to_string()));
+
+        collector.write(LogSource::Stdout, b"hello\nwor");
+        collector.write(LogSource::Stderr, b"failed\r\n");
+        collector.write(LogSource::Stdout, b"ld");
+
+        let entries = collector.finish();
+        let lines = entries
+            .iter()
+            .map(|entry| (entry.source, entry.message.as_str()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            lines,
+            vec![
+                (LogSource::Stdout, "hello"),
+                (LogSource::Stderr, "failed"),
+                (LogSource::Stdout, "world"),
+            ]
+        );
+        assert_eq!(entries[1].level, LogLevel::Error);
+        assert_eq!(entries[0].request_id.as_deref(), Some("1"));
+    }
+
+    #[test]
+    fn drop_lines_beyond_limit() {
+        let collector = LogCollector::new(uuid::Uuid::new_v4(), None);
+
+        for line in 0..MAX_LINES_PER_INVOCATION + 5 {
+            collector.log(LogLevel::Info, line.to_string(), vec![]);
+        }
+
+        let entries = collector.finish();
+        assert_eq!(entries.len(), MAX_LINES_PER_INVOCATION + 1);
+        assert_eq!(entries.last().unwrap().message, "5 log lines were dropped");
+    }
+}
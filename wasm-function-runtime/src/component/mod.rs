@@ -1,21 +1,79 @@
 use wasmtime_wasi::{IoView, ResourceTable, WasiCtx, WasiCtxBuilder, WasiView};
 
+use crate::{domain::limits::ResourceLimits, keyvalue::ScopedKeyValue};
+
+pub(crate) mod artifact;
+pub(crate) mod errors;
 pub(crate) mod http;
+pub(crate) mod http_streaming;
+pub(crate) mod keyvalue;
+pub(crate) mod limits;
+pub(crate) mod logs;
+pub(crate) mod pool;
 pub(crate) mod scheduled;
+pub(crate) mod validation;
+pub(crate) mod wasi_http;
 
-pub(crate) fn setup_engine() -> wasmtime::Engine {
+pub(crate) fn setup_engine(pooling_allocator: bool) -> wasmtime::Engine {
     let mut config = wasmtime::Config::new();
     config.wasm_backtrace_details(wasmtime::WasmBacktraceDetails::Enable);
     config.wasm_component_model(true);
     config.async_support(true);
+    // Required to enforce the per invocation fuel budget and to interrupt long running guests
+    config.consume_fuel(true);
+    config.epoch_interruption(true);
+    if pooling_allocator {
+        config.allocation_strategy(wasmtime::InstanceAllocationStrategy::pooling());
+    }
 
     wasmtime::Engine::new(&config).expect("Failed to create engine")
 }
 
+/// Maps scope variables to the environment variables exposed to functions
+pub(crate) fn variable_envs(vars: &[crate::domain::variable::Variable]) -> Vec<(String, String)> {
+    vars.iter()
+        .map(|v| (format!("VAR_{}", v.name), v.value.clone()))
+        .collect()
+}
+
+/// Maps scope secrets to the environment variables exposed to functions
+pub(crate) fn secret_envs(secrets: &[crate::domain::secret::SecretValue]) -> Vec<(String, String)> {
+    secrets
+        .iter()
+        .map(|secret| (format!("SECRET_{}", secret.name), secret.value.clone()))
+        .collect()
+}
+
+/// Creates a linker providing WASI, outbound WASI HTTP, the key-value store, logging
+/// and sending streamed responses to components
+pub(crate) fn setup_linker(
+    engine: &wasmtime::Engine,
+) -> wasmtime::Result<wasmtime::component::Linker<ComponentState>> {
+    let mut linker = wasmtime::component::Linker::new(engine);
+    wasmtime_wasi::add_to_linker_async(&mut linker)?;
+    wasmtime_wasi_http::add_only_http_to_linker_async(&mut linker)?;
+    crate::bindings_function_http::jontze::keyvalue::store::add_to_linker(&mut linker, |state| {
+        state
+    })?;
+    crate::bindings_function_http::jontze::log::logging::add_to_linker(&mut linker, |state| state)?;
+    crate::bindings_function_http_streaming::FunctionHttpStreaming::add_to_linker_imports_get_host(
+        &mut linker,
+        component_state,
+    )?;
+    Ok(linker)
+}
+
+fn component_state(state: &mut ComponentState) -> &mut ComponentState {
+    state
+}
+
 pub(crate) struct ComponentStateBuilder {
     ctx: WasiCtxBuilder,
     http_ctx: wasmtime_wasi_http::WasiHttpCtx,
     table: ResourceTable,
+    limits: ResourceLimits,
+    key_value: Option<ScopedKeyValue>,
+    logs: Option<logs::LogCollector>,
 }
 
 impl ComponentStateBuilder {
@@ -24,9 +82,29 @@ impl ComponentStateBuilder {
             ctx: WasiCtxBuilder::new(),
             http_ctx: wasmtime_wasi_http::WasiHttpCtx::new(),
             table: ResourceTable::new(),
+            limits: ResourceLimits::default(),
+            key_value: None,
+            logs: None,
         }
     }
 
+    pub fn with_limits(&mut self, limits: &ResourceLimits) -> &mut Self {
+        self.limits = limits.clone();
+        self
+    }
+
+    pub fn with_key_value(&mut self, key_value: ScopedKeyValue) -> &mut Self {
+        self.key_value = Some(key_value);
+        self
+    }
+
+    /// Captures stdout, stderr and the logging interface of the function
+    pub fn with_log_collector(&mut self, logs: logs::LogCollector) -> &mut Self {
+        self.ctx.stdout(logs.stdout()).stderr(logs.stderr());
+        self.logs = Some(logs);
+        self
+    }
+
     pub fn with_envs(&mut self, env: &[(impl AsRef<str>, impl AsRef<str>)]) -> &mut Self {
         self.ctx.envs(env);
         self
@@ -39,6 +117,12 @@ impl ComponentStateBuilder {
             ctx,
             http_ctx: self.http_ctx,
             table: self.table,
+            limiter: limits::FunctionLimiter::new(&self.limits),
+            limits: self.limits,
+            key_value: self.key_value,
+            logs: self.logs,
+            streaming_response: None,
+            exceeded_body_limit: Default::default(),
         }
     }
 }
@@ -47,6 +131,20 @@ pub(crate) struct ComponentState {
     ctx: WasiCtx,
     http_ctx: wasmtime_wasi_http::WasiHttpCtx,
     table: ResourceTable,
+    limiter: limits::FunctionLimiter,
+    limits: ResourceLimits,
+    key_value: Option<ScopedKeyValue>,
+    logs: Option<logs::LogCollector>,
+    /// Set while a streaming HTTP function hasn't sent its response yet
+    streaming_response: Option<http_streaming::ResponseSender>,
+    /// The maximum request size, once reading the streamed request body exceeded it
+    exceeded_body_limit: std::sync::Arc<std::sync::OnceLock<usize>>,
+}
+
+impl ComponentState {
+    pub(crate) fn limits(&self) -> &ResourceLimits {
+        &self.limits
+    }
 }
 
 impl IoView for ComponentState {
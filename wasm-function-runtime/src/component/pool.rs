@@ -0,0 +1,107 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+use super::http::PreparedFunctionHttp;
+
+/// Identifies a pooled function by its id and the hash of the deployed binary,
+/// so a redeploy never serves the previous binary
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct InstancePoolKey {
+    pub function_id: uuid::Uuid,
+    pub content_hash: String,
+}
+
+#[derive(Clone)]
+struct PooledFunction {
+    scope: String,
+    function: Arc<PreparedFunctionHttp>,
+}
+
+/// Keeps linked HTTP functions of recently called functions in memory,
+/// so hot functions skip compilation and linking on every request
+#[derive(Clone)]
+pub(crate) struct InstancePool {
+    functions: moka::future::Cache<InstancePoolKey, PooledFunction>,
+    /// Advances with every invalidation
+    generation: Arc<AtomicU64>,
+}
+
+impl InstancePool {
+    pub(crate) fn new(max_capacity: u64) -> Self {
+        let functions = moka::future::Cache::builder()
+            .max_capacity(max_capacity)
+            .time_to_idle(std::time::Duration::from_secs(
+                60 * 30, /* 30 minutes without a request */
+            ))
+            .support_invalidation_closures()
+            .build();
+
+        Self {
+            functions,
+            generation: Arc::default(),
+        }
+    }
+
+    /// Read before loading the variables and secrets a function is linked with,
+    /// so a function linked with the ones of before an invalidation isn't pooled
+    pub(crate) fn generation(&self) -> u64 {
+        self.generation.load(Ordering::SeqCst)
+    }
+
+    pub(crate) async fn get(&self, key: &InstancePoolKey) -> Option<Arc<PreparedFunctionHttp>> {
+        self.functions.get(key).await.map(|pooled| pooled.function)
+    }
+
+    pub(crate) async fn insert(
+        &self,
+        key: InstancePoolKey,
+        scope: &str,
+        function: Arc<PreparedFunctionHttp>,
+        generation: u64,
+    ) {
+        self.functions
+            .insert(
+                key.clone(),
+                PooledFunction {
+                    scope: scope.to_string(),
+                    function,
+                },
+            )
+            .await;
+
+        // An invalidation since the function was loaded may have run before the insert
+        if self.generation() != generation {
+            self.functions.invalidate(&key).await;
+        }
+    }
+
+    /// Drops every pooled binary of the function, e.g. after a redeploy or delete
+    pub(crate) fn invalidate_function(&self, function_id: uuid::Uuid) {
+        self.invalidate_if(move |key, _| key.function_id == function_id);
+    }
+
+    /// Drops all functions of the scope, e.g. after its variables changed
+    pub(crate) fn invalidate_scope(&self, scope: &str) {
+        let scope = scope.to_string();
+        self.invalidate_if(move |_, pooled| pooled.scope == scope);
+    }
+
+    /// Drops all pooled functions, e.g. after events of other replicas were missed
+    pub(crate) fn invalidate_all(&self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        self.functions.invalidate_all();
+    }
+
+    fn invalidate_if(
+        &self,
+        predicate: impl Fn(&InstancePoolKey, &PooledFunction) -> bool + Send + Sync + 'static,
+    ) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        // Only fails if invalidation closures are not supported, which they are
+        if let Err(err) = self.functions.invalidate_entries_if(predicate) {
+            tracing::error!("Failed to invalidate pooled functions: {err}");
+        }
+    }
+}
@@ -1,10 +1,119 @@
 use wasmtime::{
-    component::{Component, Linker},
+    component::{
+        types::{ComponentItem, Type},
+        Component, InstancePre, Linker,
+    },
     Engine, Store,
 };
 
-use super::{ComponentState, ComponentStateBuilder};
-use crate::{bindings_function_scheduled, domain};
+use super::{errors::GuestError, ComponentState, ComponentStateBuilder};
+use crate::{
+    bindings_function_scheduled, bindings_function_scheduled_v0_1,
+    bindings_function_scheduled_v0_2, domain, keyvalue::ScopedKeyValue,
+};
+
+/// A scheduled function, built against the current world or one it replaced
+pub(crate) enum FunctionScheduled {
+    Current(bindings_function_scheduled::FunctionScheduled),
+    /// Built against `function-scheduled@0.2.0`, its job runs without context
+    V0_2(bindings_function_scheduled_v0_2::FunctionScheduled),
+    /// Built against the unversioned world, its job runs without context and fails without describing why
+    V0_1(bindings_function_scheduled_v0_1::FunctionScheduled),
+}
+
+/// The world of the job the component exports.
+/// All worlds export `run-job`, only its signature tells them apart.
+enum JobWorld {
+    Current,
+    V0_2,
+    V0_1,
+}
+
+impl JobWorld {
+    fn of(engine: &Engine, component: &Component) -> Self {
+        let Some(ComponentItem::ComponentFunc(run_job)) =
+            component.component_type().get_export(engine, "run-job")
+        else {
+            return Self::Current;
+        };
+        if run_job.params().len() > 0 {
+            return Self::Current;
+        }
+        // Jobs of the unversioned world fail without a `function-error`
+        if run_job
+            .results()
+            .any(|result| matches!(result, Type::Result(result) if result.err().is_none()))
+        {
+            Self::V0_1
+        } else {
+            Self::V0_2
+        }
+    }
+}
+
+/// A scheduled function ready to be instantiated, in the world its component was built against
+pub(crate) enum FunctionScheduledPre {
+    Current(bindings_function_scheduled::FunctionScheduledPre<ComponentState>),
+    V0_2(bindings_function_scheduled_v0_2::FunctionScheduledPre<ComponentState>),
+    V0_1(bindings_function_scheduled_v0_1::FunctionScheduledPre<ComponentState>),
+}
+
+impl FunctionScheduledPre {
+    /// Checks that the component exports the job of one of the supported worlds
+    pub(crate) fn new(
+        engine: &Engine,
+        component: &Component,
+        instance_pre: InstancePre<ComponentState>,
+    ) -> wasmtime::Result<Self> {
+        // Components deployed before the job context keep running without it
+        Ok(match JobWorld::of(engine, component) {
+            JobWorld::Current => Self::Current(
+                bindings_function_scheduled::FunctionScheduledPre::new(instance_pre)?,
+            ),
+            JobWorld::V0_2 => Self::V0_2(
+                bindings_function_scheduled_v0_2::FunctionScheduledPre::new(instance_pre)?,
+            ),
+            JobWorld::V0_1 => Self::V0_1(
+                bindings_function_scheduled_v0_1::FunctionScheduledPre::new(instance_pre)?,
+            ),
+        })
+    }
+
+    async fn instantiate_async(
+        &self,
+        store: &mut Store<ComponentState>,
+    ) -> wasmtime::Result<FunctionScheduled> {
+        Ok(match self {
+            Self::Current(pre) => FunctionScheduled::Current(pre.instantiate_async(store).await?),
+            Self::V0_2(pre) => FunctionScheduled::V0_2(pre.instantiate_async(store).await?),
+            Self::V0_1(pre) => FunctionScheduled::V0_1(pre.instantiate_async(store).await?),
+        })
+    }
+}
+
+impl FunctionScheduled {
+    /// Runs the job, functions of older worlds get as much of the context as their export takes
+    pub(crate) async fn call_run_job(
+        &self,
+        store: &mut Store<ComponentState>,
+        context: &bindings_function_scheduled::JobContext,
+    ) -> wasmtime::Result<Result<(), GuestError>> {
+        match self {
+            Self::Current(function) => Ok(function
+                .call_run_job(store, context)
+                .await?
+                .map_err(GuestError::from)),
+            Self::V0_2(function) => Ok(function
+                .call_run_job(store)
+                .await?
+                .map_err(GuestError::from)),
+            Self::V0_1(function) => Ok(function
+                .call_run_job(store)
+                .await?
+                .map_err(|()| GuestError::undescribed())),
+        }
+    }
+}
 
 pub(crate) struct FunctionScheduledBuilder<'a> {
     state_builder: ComponentStateBuilder,
@@ -14,75 +123,151 @@ pub(crate) struct FunctionScheduledBuilder<'a> {
 }
 
 impl<'a> FunctionScheduledBuilder<'a> {
-    pub fn from_binary(engine: &'a Engine, bytes: &[u8]) -> Self {
+    pub fn from_component(engine: &'a Engine, component: Component) -> wasmtime::Result<Self> {
         let state_builder = ComponentStateBuilder::new();
 
-        let component = Component::from_binary(engine, bytes).expect("Failed to create component");
+        let linker: Linker<ComponentState> = super::setup_linker(engine)?;
 
-        let mut linker: Linker<ComponentState> = Linker::new(engine);
-        wasmtime_wasi::add_to_linker_async(&mut linker).expect("Failed to add WASI to linker");
-        wasmtime_wasi_http::add_only_http_to_linker_async(&mut linker)
-            .expect("Failed to add WASI HTTP to linker");
-
-        Self {
+        Ok(Self {
             state_builder,
             component,
             linker,
             engine,
-        }
+        })
     }
 
     pub fn with_variables(mut self, vars: &[domain::variable::Variable]) -> Self {
-        let vars = vars
-            .iter()
-            .map(|v| (format!("VAR_{}", v.name), &v.value))
-            .collect::<Vec<(String, &String)>>();
-        self.state_builder.with_envs(&vars);
+        self.state_builder.with_envs(&super::variable_envs(vars));
         self
     }
 
-    pub fn serialize(&self) -> Vec<u8> {
-        self.component
-            .serialize()
-            .expect("Failed to serialize component")
+    pub fn with_secrets(mut self, secrets: &[domain::secret::SecretValue]) -> Self {
+        self.state_builder.with_envs(&super::secret_envs(secrets));
+        self
     }
 
-    pub unsafe fn deserialize(engine: &'a Engine, bytes: &[u8]) -> Self {
-        let state_builder = ComponentStateBuilder::new();
-
-        let component =
-            Component::deserialize(engine, bytes).expect("Failed to deserialize component");
+    pub fn with_key_value(mut self, key_value: ScopedKeyValue) -> Self {
+        self.state_builder.with_key_value(key_value);
+        self
+    }
 
-        let mut linker: Linker<ComponentState> = Linker::new(engine);
-        wasmtime_wasi::add_to_linker_async(&mut linker).expect("Failed to add WASI to linker");
-        wasmtime_wasi_http::add_only_http_to_linker_async(&mut linker)
-            .expect("Failed to add WASI HTTP to linker");
+    pub fn with_log_collector(mut self, logs: super::logs::LogCollector) -> Self {
+        self.state_builder.with_log_collector(logs);
+        self
+    }
 
-        Self {
-            state_builder,
-            component,
-            linker,
-            engine,
-        }
+    pub fn with_limits(mut self, limits: &domain::limits::ResourceLimits) -> Self {
+        self.state_builder.with_limits(limits);
+        self
     }
 
-    pub async fn build(
-        self,
-    ) -> (
-        bindings_function_scheduled::FunctionScheduled,
-        Store<ComponentState>,
-    ) {
+    pub async fn build(self) -> wasmtime::Result<(FunctionScheduled, Store<ComponentState>)> {
         let component_state = self.state_builder.build();
+        let limits = component_state.limits().clone();
         let mut store = Store::new(self.engine, component_state);
+        super::limits::apply_to_store(&mut store, &limits)?;
+
+        let instance_pre = self.linker.instantiate_pre(&self.component)?;
+        let func_instance = FunctionScheduledPre::new(self.engine, &self.component, instance_pre)?
+            .instantiate_async(&mut store)
+            .await?;
+
+        Ok((func_instance, store))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{component::validation, domain::manifest::FuncKind};
+
+    /// Implements `function-scheduled@0.2.0`, its job succeeds without taking a context
+    const V0_2_COMPONENT: &str = r#"(component
+        (core module $job
+            (memory (export "memory") 1)
+            ;; Points at zeroed memory, which reads as the ok case
+            (func (export "run-job") (result i32) i32.const 0)
+        )
+        (core instance $job (instantiate $job))
+        (type $function-error (record
+            (field "code" string)
+            (field "message" string)
+            (field "status" (option u16))
+            (field "retryable" (option bool))
+        ))
+        (export $exported-error "function-error" (type $function-error))
+        (func $run-job (result (result (error $exported-error)))
+            (canon lift (core func $job "run-job") (memory $job "memory"))
+        )
+        (export "run-job" (func $run-job))
+    )"#;
 
-        let func_instance = bindings_function_scheduled::FunctionScheduled::instantiate_async(
-            &mut store,
-            &self.component,
-            &self.linker,
+    /// Implements the unversioned `function-scheduled` world, its job fails without details
+    const V0_1_COMPONENT: &str = r#"(component
+        (core module $job
+            (func (export "run-job") (result i32) i32.const 1)
         )
-        .await
-        .expect("Failed to instantiate component");
+        (core instance $job (instantiate $job))
+        (func $run-job (result (result))
+            (canon lift (core func $job "run-job"))
+        )
+        (export "run-job" (func $run-job))
+    )"#;
+
+    fn job_context() -> bindings_function_scheduled::JobContext {
+        bindings_function_scheduled::JobContext {
+            run_id: uuid::Uuid::new_v4().to_string(),
+            function_name: "legacy".to_string(),
+            scope: "example".to_string(),
+            scheduled_at_ms: 0,
+            started_at_ms: 0,
+            attempt: 1,
+            manual: true,
+        }
+    }
+
+    #[tokio::test]
+    async fn run_component_of_previous_world() {
+        let engine = super::super::setup_engine(false);
+        let bytes = wat::parse_str(V0_2_COMPONENT).unwrap();
+
+        // Redeploys of the unchanged binary pass validation
+        let component = validation::validate_component(&engine, &FuncKind::Scheduled, &bytes)
+            .expect("component of function-scheduled@0.2.0 is valid");
+
+        let (function, mut store) = FunctionScheduledBuilder::from_component(&engine, component)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        assert!(matches!(function, FunctionScheduled::V0_2(_)));
+
+        let result = function
+            .call_run_job(&mut store, &job_context())
+            .await
+            .unwrap();
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn run_component_of_unversioned_world() {
+        let engine = super::super::setup_engine(false);
+        let bytes = wat::parse_str(V0_1_COMPONENT).unwrap();
+
+        let component = validation::validate_component(&engine, &FuncKind::Scheduled, &bytes)
+            .expect("component of the unversioned function-scheduled world is valid");
+
+        let (function, mut store) = FunctionScheduledBuilder::from_component(&engine, component)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        assert!(matches!(function, FunctionScheduled::V0_1(_)));
 
-        (func_instance, store)
+        let result = function
+            .call_run_job(&mut store, &job_context())
+            .await
+            .unwrap();
+        assert_eq!(result, Err(GuestError::undescribed()));
     }
 }
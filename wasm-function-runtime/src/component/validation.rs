@@ -0,0 +1,168 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use thiserror::Error;
+use wasmtime::{component::Component, Engine};
+use wasmtime_wasi_http::bindings::ProxyPre;
+
+use crate::{
+    bindings_function_http_streaming::FunctionHttpStreamingPre,
+    component::{http::FunctionHttpPre, scheduled::FunctionScheduledPre},
+    domain::manifest::FuncKind,
+    utils::ErrorResponse,
+};
+
+/// Reasons an uploaded binary can't run as the trigger declared in its manifest
+#[derive(Debug, Error)]
+pub(crate) enum ValidationError {
+    #[error("The uploaded file is not a valid WebAssembly component: {0:#}")]
+    InvalidComponent(#[source] wasmtime::Error),
+    #[error("The component imports interfaces the runtime doesn't provide: {0:#}")]
+    UnsupportedImport(#[source] wasmtime::Error),
+    #[error("The component doesn't implement the '{world}' world: {error:#}")]
+    WorldMismatch {
+        world: &'static str,
+        #[source]
+        error: wasmtime::Error,
+    },
+    #[error("Failed to set up the linker")]
+    Linker(#[source] wasmtime::Error),
+    #[error("Validation of the component was aborted")]
+    Aborted(#[source] tokio::task::JoinError),
+}
+
+impl IntoResponse for ValidationError {
+    fn into_response(self) -> Response {
+        let status_code = match self {
+            ValidationError::Linker(_) | ValidationError::Aborted(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+            _ => StatusCode::UNPROCESSABLE_ENTITY,
+        };
+        (
+            status_code,
+            Json(ErrorResponse {
+                message: &self.to_string(),
+            }),
+        )
+            .into_response()
+    }
+}
+
+/// Validates the component on a blocking thread, compiling it would stall the async workers
+pub(crate) async fn validate_component_blocking(
+    engine: Engine,
+    trigger: FuncKind,
+    bytes: bytes::Bytes,
+) -> Result<Component, ValidationError> {
+    tokio::task::spawn_blocking(move || validate_component(&engine, &trigger, &bytes))
+        .await
+        .map_err(ValidationError::Aborted)?
+}
+
+/// Compiles the component and type-checks its imports and exports against the world of the trigger.
+/// Returns the compiled component, so it doesn't need to be compiled again on the first invocation.
+pub(crate) fn validate_component(
+    engine: &Engine,
+    trigger: &FuncKind,
+    bytes: &[u8],
+) -> Result<Component, ValidationError> {
+    let component =
+        Component::from_binary(engine, bytes).map_err(ValidationError::InvalidComponent)?;
+
+    // Linking fails for imports without a host implementation
+    let linker = super::setup_linker(engine).map_err(ValidationError::Linker)?;
+    let instance_pre = linker
+        .instantiate_pre(&component)
+        .map_err(ValidationError::UnsupportedImport)?;
+
+    // The typed bindings check that the exports match the world
+    match trigger {
+        // Binaries of previous worlds are still accepted, redeploys of them must not fail
+        FuncKind::Http => FunctionHttpPre::new(engine, &component, instance_pre)
+            .map(|_| ())
+            .map_err(|error| ValidationError::WorldMismatch {
+                world: "function-http",
+                error,
+            })?,
+        FuncKind::Scheduled => FunctionScheduledPre::new(engine, &component, instance_pre)
+            .map(|_| ())
+            .map_err(|error| ValidationError::WorldMismatch {
+                world: "function-scheduled",
+                error,
+            })?,
+        FuncKind::HttpStreaming => FunctionHttpStreamingPre::new(instance_pre)
+            .map(|_| ())
+            .map_err(|error| ValidationError::WorldMismatch {
+                world: "function-http-streaming",
+                error,
+            })?,
+        FuncKind::WasiHttp => ProxyPre::new(instance_pre).map(|_| ()).map_err(|error| {
+            ValidationError::WorldMismatch {
+                world: "wasi:http/proxy",
+                error,
+            }
+        })?,
+    }
+
+    Ok(component)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reject_invalid_binary() {
+        let engine = super::super::setup_engine(false);
+
+        let result = validate_component(&engine, &FuncKind::Http, b"not a component");
+
+        assert!(matches!(result, Err(ValidationError::InvalidComponent(_))));
+    }
+
+    #[test]
+    fn reject_component_without_world_exports() {
+        let engine = super::super::setup_engine(false);
+        let empty_component = wat::parse_str("(component)").unwrap();
+
+        let result = validate_component(&engine, &FuncKind::Scheduled, &empty_component);
+
+        assert!(matches!(
+            result,
+            Err(ValidationError::WorldMismatch {
+                world: "function-scheduled",
+                ..
+            })
+        ));
+
+        let result = validate_component(&engine, &FuncKind::WasiHttp, &empty_component);
+
+        assert!(matches!(
+            result,
+            Err(ValidationError::WorldMismatch {
+                world: "wasi:http/proxy",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn reject_unsupported_imports() {
+        let engine = super::super::setup_engine(false);
+        let importing_component = wat::parse_str(
+            r#"(component
+                (import "acme:unknown/api" (instance
+                    (export "call" (func))
+                ))
+            )"#,
+        )
+        .unwrap();
+
+        let result = validate_component(&engine, &FuncKind::Http, &importing_component);
+
+        assert!(matches!(result, Err(ValidationError::UnsupportedImport(_))));
+    }
+}
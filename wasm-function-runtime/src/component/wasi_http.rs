@@ -0,0 +1,144 @@
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use axum::{body::Body, response::Response};
+use bytes::Bytes;
+use http_body::Frame;
+use http_body_util::BodyExt;
+use sync_wrapper::SyncWrapper;
+use tokio::sync::oneshot;
+use wasmtime::{component::Resource, Store};
+use wasmtime_wasi::IoView;
+use wasmtime_wasi_http::{
+    bindings::{http::types::ErrorCode, Proxy},
+    body::HostIncomingBody,
+    types::{HostIncomingRequest, HostResponseOutparam},
+    WasiHttpView,
+};
+
+use super::{errors::InvocationError, limits, ComponentState};
+
+/// How long the function waits for the next chunk of the request body before it fails to read it
+const BETWEEN_BYTES_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(600);
+
+/// Passes the request to the `wasi:http/incoming-handler` of the function.
+/// Resolves as soon as the function set the response, whose body streams while the function keeps running.
+/// `finished` is called once the function returned, e.g. to persist its logs.
+pub(crate) async fn serve(
+    proxy: Proxy,
+    mut store: Store<ComponentState>,
+    request: http::Request<Body>,
+    finished: impl FnOnce() + Send + 'static,
+) -> Result<Response, InvocationError> {
+    let (sender, receiver) = oneshot::channel();
+    let incoming_request = incoming_request(&mut store, request)?;
+    let response_outparam = response_outparam(&mut store, sender)?;
+
+    let timeout = store.data().limits().timeout();
+    let call = tokio::spawn(async move {
+        let call_result = limits::with_timeout(
+            timeout,
+            proxy.wasi_http_incoming_handler().call_handle(
+                &mut store,
+                incoming_request,
+                response_outparam,
+            ),
+        )
+        .await;
+        finished();
+
+        call_result
+            .map_err(InvocationError::from)
+            .and_then(|call_result| {
+                call_result.map_err(|err| InvocationError::from_call_error(&store, err))
+            })
+    });
+
+    match receiver.await {
+        Ok(Ok(response)) => {
+            // The response is on its way, later failures can only cut its body short
+            tokio::spawn(async move {
+                if let Ok(Err(err)) = call.await {
+                    err.log();
+                }
+            });
+            Ok(response.map(|body| Body::new(body.map_err(ResponseBodyError))))
+        }
+        Ok(Err(error_code)) => Err(InvocationError::Guest(error_code.into())),
+        // The function dropped the response without setting it, most likely it failed
+        Err(_) => match call.await {
+            Ok(Err(err)) => Err(err),
+            Ok(Ok(())) => Err(InvocationError::InvalidResponse(
+                "the function returned without setting a response".to_string(),
+            )),
+            Err(err) => Err(InvocationError::Trap(err.into())),
+        },
+    }
+}
+
+fn incoming_request(
+    store: &mut Store<ComponentState>,
+    request: http::Request<Body>,
+) -> Result<Resource<HostIncomingRequest>, InvocationError> {
+    let (parts, body) = request.into_parts();
+    let body = HostIncomingBody::new(
+        SyncBody(SyncWrapper::new(body)).boxed(),
+        BETWEEN_BYTES_TIMEOUT,
+    );
+
+    let state = store.data_mut();
+    let incoming_request = HostIncomingRequest::new(
+        state,
+        parts,
+        wasmtime_wasi_http::bindings::http::types::Scheme::Http,
+        Some(body),
+    )
+    .map_err(InvocationError::Component)?;
+    state
+        .table()
+        .push(incoming_request)
+        .map_err(|err| InvocationError::Component(err.into()))
+}
+
+fn response_outparam(
+    store: &mut Store<ComponentState>,
+    sender: oneshot::Sender<
+        Result<http::Response<wasmtime_wasi_http::body::HyperOutgoingBody>, ErrorCode>,
+    >,
+) -> Result<Resource<HostResponseOutparam>, InvocationError> {
+    store
+        .data_mut()
+        .new_response_outparam(sender)
+        .map_err(InvocationError::Component)
+}
+
+/// The incoming body of `wasi:http` must be shareable between threads, axum's request body isn't.
+/// Only the function polls the body, so it is never accessed from two threads at once.
+struct SyncBody(SyncWrapper<Body>);
+
+impl http_body::Body for SyncBody {
+    type Data = Bytes;
+    type Error = ErrorCode;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        Pin::new(self.get_mut().0.get_mut())
+            .poll_frame(cx)
+            .map_err(|err| {
+                if limits::exceeds_body_limit(&err) {
+                    ErrorCode::HttpRequestBodySize(None)
+                } else {
+                    ErrorCode::InternalError(Some(err.to_string()))
+                }
+            })
+    }
+}
+
+/// A function failed to write the body of its response
+#[derive(Debug, thiserror::Error)]
+#[error("Function failed to write the response body: {0:?}")]
+struct ResponseBodyError(ErrorCode);
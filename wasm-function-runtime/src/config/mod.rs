@@ -5,6 +5,21 @@ pub(crate) struct AppConfig {
     pub azure_storage: Option<AzureStorageConfig>,
     pub hetzner_storage: Option<HetznerStorageConfig>,
     pub redis_cache: Option<RedisCacheConfig>,
+    pub function_limits: crate::domain::limits::ResourceLimits,
+    /// Requests to HTTP functions with a larger body are rejected with 413
+    pub max_request_body_bytes: usize,
+    pub pooling_allocator: bool,
+    pub secret_encryption: SecretEncryptionConfig,
+    /// Revisions kept per function, older ones are removed on deploy
+    pub revision_limit: u64,
+    /// Store compiled functions as `.cwasm` in the storage backend, so they survive restarts
+    pub persist_artifacts: bool,
+    /// Runs kept per scheduled function, unless its manifest overrides it
+    pub run_history_limit: u64,
+    /// Elect a single replica to run scheduled functions
+    pub leader_election: bool,
+    /// Time after which another replica takes over from a leader that stopped renewing its lease
+    pub lease_ttl_secs: u64,
 }
 
 pub(crate) struct OpenIdConnectConfig {
@@ -31,6 +46,63 @@ impl Loader for OpenIdConnectConfig {
     }
 }
 
+pub(crate) struct SecretEncryptionConfig {
+    /// Base64 encoded 32 byte key used to encrypt secrets, without it secrets are disabled
+    pub key: Option<String>,
+    /// Keys that were used before, secrets encrypted with them are rotated to the current key
+    pub previous_keys: Vec<String>,
+}
+
+impl Loader for SecretEncryptionConfig {
+    fn load() -> Self {
+        let key = std::env::var("SECRET_ENCRYPTION_KEY")
+            .ok()
+            .filter(|key| !key.trim().is_empty());
+        let previous_keys = std::env::var("SECRET_ENCRYPTION_PREVIOUS_KEYS")
+            .map(|keys| {
+                keys.split(',')
+                    .map(str::trim)
+                    .filter(|key| !key.is_empty())
+                    .map(str::to_owned)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self { key, previous_keys }
+    }
+}
+
+const DEFAULT_FUNCTION_TIMEOUT_SECS: u64 = 30;
+const DEFAULT_FUNCTION_MAX_MEMORY_MB: u64 = 256;
+const DEFAULT_LEASE_TTL_SECS: u64 = 15;
+const DEFAULT_MAX_REQUEST_BODY_MB: usize = 10;
+
+impl Loader for crate::domain::limits::ResourceLimits {
+    fn load() -> Self {
+        let parse_env = |key: &str| {
+            std::env::var(key)
+                .ok()
+                .filter(|value| !value.is_empty())
+                .map(|value| {
+                    value
+                        .parse::<u64>()
+                        .unwrap_or_else(|_| panic!("{key} is invalid"))
+                })
+        };
+
+        Self {
+            timeout_secs: Some(
+                parse_env("FUNCTION_TIMEOUT_SECS").unwrap_or(DEFAULT_FUNCTION_TIMEOUT_SECS),
+            ),
+            fuel: parse_env("FUNCTION_FUEL"),
+            max_memory_mb: Some(
+                parse_env("FUNCTION_MAX_MEMORY_MB").unwrap_or(DEFAULT_FUNCTION_MAX_MEMORY_MB),
+            ),
+            max_table_elements: parse_env("FUNCTION_MAX_TABLE_ELEMENTS"),
+        }
+    }
+}
+
 struct MinioStorageConfigBuilder {
     endpoint: Option<String>,
     access_key: Option<String>,
@@ -288,6 +360,64 @@ impl Loader for AppConfig {
         }
         let redis_cache = redis_cache_config_builder.build();
 
+        // Preallocate instance memories in a pool instead of mapping them per request
+        let pooling_allocator = std::env::var("FUNCTION_POOLING_ALLOCATOR")
+            .is_ok_and(|value| value == "true" || value == "1");
+
+        let persist_artifacts = std::env::var("FUNCTION_PERSIST_ARTIFACTS")
+            .is_ok_and(|value| value == "true" || value == "1");
+
+        let revision_limit = std::env::var("FUNCTION_REVISION_LIMIT")
+            .ok()
+            .filter(|value| !value.is_empty())
+            .map(|value| {
+                value
+                    .parse::<u64>()
+                    .ok()
+                    .filter(|limit| *limit > 0)
+                    .expect("FUNCTION_REVISION_LIMIT is invalid")
+            })
+            .unwrap_or(crate::services::revision_service::DEFAULT_REVISION_LIMIT);
+
+        let run_history_limit = std::env::var("FUNCTION_RUN_HISTORY_LIMIT")
+            .ok()
+            .filter(|value| !value.is_empty())
+            .map(|value| {
+                value
+                    .parse::<u64>()
+                    .ok()
+                    .filter(|limit| *limit > 0)
+                    .expect("FUNCTION_RUN_HISTORY_LIMIT is invalid")
+            })
+            .unwrap_or(crate::services::run_service::DEFAULT_RUN_HISTORY_LIMIT);
+
+        let max_request_body_bytes = std::env::var("FUNCTION_MAX_REQUEST_BODY_MB")
+            .ok()
+            .filter(|value| !value.is_empty())
+            .map(|value| {
+                value
+                    .parse::<usize>()
+                    .expect("FUNCTION_MAX_REQUEST_BODY_MB is invalid")
+            })
+            .unwrap_or(DEFAULT_MAX_REQUEST_BODY_MB)
+            .saturating_mul(1024 * 1024);
+
+        // Replicas share the schedule, so only one of them runs it unless disabled for single nodes
+        let leader_election = std::env::var("SCHEDULER_LEADER_ELECTION")
+            .map_or(true, |value| value != "false" && value != "0");
+
+        let lease_ttl_secs = std::env::var("SCHEDULER_LEASE_TTL_SECS")
+            .ok()
+            .filter(|value| !value.is_empty())
+            .map(|value| {
+                value
+                    .parse::<u64>()
+                    .ok()
+                    .filter(|ttl| *ttl > 0)
+                    .expect("SCHEDULER_LEASE_TTL_SECS is invalid")
+            })
+            .unwrap_or(DEFAULT_LEASE_TTL_SECS);
+
         Self {
             local_storage_dir,
             openid_connect: OpenIdConnectConfig::load(),
@@ -295,6 +425,15 @@ impl Loader for AppConfig {
             azure_storage,
             hetzner_storage,
             redis_cache,
+            function_limits: crate::domain::limits::ResourceLimits::load(),
+            max_request_body_bytes,
+            pooling_allocator,
+            secret_encryption: SecretEncryptionConfig::load(),
+            revision_limit,
+            persist_artifacts,
+            run_history_limit,
+            leader_election,
+            lease_ttl_secs,
         }
     }
 }
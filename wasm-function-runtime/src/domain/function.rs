@@ -2,6 +2,8 @@ use serde::Serialize;
 use sha2::Digest;
 use uuid::Uuid;
 
+use super::{http_world::HttpWorld, limits::ResourceLimits};
+
 pub(crate) trait WasmFunctionTrait {
     fn uuid(&self) -> Uuid;
     fn name(&self) -> &str;
@@ -26,6 +28,11 @@ impl Function {
         let digest_bytes = sha2::Sha256::digest(content);
         hex::encode(digest_bytes)
     }
+
+    /// Name of the stored binary of a function with the given kind and content
+    pub(crate) fn wasm_file_name(kind: &str, uuid: &Uuid, content_hash: &str) -> String {
+        format!("{kind}_{uuid}_{content_hash}.wasm")
+    }
 }
 
 impl WasmFunctionTrait for Function {
@@ -51,13 +58,36 @@ impl WasmFunctionTrait for Function {
     }
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Clone)]
 pub(crate) struct HttpFunction {
     pub(crate) uuid: Uuid,
     pub(crate) name: String,
     pub(crate) path: String,
     pub(crate) method: String,
+    pub(crate) is_public: bool,
+    #[serde(skip)]
+    pub(crate) scope_id: Uuid,
     pub(crate) content_hash: String,
+    pub(crate) limits: ResourceLimits,
+    /// The active revision, unknown for functions deployed before revisions were tracked
+    pub(crate) revision: Option<i32>,
+    /// Whether deploys keep the active revision instead of activating the new one
+    pub(crate) pinned: bool,
+    /// The world the function serves requests through
+    pub(crate) world: HttpWorld,
+}
+
+impl HttpFunction {
+    /// Methods the function is registered for, stored comma separated (e.g. `GET,PUT`)
+    pub(crate) fn methods(&self) -> impl Iterator<Item = &str> {
+        self.method.split(',').map(str::trim)
+    }
+
+    /// Whether the function handles requests with the given method
+    pub(crate) fn accepts_method(&self, method: &str) -> bool {
+        self.methods()
+            .any(|declared| declared == "ANY" || declared.eq_ignore_ascii_case(method))
+    }
 }
 
 impl WasmFunctionTrait for HttpFunction {
@@ -70,7 +100,7 @@ impl WasmFunctionTrait for HttpFunction {
     }
 
     fn related_wasm(&self) -> String {
-        format!("http_{}_{}.wasm", self.uuid, self.content_hash)
+        Function::wasm_file_name("http", &self.uuid, &self.content_hash)
     }
 }
 
@@ -81,7 +111,18 @@ impl From<entity::http_function::Model> for HttpFunction {
             name: http_function.name,
             method: http_function.method,
             path: http_function.path,
+            is_public: http_function.is_public,
+            scope_id: http_function.scope_id,
             content_hash: http_function.content_hash,
+            limits: ResourceLimits::from_columns(
+                http_function.timeout_secs,
+                http_function.fuel,
+                http_function.max_memory_mb,
+                http_function.max_table_elements,
+            ),
+            revision: http_function.revision,
+            pinned: http_function.pinned,
+            world: HttpWorld::from_column(http_function.world.as_deref()),
         }
     }
 }
@@ -91,7 +132,21 @@ pub(crate) struct ScheduledFunction {
     pub(crate) name: String,
     pub(crate) uuid: Uuid,
     pub(crate) cron: String,
+    /// The cron expression follows this IANA timezone, `None` is UTC
+    pub(crate) timezone: Option<String>,
+    #[serde(skip)]
+    pub(crate) scope_id: Uuid,
     pub(crate) content_hash: String,
+    pub(crate) limits: ResourceLimits,
+    pub(crate) revision: Option<i32>,
+    pub(crate) pinned: bool,
+    /// Runs kept in the history, `None` uses the runtime-wide limit
+    pub(crate) run_history: Option<u32>,
+    /// Failed runs are only retried with a policy
+    pub(crate) retry: Option<super::retry::RetryPolicy>,
+    pub(crate) overlap: super::overlap::OverlapPolicy,
+    /// The longest a tick may run, including its retries
+    pub(crate) run_timeout_secs: Option<u64>,
 }
 
 impl WasmFunctionTrait for ScheduledFunction {
@@ -104,7 +159,7 @@ impl WasmFunctionTrait for ScheduledFunction {
     }
 
     fn related_wasm(&self) -> String {
-        format!("scheduled_{}_{}.wasm", self.uuid, self.content_hash)
+        Function::wasm_file_name("scheduled", &self.uuid, &self.content_hash)
     }
 }
 
@@ -114,7 +169,32 @@ impl From<entity::scheduled_function::Model> for ScheduledFunction {
             name: scheduled_function.name,
             uuid: scheduled_function.id,
             cron: scheduled_function.cron,
+            timezone: scheduled_function.timezone,
+            scope_id: scheduled_function.scope_id,
             content_hash: scheduled_function.content_hash,
+            limits: ResourceLimits::from_columns(
+                scheduled_function.timeout_secs,
+                scheduled_function.fuel,
+                scheduled_function.max_memory_mb,
+                scheduled_function.max_table_elements,
+            ),
+            revision: scheduled_function.revision,
+            pinned: scheduled_function.pinned,
+            run_history: scheduled_function
+                .run_history
+                .and_then(|run_history| run_history.try_into().ok()),
+            retry: super::retry::RetryPolicy::from_columns((
+                scheduled_function.retry_max_attempts,
+                scheduled_function.retry_backoff_secs,
+                scheduled_function.retry_max_backoff_secs,
+                scheduled_function.retry_jitter,
+            )),
+            overlap: super::overlap::OverlapPolicy::from_column(
+                scheduled_function.overlap.as_deref(),
+            ),
+            run_timeout_secs: scheduled_function
+                .run_timeout_secs
+                .and_then(|run_timeout_secs| run_timeout_secs.try_into().ok()),
         }
     }
 }
@@ -129,4 +209,35 @@ mod test {
         let hash = Function::hash(content);
         assert_eq!(hash.len(), 64);
     }
+
+    fn http_function_with_method(method: &str) -> HttpFunction {
+        HttpFunction {
+            uuid: Uuid::new_v4(),
+            name: "test".to_string(),
+            path: "/test".to_string(),
+            method: method.to_string(),
+            is_public: true,
+            scope_id: Uuid::new_v4(),
+            content_hash: Function::hash(b"test"),
+            limits: ResourceLimits::default(),
+            revision: None,
+            pinned: false,
+            world: HttpWorld::FunctionHttp,
+        }
+    }
+
+    #[test]
+    fn test_http_function_accepts_declared_methods() {
+        let function = http_function_with_method("GET,PUT");
+        assert!(function.accepts_method("GET"));
+        assert!(function.accepts_method("PUT"));
+        assert!(!function.accepts_method("POST"));
+    }
+
+    #[test]
+    fn test_http_function_accepts_any_method() {
+        let function = http_function_with_method("ANY");
+        assert!(function.accepts_method("DELETE"));
+        assert!(function.accepts_method("OPTIONS"));
+    }
 }
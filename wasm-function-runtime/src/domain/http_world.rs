@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+
+/// The world an HTTP function exports to serve requests.
+/// Selected through the `trigger` of the manifest.
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Default,
+    strum::AsRefStr,
+    strum::EnumString,
+)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub(crate) enum HttpWorld {
+    /// The `jontze:function-http` world of the SDK, bodies are passed as a whole
+    #[default]
+    FunctionHttp,
+    /// The `function-http-streaming` world of the SDK, bodies are streamed
+    FunctionHttpStreaming,
+    /// The standard `wasi:http/proxy` world, bodies are streamed
+    WasiHttp,
+}
+
+impl HttpWorld {
+    /// The world stored with an HTTP function, functions deployed without one use the SDK world
+    pub(crate) fn from_column(world: Option<&str>) -> Self {
+        world
+            .and_then(|world| world.parse().ok())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn to_column(self) -> Option<String> {
+        Some(self.as_ref().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn store_world_as_column() {
+        for world in [
+            HttpWorld::FunctionHttp,
+            HttpWorld::FunctionHttpStreaming,
+            HttpWorld::WasiHttp,
+        ] {
+            assert_eq!(HttpWorld::from_column(world.to_column().as_deref()), world);
+        }
+        assert_eq!(
+            HttpWorld::WasiHttp.to_column().as_deref(),
+            Some("wasi-http")
+        );
+        assert_eq!(HttpWorld::from_column(None), HttpWorld::FunctionHttp);
+        assert_eq!(
+            HttpWorld::from_column(Some("unknown")),
+            HttpWorld::FunctionHttp
+        );
+    }
+}
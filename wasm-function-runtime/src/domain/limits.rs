@@ -0,0 +1,143 @@
+use serde::{Deserialize, Serialize};
+
+/// Resource limits applied to a single function invocation.
+/// Unset values fall back to the runtime-wide defaults, which also cap the set ones.
+#[derive(Deserialize, Serialize, PartialEq, Eq, Debug, Clone, Default)]
+pub(crate) struct ResourceLimits {
+    /// Wall-clock time a single invocation may take
+    pub timeout_secs: Option<u64>,
+    /// Fuel budget, roughly the number of executed wasm instructions
+    pub fuel: Option<u64>,
+    /// Maximum size of each linear memory in megabytes
+    pub max_memory_mb: Option<u64>,
+    /// Maximum number of elements per table
+    pub max_table_elements: Option<u64>,
+}
+
+impl ResourceLimits {
+    /// Fills every unset limit with the value of the given defaults and lowers the others to it.
+    /// The runtime-wide limits are maxima, a function can only tighten them.
+    pub(crate) fn within(&self, defaults: &ResourceLimits) -> ResourceLimits {
+        let capped = |limit: Option<u64>, default: Option<u64>| match (limit, default) {
+            (Some(limit), Some(default)) => Some(limit.min(default)),
+            (limit, default) => limit.or(default),
+        };
+        ResourceLimits {
+            timeout_secs: capped(self.timeout_secs, defaults.timeout_secs),
+            fuel: capped(self.fuel, defaults.fuel),
+            max_memory_mb: capped(self.max_memory_mb, defaults.max_memory_mb),
+            max_table_elements: capped(self.max_table_elements, defaults.max_table_elements),
+        }
+    }
+
+    pub(crate) fn timeout(&self) -> Option<std::time::Duration> {
+        self.timeout_secs.map(std::time::Duration::from_secs)
+    }
+
+    pub(crate) fn max_memory_bytes(&self) -> Option<usize> {
+        self.max_memory_mb
+            .map(|mb| usize::try_from(mb.saturating_mul(1024 * 1024)).unwrap_or(usize::MAX))
+    }
+
+    pub(crate) fn max_table_elements(&self) -> Option<usize> {
+        self.max_table_elements
+            .map(|elements| usize::try_from(elements).unwrap_or(usize::MAX))
+    }
+
+    /// Builds the limits from the nullable columns stored for a function
+    pub(crate) fn from_columns(
+        timeout_secs: Option<i64>,
+        fuel: Option<i64>,
+        max_memory_mb: Option<i64>,
+        max_table_elements: Option<i64>,
+    ) -> Self {
+        let to_u64 = |value: Option<i64>| value.and_then(|value| u64::try_from(value).ok());
+        Self {
+            timeout_secs: to_u64(timeout_secs),
+            fuel: to_u64(fuel),
+            max_memory_mb: to_u64(max_memory_mb),
+            max_table_elements: to_u64(max_table_elements),
+        }
+    }
+
+    /// Converts the limits into the nullable columns stored for a function,
+    /// ordered as timeout, fuel, memory and table elements
+    pub(crate) fn to_columns(&self) -> [Option<i64>; 4] {
+        let to_i64 =
+            |value: Option<u64>| value.map(|value| i64::try_from(value).unwrap_or(i64::MAX));
+        [
+            to_i64(self.timeout_secs),
+            to_i64(self.fuel),
+            to_i64(self.max_memory_mb),
+            to_i64(self.max_table_elements),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fill_unset_limits_from_defaults() {
+        let defaults = ResourceLimits {
+            timeout_secs: Some(30),
+            fuel: None,
+            max_memory_mb: Some(256),
+            max_table_elements: Some(10_000),
+        };
+        let overrides = ResourceLimits {
+            timeout_secs: Some(5),
+            fuel: Some(1_000_000),
+            ..Default::default()
+        };
+
+        let limits = overrides.within(&defaults);
+
+        assert_eq!(limits.timeout_secs, Some(5));
+        assert_eq!(limits.fuel, Some(1_000_000));
+        assert_eq!(limits.max_memory_mb, Some(256));
+        assert_eq!(limits.max_memory_bytes(), Some(256 * 1024 * 1024));
+        assert_eq!(limits.max_table_elements, Some(10_000));
+    }
+
+    #[test]
+    fn cap_limits_at_defaults() {
+        let defaults = ResourceLimits {
+            timeout_secs: Some(30),
+            fuel: None,
+            max_memory_mb: Some(256),
+            max_table_elements: Some(10_000),
+        };
+        let overrides = ResourceLimits {
+            timeout_secs: Some(3600),
+            fuel: Some(1_000_000),
+            max_memory_mb: Some(4096),
+            max_table_elements: Some(u64::MAX),
+        };
+
+        let limits = overrides.within(&defaults);
+
+        assert_eq!(limits.timeout_secs, Some(30));
+        assert_eq!(limits.fuel, Some(1_000_000));
+        assert_eq!(limits.max_memory_mb, Some(256));
+        assert_eq!(limits.max_table_elements, Some(10_000));
+    }
+
+    #[test]
+    fn roundtrip_limit_columns() {
+        let limits = ResourceLimits {
+            timeout_secs: Some(10),
+            fuel: None,
+            max_memory_mb: Some(64),
+            max_table_elements: None,
+        };
+
+        let [timeout_secs, fuel, max_memory_mb, max_table_elements] = limits.to_columns();
+
+        assert_eq!(
+            ResourceLimits::from_columns(timeout_secs, fuel, max_memory_mb, max_table_elements),
+            limits
+        );
+    }
+}
@@ -0,0 +1,100 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Severity of a log line, ordered from least to most severe
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Debug,
+    Clone,
+    Copy,
+    strum::AsRefStr,
+    strum::EnumString,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub(crate) enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl LogLevel {
+    const ALL: [LogLevel; 5] = [
+        LogLevel::Trace,
+        LogLevel::Debug,
+        LogLevel::Info,
+        LogLevel::Warn,
+        LogLevel::Error,
+    ];
+
+    /// Levels that are as or more severe than this one
+    pub(crate) fn at_least(self) -> impl Iterator<Item = LogLevel> {
+        Self::ALL.into_iter().filter(move |level| *level >= self)
+    }
+}
+
+/// Where a log line originated from
+#[derive(
+    Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy, strum::AsRefStr, strum::EnumString,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub(crate) enum LogSource {
+    /// Written through the logging interface
+    Log,
+    Stdout,
+    Stderr,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+pub(crate) struct LogField {
+    pub key: String,
+    pub value: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub(crate) struct LogEntry {
+    pub function_id: Uuid,
+    pub request_id: Option<String>,
+    pub source: LogSource,
+    pub level: LogLevel,
+    pub message: String,
+    pub fields: Vec<LogField>,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+}
+
+impl From<entity::function_log::Model> for LogEntry {
+    fn from(model: entity::function_log::Model) -> Self {
+        Self {
+            function_id: model.function_id,
+            request_id: model.request_id,
+            source: model.source.parse().unwrap_or(LogSource::Log),
+            level: model.level.parse().unwrap_or(LogLevel::Info),
+            message: model.message,
+            fields: model
+                .fields
+                .and_then(|fields| serde_json::from_str(&fields).ok())
+                .unwrap_or_default(),
+            timestamp: model.created_at.to_utc(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn at_least_includes_more_severe_levels() {
+        let levels: Vec<LogLevel> = LogLevel::Warn.at_least().collect();
+        assert_eq!(levels, vec![LogLevel::Warn, LogLevel::Error]);
+        assert_eq!(LogLevel::Trace.at_least().count(), 5);
+    }
+}
@@ -1,10 +1,16 @@
 use serde::Deserialize;
 
-#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+use super::http_world::HttpWorld;
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
 pub(crate) struct Manifest {
     pub function: Function,
     pub http: Option<HttpFunc>,
     pub scheduled: Option<ScheduledFunc>,
+    /// Lowers the runtime-wide resource limits for this function
+    pub limits: Option<super::limits::ResourceLimits>,
+    /// Deploys the function as canary next to the active revision instead of replacing it
+    pub rollout: Option<super::rollout::RolloutConfig>,
 }
 
 #[derive(Deserialize, PartialEq, Eq, Debug, Clone)]
@@ -13,6 +19,24 @@ pub(crate) enum FuncKind {
     Http,
     #[serde(rename = "scheduled")]
     Scheduled,
+    /// Like `http`, but streams the request and response bodies
+    #[serde(rename = "http-streaming")]
+    HttpStreaming,
+    /// Serves requests through the standard `wasi:http/proxy` world, configured by the `[http]` section
+    #[serde(rename = "wasi-http")]
+    WasiHttp,
+}
+
+impl FuncKind {
+    /// The world HTTP triggers serve requests through, `None` for other triggers
+    pub(crate) fn http_world(&self) -> Option<HttpWorld> {
+        match self {
+            FuncKind::Http => Some(HttpWorld::FunctionHttp),
+            FuncKind::HttpStreaming => Some(HttpWorld::FunctionHttpStreaming),
+            FuncKind::WasiHttp => Some(HttpWorld::WasiHttp),
+            FuncKind::Scheduled => None,
+        }
+    }
 }
 
 #[derive(Deserialize, PartialEq, Eq, Debug, Clone)]
@@ -30,18 +54,86 @@ pub(crate) enum HttpFuncMehod {
     #[serde(rename = "POST")]
     #[strum(serialize = "POST")]
     Post,
+    #[serde(rename = "PUT")]
+    #[strum(serialize = "PUT")]
+    Put,
+    #[serde(rename = "PATCH")]
+    #[strum(serialize = "PATCH")]
+    Patch,
+    #[serde(rename = "DELETE")]
+    #[strum(serialize = "DELETE")]
+    Delete,
+    #[serde(rename = "HEAD")]
+    #[strum(serialize = "HEAD")]
+    Head,
+    #[serde(rename = "OPTIONS")]
+    #[strum(serialize = "OPTIONS")]
+    Options,
+    /// Matches every supported method
+    #[serde(rename = "ANY")]
+    #[strum(serialize = "ANY")]
+    Any,
 }
 
 #[derive(Deserialize, PartialEq, Eq, Debug, Clone)]
 pub(crate) struct HttpFunc {
     pub path: String,
-    pub method: HttpFuncMehod,
+    /// Either a single method (`method = "GET"`) or a list (`method = ["GET", "PUT"]`)
+    #[serde(rename = "method", deserialize_with = "deserialize_methods")]
+    pub methods: Vec<HttpFuncMehod>,
+    /// Callable without token. Private functions accept any valid token of the identity provider,
+    /// whatever scope the function belongs to, and never see the `Authorization` and `Cookie` headers.
     pub public: bool,
 }
 
+impl HttpFunc {
+    /// The declared methods joined as stored for the function (e.g. `GET,PUT`)
+    pub(crate) fn method_column(&self) -> String {
+        self.methods
+            .iter()
+            .map(|method| method.as_ref())
+            .collect::<Vec<&str>>()
+            .join(",")
+    }
+}
+
+fn deserialize_methods<'de, D>(deserializer: D) -> Result<Vec<HttpFuncMehod>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(HttpFuncMehod),
+        Many(Vec<HttpFuncMehod>),
+    }
+
+    match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(method) => Ok(vec![method]),
+        OneOrMany::Many(methods) if methods.is_empty() => Err(serde::de::Error::custom(
+            "at least one HTTP method must be declared",
+        )),
+        OneOrMany::Many(methods) => Ok(methods),
+    }
+}
+
+/// Longest `timeout_secs` a scheduled function may declare, a day
+pub(crate) const MAX_SCHEDULED_TIMEOUT_SECS: u64 = 24 * 60 * 60;
+
 #[derive(Deserialize, PartialEq, Eq, Debug, Clone)]
 pub(crate) struct ScheduledFunc {
     pub cron: String,
+    /// IANA timezone the cron expression follows, e.g. `Europe/Berlin`, defaults to UTC
+    pub timezone: Option<String>,
+    /// Runs kept in the history of the function, overrides the runtime-wide limit
+    pub run_history: Option<u32>,
+    /// Retries failed runs instead of waiting for the next scheduled run
+    pub retry: Option<super::retry::RetryPolicy>,
+    /// What a tick does while the previous run is still going
+    #[serde(default)]
+    pub overlap: super::overlap::OverlapPolicy,
+    /// The longest a tick may run, including its retries
+    pub timeout_secs: Option<u64>,
 }
 
 #[cfg(test)]
@@ -68,9 +160,168 @@ mod tests_http_manifest {
         assert_eq!(manifest.function.scope, "my-scope");
         assert_eq!(manifest.function.trigger, FuncKind::Http);
         assert_eq!(manifest.http.as_ref().unwrap().path, "/my-http-function");
-        assert_eq!(manifest.http.as_ref().unwrap().method, HttpFuncMehod::Get);
+        assert_eq!(
+            manifest.http.as_ref().unwrap().methods,
+            vec![HttpFuncMehod::Get]
+        );
         assert!(manifest.http.as_ref().unwrap().public);
     }
+
+    #[test]
+    fn parse_http_manifest_with_multiple_methods() {
+        let toml_http_function_manifest = r#"
+            [function]
+            name = "my-http-function"
+            scope = "my-scope"
+            trigger = "http"
+
+            [http]
+            path = "/my-http-function"
+            method = ["GET", "PUT", "DELETE"]
+            public = false
+        "#;
+
+        let manifest: Manifest = toml::from_str(toml_http_function_manifest).unwrap();
+
+        assert_eq!(
+            manifest.http.as_ref().unwrap().methods,
+            vec![
+                HttpFuncMehod::Get,
+                HttpFuncMehod::Put,
+                HttpFuncMehod::Delete
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_http_manifest_with_any_method() {
+        let toml_http_function_manifest = r#"
+            [function]
+            name = "my-http-function"
+            scope = "my-scope"
+            trigger = "http"
+
+            [http]
+            path = "/my-http-function"
+            method = "ANY"
+            public = true
+        "#;
+
+        let manifest: Manifest = toml::from_str(toml_http_function_manifest).unwrap();
+
+        assert_eq!(
+            manifest.http.as_ref().unwrap().methods,
+            vec![HttpFuncMehod::Any]
+        );
+    }
+
+    #[test]
+    fn parse_wasi_http_manifest() {
+        let toml_http_function_manifest = r#"
+            [function]
+            name = "my-proxy-function"
+            scope = "my-scope"
+            trigger = "wasi-http"
+
+            [http]
+            path = "/proxy/*rest"
+            method = "ANY"
+            public = true
+        "#;
+
+        let manifest: Manifest = toml::from_str(toml_http_function_manifest).unwrap();
+
+        assert_eq!(manifest.function.trigger, FuncKind::WasiHttp);
+        assert_eq!(
+            manifest.function.trigger.http_world(),
+            Some(HttpWorld::WasiHttp)
+        );
+        assert_eq!(manifest.http.as_ref().unwrap().path, "/proxy/*rest");
+    }
+
+    #[test]
+    fn reject_http_manifest_without_methods() {
+        let toml_http_function_manifest = r#"
+            [function]
+            name = "my-http-function"
+            scope = "my-scope"
+            trigger = "http"
+
+            [http]
+            path = "/my-http-function"
+            method = []
+            public = true
+        "#;
+
+        assert!(toml::from_str::<Manifest>(toml_http_function_manifest).is_err());
+    }
+}
+
+#[cfg(test)]
+mod tests_limits_manifest {
+    use super::*;
+
+    #[test]
+    fn parse_manifest_with_limits() {
+        let toml_function_manifest = r#"
+            [function]
+            name = "my-http-function"
+            scope = "my-scope"
+            trigger = "http"
+
+            [http]
+            path = "/my-http-function"
+            method = "GET"
+            public = true
+
+            [limits]
+            timeout_secs = 5
+            max_memory_mb = 64
+        "#;
+
+        let manifest: Manifest = toml::from_str(toml_function_manifest).unwrap();
+        let limits = manifest.limits.unwrap();
+
+        assert_eq!(limits.timeout_secs, Some(5));
+        assert_eq!(limits.fuel, None);
+        assert_eq!(limits.max_memory_mb, Some(64));
+        assert_eq!(limits.max_table_elements, None);
+    }
+}
+
+#[cfg(test)]
+mod tests_rollout_manifest {
+    use super::*;
+
+    #[test]
+    fn parse_manifest_with_rollout() {
+        let toml_function_manifest = r#"
+            [function]
+            name = "my-http-function"
+            scope = "my-scope"
+            trigger = "http"
+
+            [http]
+            path = "/my-http-function"
+            method = "GET"
+            public = true
+
+            [rollout]
+            weight = 10
+            sticky_header = "x-user-id"
+            error_threshold = 2.5
+        "#;
+
+        let manifest: Manifest = toml::from_str(toml_function_manifest).unwrap();
+        let rollout = manifest.rollout.unwrap();
+
+        assert_eq!(rollout.weight, 10);
+        assert_eq!(rollout.sticky_header.as_deref(), Some("x-user-id"));
+        assert_eq!(rollout.sticky_cookie, None);
+        assert_eq!(rollout.error_threshold, Some(2.5));
+        assert_eq!(rollout.min_requests, 100);
+        assert_eq!(rollout.promote_after, None);
+    }
 }
 
 #[cfg(test)]
@@ -95,5 +346,98 @@ mod tests_scheduled_manifest {
         assert_eq!(manifest.function.scope, "my-scope");
         assert_eq!(manifest.function.trigger, FuncKind::Scheduled);
         assert_eq!(manifest.scheduled.as_ref().unwrap().cron, "0 0 * * *");
+        assert_eq!(manifest.scheduled.as_ref().unwrap().run_history, None);
+        assert_eq!(
+            manifest.scheduled.as_ref().unwrap().overlap,
+            super::super::overlap::OverlapPolicy::Allow
+        );
+    }
+
+    #[test]
+    fn parse_scheduled_manifest_with_run_history() {
+        let toml_scheduled_function_manifest = r#"
+            [function]
+            name = "my-scheduled-function"
+            scope = "my-scope"
+            trigger = "scheduled"
+
+            [scheduled]
+            cron = "0 0 * * *"
+            run_history = 25
+        "#;
+
+        let manifest: Manifest = toml::from_str(toml_scheduled_function_manifest).unwrap();
+
+        assert_eq!(manifest.scheduled.unwrap().run_history, Some(25));
+    }
+
+    #[test]
+    fn parse_scheduled_manifest_with_retry_policy() {
+        let toml_scheduled_function_manifest = r#"
+            [function]
+            name = "my-scheduled-function"
+            scope = "my-scope"
+            trigger = "scheduled"
+
+            [scheduled]
+            cron = "0 0 * * *"
+
+            [scheduled.retry]
+            max_attempts = 5
+            backoff_secs = 30
+        "#;
+
+        let manifest: Manifest = toml::from_str(toml_scheduled_function_manifest).unwrap();
+        let retry = manifest.scheduled.unwrap().retry.unwrap();
+
+        assert_eq!(retry.max_attempts, 5);
+        assert_eq!(retry.backoff_secs, 30);
+        assert_eq!(retry.max_backoff_secs, 300);
+        assert!(retry.jitter);
+    }
+
+    #[test]
+    fn parse_scheduled_manifest_with_overlap_policy() {
+        let toml_scheduled_function_manifest = r#"
+            [function]
+            name = "my-scheduled-function"
+            scope = "my-scope"
+            trigger = "scheduled"
+
+            [scheduled]
+            cron = "0 0 * * *"
+            overlap = "skip"
+            timeout_secs = 120
+        "#;
+
+        let manifest: Manifest = toml::from_str(toml_scheduled_function_manifest).unwrap();
+        let scheduled = manifest.scheduled.unwrap();
+
+        assert_eq!(
+            scheduled.overlap,
+            super::super::overlap::OverlapPolicy::Skip
+        );
+        assert_eq!(scheduled.timeout_secs, Some(120));
+    }
+
+    #[test]
+    fn parse_scheduled_manifest_with_timezone() {
+        let toml_scheduled_function_manifest = r#"
+            [function]
+            name = "my-scheduled-function"
+            scope = "my-scope"
+            trigger = "scheduled"
+
+            [scheduled]
+            cron = "0 30 8 * * Mon-Fri"
+            timezone = "Europe/Berlin"
+        "#;
+
+        let manifest: Manifest = toml::from_str(toml_scheduled_function_manifest).unwrap();
+
+        assert_eq!(
+            manifest.scheduled.unwrap().timezone.as_deref(),
+            Some("Europe/Berlin")
+        );
     }
 }
@@ -1,5 +1,13 @@
 pub(crate) mod function;
+pub(crate) mod http_world;
+pub(crate) mod limits;
+pub(crate) mod log;
 pub(crate) mod manifest;
+pub(crate) mod overlap;
+pub(crate) mod retry;
+pub(crate) mod revision;
+pub(crate) mod rollout;
+pub(crate) mod run;
 pub(crate) mod scope;
 pub(crate) mod secret;
 pub(crate) mod variable;
@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+
+/// What a cron tick does while the previous run of the function is still going.
+/// Configured through `overlap` in the `[scheduled]` section of the manifest.
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Default,
+    strum::AsRefStr,
+    strum::EnumString,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub(crate) enum OverlapPolicy {
+    /// The tick doesn't run and is recorded as skipped
+    Skip,
+    /// The tick runs once the previous run finished, further ticks in the meantime are skipped
+    Queue,
+    /// The tick runs next to the previous run
+    #[default]
+    Allow,
+}
+
+impl OverlapPolicy {
+    /// The policy stored with a scheduled function, functions deployed without one allow overlaps
+    pub(crate) fn from_column(overlap: Option<&str>) -> Self {
+        overlap
+            .and_then(|overlap| overlap.parse().ok())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn to_column(self) -> Option<String> {
+        Some(self.as_ref().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn store_policy_as_column() {
+        for policy in [
+            OverlapPolicy::Skip,
+            OverlapPolicy::Queue,
+            OverlapPolicy::Allow,
+        ] {
+            assert_eq!(
+                OverlapPolicy::from_column(policy.to_column().as_deref()),
+                policy
+            );
+        }
+        assert_eq!(OverlapPolicy::from_column(None), OverlapPolicy::Allow);
+    }
+}
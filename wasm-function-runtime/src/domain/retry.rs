@@ -0,0 +1,183 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// Delay before the first retry, if not configured otherwise
+const DEFAULT_BACKOFF_SECS: u64 = 10;
+
+/// Upper bound of the delay between two attempts, if not configured otherwise
+const DEFAULT_MAX_BACKOFF_SECS: u64 = 300;
+
+/// Longest delay between two attempts a policy may configure, a day
+const MAX_BACKOFF_SECS_LIMIT: u64 = 24 * 60 * 60;
+
+/// How failed runs of a scheduled function are retried.
+/// Configured through the `[scheduled.retry]` section of the manifest.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub(crate) struct RetryPolicy {
+    /// Attempts per run, including the first one
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled for every further retry
+    #[serde(default = "default_backoff_secs")]
+    pub backoff_secs: u64,
+    /// Upper bound of the delay between two attempts
+    #[serde(default = "default_max_backoff_secs")]
+    pub max_backoff_secs: u64,
+    /// Randomizes the delays, so functions failing at the same time don't retry at the same time
+    #[serde(default = "default_jitter")]
+    pub jitter: bool,
+}
+
+fn default_backoff_secs() -> u64 {
+    DEFAULT_BACKOFF_SECS
+}
+
+fn default_max_backoff_secs() -> u64 {
+    DEFAULT_MAX_BACKOFF_SECS
+}
+
+fn default_jitter() -> bool {
+    true
+}
+
+/// The retry policy as stored with a scheduled function, ordered as
+/// max attempts, backoff, max backoff and jitter
+pub(crate) type RetryColumns = (Option<i32>, Option<i64>, Option<i64>, Option<bool>);
+
+impl RetryPolicy {
+    /// Checks the policy, returning a message describing the first problem
+    pub(crate) fn validate(&self) -> Result<(), &'static str> {
+        if self.max_attempts == 0 {
+            return Err("Retry max_attempts must be at least 1");
+        }
+        if self.backoff_secs > self.max_backoff_secs {
+            return Err("Retry backoff_secs must not exceed max_backoff_secs");
+        }
+        if self.max_backoff_secs > MAX_BACKOFF_SECS_LIMIT {
+            return Err("Retry max_backoff_secs must not exceed a day (86400)");
+        }
+        Ok(())
+    }
+
+    /// Whether another attempt follows the given failed attempt, counted from 1
+    pub(crate) fn retries(&self, attempt: u32, retryable: bool) -> bool {
+        retryable && attempt < self.max_attempts
+    }
+
+    /// Delay after the given failed attempt, counted from 1.
+    /// With jitter the delay is picked between half and the full exponential backoff.
+    pub(crate) fn delay(&self, attempt: u32) -> Duration {
+        let factor = 2u64.saturating_pow(attempt.saturating_sub(1));
+        let delay_ms = self
+            .backoff_secs
+            .saturating_mul(factor)
+            .min(self.max_backoff_secs)
+            .saturating_mul(1000);
+
+        if !self.jitter || delay_ms == 0 {
+            return Duration::from_millis(delay_ms);
+        }
+        let half_ms = delay_ms / 2;
+        let random_ms =
+            (uuid::Uuid::new_v4().as_u128() % u128::from(delay_ms - half_ms + 1)) as u64;
+        Duration::from_millis(half_ms + random_ms)
+    }
+
+    /// Builds the policy from the nullable columns stored for a function,
+    /// functions without max attempts are not retried
+    pub(crate) fn from_columns(
+        (max_attempts, backoff_secs, max_backoff_secs, jitter): RetryColumns,
+    ) -> Option<Self> {
+        let max_attempts = max_attempts.and_then(|value| u32::try_from(value).ok())?;
+        Some(Self {
+            max_attempts,
+            backoff_secs: backoff_secs
+                .and_then(|value| u64::try_from(value).ok())
+                .unwrap_or(DEFAULT_BACKOFF_SECS),
+            max_backoff_secs: max_backoff_secs
+                .and_then(|value| u64::try_from(value).ok())
+                .unwrap_or(DEFAULT_MAX_BACKOFF_SECS),
+            jitter: jitter.unwrap_or(true),
+        })
+    }
+
+    /// Converts the policy into the nullable columns stored for a function
+    pub(crate) fn to_columns(policy: Option<&Self>) -> RetryColumns {
+        match policy {
+            Some(policy) => (
+                Some(i32::try_from(policy.max_attempts).unwrap_or(i32::MAX)),
+                Some(i64::try_from(policy.backoff_secs).unwrap_or(i64::MAX)),
+                Some(i64::try_from(policy.max_backoff_secs).unwrap_or(i64::MAX)),
+                Some(policy.jitter),
+            ),
+            None => (None, None, None, None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(jitter: bool) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 4,
+            backoff_secs: 10,
+            max_backoff_secs: 30,
+            jitter,
+        }
+    }
+
+    #[test]
+    fn back_off_exponentially_up_to_the_maximum() {
+        let policy = policy(false);
+
+        assert_eq!(policy.delay(1), Duration::from_secs(10));
+        assert_eq!(policy.delay(2), Duration::from_secs(20));
+        assert_eq!(policy.delay(3), Duration::from_secs(30));
+        assert_eq!(policy.delay(40), Duration::from_secs(30));
+    }
+
+    #[test]
+    fn jitter_stays_within_half_of_the_backoff() {
+        let policy = policy(true);
+
+        for _ in 0..20 {
+            let delay = policy.delay(2);
+            assert!(delay >= Duration::from_secs(10) && delay <= Duration::from_secs(20));
+        }
+    }
+
+    #[test]
+    fn retry_retryable_failures_until_attempts_are_used_up() {
+        let policy = policy(false);
+
+        assert!(policy.retries(1, true));
+        assert!(policy.retries(3, true));
+        assert!(!policy.retries(4, true));
+        assert!(!policy.retries(1, false));
+    }
+
+    #[test]
+    fn reject_backoff_beyond_a_day() {
+        let mut policy = policy(false);
+        assert_eq!(policy.validate(), Ok(()));
+
+        policy.max_backoff_secs = u64::MAX;
+        assert!(policy.validate().is_err());
+    }
+
+    #[test]
+    fn round_trip_columns() {
+        let policy = policy(true);
+
+        assert_eq!(
+            RetryPolicy::from_columns(RetryPolicy::to_columns(Some(&policy))),
+            Some(policy)
+        );
+        assert_eq!(
+            RetryPolicy::from_columns(RetryPolicy::to_columns(None)),
+            None
+        );
+    }
+}
@@ -0,0 +1,29 @@
+use serde::Serialize;
+use uuid::Uuid;
+
+/// An immutable snapshot of a single deploy of a function
+#[derive(Serialize, Debug, Clone)]
+pub(crate) struct FunctionRevision {
+    pub function_id: Uuid,
+    pub revision: i32,
+    pub content_hash: String,
+    /// The manifest the revision was deployed with, as uploaded
+    #[serde(skip)]
+    pub manifest: String,
+    /// Object ID of the user that deployed the revision
+    pub deployed_by: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl From<entity::function_revision::Model> for FunctionRevision {
+    fn from(model: entity::function_revision::Model) -> Self {
+        Self {
+            function_id: model.function_id,
+            revision: model.revision,
+            content_hash: model.content_hash,
+            manifest: model.manifest,
+            deployed_by: model.deployed_by,
+            created_at: model.created_at.to_utc(),
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Canary requests observed before the error rate is evaluated, if not configured otherwise
+const DEFAULT_MIN_REQUESTS: u32 = 100;
+
+/// How traffic is split between the active revision of an HTTP function and a canary revision.
+/// Configured through the `[rollout]` section of the manifest or the API.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub(crate) struct RolloutConfig {
+    /// Percentage of requests routed to the canary
+    pub weight: u8,
+    /// Requests with the same value of this header are always routed to the same variant
+    pub sticky_header: Option<String>,
+    /// Requests with the same value of this cookie are always routed to the same variant,
+    /// the header takes precedence if both are present
+    pub sticky_cookie: Option<String>,
+    /// Error rate in percent above which the canary is rolled back
+    pub error_threshold: Option<f64>,
+    /// Canary requests required before the error rate is evaluated
+    #[serde(default = "default_min_requests")]
+    pub min_requests: u32,
+    /// Canary requests after which the canary is promoted, if it stayed below the error threshold
+    pub promote_after: Option<u32>,
+}
+
+fn default_min_requests() -> u32 {
+    DEFAULT_MIN_REQUESTS
+}
+
+/// The action a rollout calls for after observing the canary
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RolloutVerdict {
+    /// The canary becomes the active revision
+    Promote,
+    /// The canary is dropped, all traffic returns to the active revision
+    Abort,
+}
+
+impl RolloutConfig {
+    /// Checks the configuration, returning a message describing the first problem
+    pub(crate) fn validate(&self) -> Result<(), &'static str> {
+        if self.weight > 100 {
+            return Err("Rollout weight must be a percentage between 0 and 100");
+        }
+        if self
+            .error_threshold
+            .is_some_and(|threshold| !(0.0..=100.0).contains(&threshold))
+        {
+            return Err("Rollout error threshold must be a percentage between 0 and 100");
+        }
+        Ok(())
+    }
+
+    /// Decides on the canary once enough of its requests have been observed
+    pub(crate) fn evaluate(&self, requests: u32, failures: u32) -> Option<RolloutVerdict> {
+        let evaluated = requests >= self.min_requests;
+
+        if let Some(threshold) = self.error_threshold {
+            let error_rate = f64::from(failures) * 100.0 / f64::from(requests.max(1));
+            if evaluated && error_rate > threshold {
+                return Some(RolloutVerdict::Abort);
+            }
+            if !evaluated {
+                return None;
+            }
+        }
+
+        self.promote_after
+            .filter(|promote_after| requests >= *promote_after)
+            .map(|_| RolloutVerdict::Promote)
+    }
+}
+
+/// A canary revision receiving a share of the traffic of an HTTP function
+#[derive(Serialize, Debug, Clone)]
+pub(crate) struct Rollout {
+    pub function_id: Uuid,
+    /// The canary revision
+    pub revision: i32,
+    #[serde(flatten)]
+    pub config: RolloutConfig,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl From<entity::function_rollout::Model> for Rollout {
+    fn from(model: entity::function_rollout::Model) -> Self {
+        Self {
+            function_id: model.function_id,
+            revision: model.revision,
+            config: RolloutConfig {
+                weight: model.weight.clamp(0, 100) as u8,
+                sticky_header: model.sticky_header,
+                sticky_cookie: model.sticky_cookie,
+                error_threshold: model.error_threshold,
+                min_requests: model.min_requests.max(0) as u32,
+                promote_after: model
+                    .promote_after
+                    .map(|promote_after| promote_after.max(0) as u32),
+            },
+            created_at: model.created_at.to_utc(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(error_threshold: Option<f64>, promote_after: Option<u32>) -> RolloutConfig {
+        RolloutConfig {
+            weight: 10,
+            sticky_header: None,
+            sticky_cookie: None,
+            error_threshold,
+            min_requests: 10,
+            promote_after,
+        }
+    }
+
+    #[test]
+    fn abort_canary_above_error_threshold() {
+        let config = config(Some(5.0), Some(100));
+
+        // Too few requests to judge the canary
+        assert_eq!(config.evaluate(5, 5), None);
+        assert_eq!(config.evaluate(20, 1), None);
+        assert_eq!(config.evaluate(20, 2), Some(RolloutVerdict::Abort));
+    }
+
+    #[test]
+    fn promote_healthy_canary() {
+        assert_eq!(
+            config(Some(5.0), Some(100)).evaluate(100, 4),
+            Some(RolloutVerdict::Promote)
+        );
+        assert_eq!(
+            config(None, Some(50)).evaluate(50, 50),
+            Some(RolloutVerdict::Promote)
+        );
+        assert_eq!(config(None, None).evaluate(1000, 0), None);
+    }
+
+    #[test]
+    fn reject_invalid_config() {
+        let mut invalid_weight = config(None, None);
+        invalid_weight.weight = 101;
+        assert!(invalid_weight.validate().is_err());
+
+        assert!(config(Some(120.0), None).validate().is_err());
+        assert!(config(Some(2.5), Some(10)).validate().is_ok());
+    }
+}
@@ -0,0 +1,212 @@
+use serde::Serialize;
+use uuid::Uuid;
+
+/// How a run of a scheduled function ended
+#[derive(Serialize, PartialEq, Eq, Debug, Clone, Copy, strum::AsRefStr, strum::EnumString)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub(crate) enum RunOutcome {
+    Success,
+    Failure,
+    /// The tick didn't run, the previous run was still going
+    Skipped,
+    /// The run was aborted once it exceeded the timeout of the function
+    #[serde(rename = "timed_out")]
+    #[strum(serialize = "timed_out")]
+    TimedOut,
+}
+
+/// A finished run of a scheduled function, its logs are stored with the run id as request id
+#[derive(Serialize, Debug, Clone)]
+pub(crate) struct ScheduledRun {
+    pub id: Uuid,
+    pub function_id: Uuid,
+    pub revision: Option<i32>,
+    /// Attempt of the run, counted from 1, retries of a failed run are recorded as further attempts.
+    /// Skipped ticks made no attempt.
+    pub attempt: u32,
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    pub finished_at: chrono::DateTime<chrono::Utc>,
+    pub duration_ms: i64,
+    pub outcome: RunOutcome,
+    pub error: Option<String>,
+}
+
+impl ScheduledRun {
+    /// Records the result of a run that started at `started_at` and finished now
+    pub(crate) fn finished<E: std::fmt::Display>(
+        id: Uuid,
+        function_id: Uuid,
+        revision: Option<i32>,
+        attempt: u32,
+        started_at: chrono::DateTime<chrono::Utc>,
+        result: &Result<(), E>,
+    ) -> Self {
+        let (outcome, error) = match result {
+            Ok(()) => (RunOutcome::Success, None),
+            Err(err) => (RunOutcome::Failure, Some(err.to_string())),
+        };
+
+        Self::ended(
+            id,
+            function_id,
+            revision,
+            attempt,
+            started_at,
+            outcome,
+            error,
+        )
+    }
+
+    /// Records a tick that didn't run, because the previous run was still going
+    pub(crate) fn skipped(id: Uuid, function_id: Uuid, revision: Option<i32>) -> Self {
+        Self::ended(
+            id,
+            function_id,
+            revision,
+            0,
+            chrono::Utc::now(),
+            RunOutcome::Skipped,
+            None,
+        )
+    }
+
+    /// Records an attempt that was aborted once the run exceeded its timeout
+    pub(crate) fn timed_out(
+        id: Uuid,
+        function_id: Uuid,
+        revision: Option<i32>,
+        attempt: u32,
+        started_at: chrono::DateTime<chrono::Utc>,
+        timeout: std::time::Duration,
+    ) -> Self {
+        Self::ended(
+            id,
+            function_id,
+            revision,
+            attempt,
+            started_at,
+            RunOutcome::TimedOut,
+            Some(format!(
+                "Run exceeded its timeout of {}s",
+                timeout.as_secs()
+            )),
+        )
+    }
+
+    fn ended(
+        id: Uuid,
+        function_id: Uuid,
+        revision: Option<i32>,
+        attempt: u32,
+        started_at: chrono::DateTime<chrono::Utc>,
+        outcome: RunOutcome,
+        error: Option<String>,
+    ) -> Self {
+        let finished_at = chrono::Utc::now();
+
+        Self {
+            id,
+            function_id,
+            revision,
+            attempt,
+            started_at,
+            finished_at,
+            duration_ms: (finished_at - started_at).num_milliseconds().max(0),
+            outcome,
+            error,
+        }
+    }
+}
+
+impl From<entity::scheduled_run::Model> for ScheduledRun {
+    fn from(model: entity::scheduled_run::Model) -> Self {
+        Self {
+            id: model.id,
+            function_id: model.function_id,
+            revision: model.revision,
+            attempt: model.attempt.try_into().unwrap_or(1),
+            started_at: model.started_at.to_utc(),
+            finished_at: model.finished_at.to_utc(),
+            duration_ms: model.duration_ms,
+            outcome: model.outcome.parse().unwrap_or(RunOutcome::Failure),
+            error: model.error,
+        }
+    }
+}
+
+/// A run that failed for good, parked until it is replayed or discarded
+#[derive(Serialize, Debug, Clone)]
+pub(crate) struct DeadLetter {
+    pub id: Uuid,
+    pub function_id: Uuid,
+    pub revision: Option<i32>,
+    /// The last attempt of the run
+    pub run_id: Uuid,
+    pub attempts: u32,
+    pub error: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl From<entity::dead_letter::Model> for DeadLetter {
+    fn from(model: entity::dead_letter::Model) -> Self {
+        Self {
+            id: model.id,
+            function_id: model.function_id,
+            revision: model.revision,
+            run_id: model.run_id,
+            attempts: model.attempts.try_into().unwrap_or(1),
+            error: model.error,
+            created_at: model.created_at.to_utc(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_failed_run_with_error() {
+        let started_at = chrono::Utc::now() - chrono::Duration::milliseconds(250);
+
+        let run = ScheduledRun::finished(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Some(3),
+            2,
+            started_at,
+            &Err("guest trapped"),
+        );
+
+        assert_eq!(run.outcome, RunOutcome::Failure);
+        assert_eq!(run.error.as_deref(), Some("guest trapped"));
+        assert!(run.duration_ms >= 250);
+        assert!(run.finished_at >= run.started_at);
+    }
+
+    #[test]
+    fn record_successful_run() {
+        let run = ScheduledRun::finished::<String>(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            None,
+            1,
+            chrono::Utc::now(),
+            &Ok(()),
+        );
+
+        assert_eq!(run.outcome, RunOutcome::Success);
+        assert!(run.error.is_none());
+    }
+
+    #[test]
+    fn store_timed_out_outcome_with_underscore() {
+        assert_eq!(RunOutcome::TimedOut.as_ref(), "timed_out");
+        assert_eq!("timed_out".parse(), Ok(RunOutcome::TimedOut));
+        assert_eq!(
+            serde_json::to_value(RunOutcome::TimedOut).unwrap(),
+            "timed_out"
+        );
+    }
+}
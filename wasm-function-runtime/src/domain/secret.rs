@@ -1,10 +1,9 @@
 use uuid::Uuid;
 
-#[allow(unused)]
+/// A secret without its value, values are only decrypted for function instances
 pub(crate) struct Secret {
     pub uuid: Uuid,
     pub name: String,
-    pub encrypted_value: String,
 }
 
 impl From<entity::secret::Model> for Secret {
@@ -12,20 +11,33 @@ impl From<entity::secret::Model> for Secret {
         Self {
             uuid: model.id,
             name: model.name,
-            encrypted_value: model.value,
         }
     }
 }
 
-#[allow(unused)]
-pub(crate) trait SecretDecrpytTrait {
-    /// Returns the decrypted value of the secret
-    /// The key is used to decrypt the value.
-    fn decrypt(&self, key: &str) -> String;
+/// A decrypted secret, only handed to function instances and never returned by the API
+pub(crate) struct SecretValue {
+    pub name: String,
+    pub value: String,
+}
+
+/// Secret names are exposed as environment variables, so they are limited to ASCII letters, digits and underscores
+pub(crate) fn is_valid_secret_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|char| char.is_ascii_alphanumeric() || char == '_')
 }
 
-impl SecretDecrpytTrait for Secret {
-    fn decrypt(&self, _key: &str) -> String {
-        todo!("Decrypt the value using the key with some algorithm")
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_secret_names() {
+        assert!(is_valid_secret_name("API_TOKEN_2"));
+        assert!(!is_valid_secret_name(""));
+        assert!(!is_valid_secret_name("API-TOKEN"));
+        assert!(!is_valid_secret_name("TOKEN=1"));
     }
 }
@@ -1,6 +1,12 @@
-use axum::{extract::State, http::StatusCode, response::IntoResponse, routing::post, Router};
+use axum::{
+    extract::State, http::StatusCode, response::IntoResponse, routing::post, Extension, Router,
+};
 
 use super::{domain, function_service, RuntimeStateRef};
+use crate::{
+    component::validation, middlewares::auth::AuthenticatedUser, routing,
+    scheduler::schedule::Schedule,
+};
 
 pub(super) fn router() -> Router<RuntimeStateRef> {
     Router::new().route("/", post(deploy_function_with_manifest))
@@ -13,7 +19,14 @@ pub(crate) struct CreateHttpFunctionPayload {
     pub scope: String,
     pub path: String,
     pub is_public: bool,
+    pub world: domain::http_world::HttpWorld,
+    pub limits: domain::limits::ResourceLimits,
     pub wasm_bytes: Vec<u8>,
+    /// The uploaded manifest, kept with the revision
+    pub manifest: String,
+    pub deployed_by: Option<String>,
+    /// Roll the new revision out as canary of the active one
+    pub rollout: Option<domain::rollout::RolloutConfig>,
 }
 
 #[derive(Default)]
@@ -21,30 +34,42 @@ pub(crate) struct CreateScheduledFunctionPayload {
     pub name: String,
     pub scope: String,
     pub cron: String,
+    pub timezone: Option<String>,
+    pub run_history: Option<u32>,
+    pub retry: Option<domain::retry::RetryPolicy>,
+    pub overlap: domain::overlap::OverlapPolicy,
+    pub run_timeout_secs: Option<u64>,
+    pub limits: domain::limits::ResourceLimits,
     pub wasm_bytes: Vec<u8>,
+    /// The uploaded manifest, kept with the revision
+    pub manifest: String,
+    pub deployed_by: Option<String>,
 }
 
 async fn deploy_function_with_manifest(
     State(state): State<RuntimeStateRef>,
+    Extension(user): Extension<AuthenticatedUser>,
     mut multipart: axum::extract::Multipart,
 ) -> impl IntoResponse {
     let mut manifest: Option<domain::manifest::Manifest> = None;
-    let mut wasm_bytes: Vec<u8> = vec![];
+    let mut manifest_raw = String::new();
+    let mut wasm_bytes = bytes::Bytes::new();
 
     while let Some(field) = multipart.next_field().await.expect("Failed to read file") {
         match field.file_name().expect("Failed to get file name") {
             "manifest.toml" => {
                 let data = field.bytes().await.expect("Failed to read field");
+                manifest_raw = std::str::from_utf8(&data)
+                    .expect("Failed to parse manifest")
+                    .to_owned();
                 manifest = Some(
-                    toml::from_str::<domain::manifest::Manifest>(
-                        std::str::from_utf8(&data).expect("Failed to parse manifest"),
-                    )
-                    .expect("Failed to parse manifest"),
+                    toml::from_str::<domain::manifest::Manifest>(&manifest_raw)
+                        .expect("Failed to parse manifest"),
                 );
             }
             file_name => {
                 if file_name.ends_with(".wasm") {
-                    wasm_bytes = field.bytes().await.expect("Failed to read field").to_vec();
+                    wasm_bytes = field.bytes().await.expect("Failed to read field");
                 } else {
                     return Err(format!("Invalid file name: {}", file_name).into_response());
                 }
@@ -58,42 +83,148 @@ async fn deploy_function_with_manifest(
 
     // Push manifest data to the corresponding payload
     if let Some(manifest) = manifest {
+        // Reject components that would only fail once they are invoked
+        let component = validation::validate_component_blocking(
+            state.engine.clone(),
+            manifest.function.trigger.clone(),
+            wasm_bytes.clone(),
+        )
+        .await
+        .map_err(|err| err.into_response())?;
+        let content_hash = domain::function::Function::hash(&wasm_bytes);
+
         match manifest.function.trigger {
-            domain::manifest::FuncKind::Http => {
+            domain::manifest::FuncKind::Http
+            | domain::manifest::FuncKind::HttpStreaming
+            | domain::manifest::FuncKind::WasiHttp => {
                 if let Some(http) = &manifest.http {
+                    if let Err(err) = routing::validate_route(&http.path) {
+                        return Err((
+                            StatusCode::BAD_REQUEST,
+                            format!("Invalid HTTP function path '{}': {err}", http.path),
+                        )
+                            .into_response());
+                    }
+                    if let Some(Err(err)) =
+                        manifest.rollout.as_ref().map(|rollout| rollout.validate())
+                    {
+                        return Err((StatusCode::BAD_REQUEST, err).into_response());
+                    }
+
                     let payload = CreateHttpFunctionPayload {
                         name: manifest.function.name,
                         scope: manifest.function.scope,
-                        method: http.method.as_ref().to_string(),
+                        method: http.method_column(),
                         path: http.path.clone(),
                         is_public: http.public,
-                        wasm_bytes,
+                        world: manifest.function.trigger.http_world().unwrap_or_default(),
+                        limits: manifest.limits.clone().unwrap_or_default(),
+                        wasm_bytes: wasm_bytes.to_vec(),
+                        manifest: manifest_raw,
+                        deployed_by: Some(user.oid),
+                        rollout: manifest.rollout.clone(),
                     };
 
-                    function_service::create_http_func(&state.db, &*state.storage_backend, payload)
-                        .await
-                        .map_err(|e| e.into_response())?;
+                    let http_function = function_service::create_http_func(
+                        &state.db,
+                        &state.route_cache,
+                        &state.invalidator,
+                        &*state.storage_backend,
+                        &state.artifact_cache,
+                        payload,
+                        state.app_config.revision_limit,
+                    )
+                    .await
+                    .map_err(|e| e.into_response())?;
+
+                    // Limits may change without a new binary, so drop the linked function
+                    state.instance_pool.invalidate_function(http_function.uuid);
+
+                    // Spare the first request the compilation, also if the revision is rolled out as canary
+                    state
+                        .artifact_cache
+                        .store(
+                            &domain::function::Function::wasm_file_name(
+                                "http",
+                                &http_function.uuid,
+                                &content_hash,
+                            ),
+                            &component,
+                        )
+                        .await;
                 } else {
                     return Err("HTTP function must have HTTP section in manifest".into_response());
                 }
             }
             domain::manifest::FuncKind::Scheduled => {
                 if let Some(scheduled) = &manifest.scheduled {
+                    if let Some(Err(err)) = scheduled.retry.as_ref().map(|retry| retry.validate()) {
+                        return Err((StatusCode::BAD_REQUEST, err).into_response());
+                    }
+                    // An invalid schedule would leave the function without timer
+                    if let Err(err) = Schedule::new(&scheduled.cron, scheduled.timezone.as_deref())
+                    {
+                        return Err(
+                            (StatusCode::UNPROCESSABLE_ENTITY, err.to_string()).into_response()
+                        );
+                    }
+                    // The history always keeps the latest run
+                    if scheduled.run_history == Some(0) {
+                        return Err((
+                            StatusCode::BAD_REQUEST,
+                            "Scheduled run_history must be at least 1",
+                        )
+                            .into_response());
+                    }
+                    if scheduled.timeout_secs.is_some_and(|timeout_secs| {
+                        !(1..=domain::manifest::MAX_SCHEDULED_TIMEOUT_SECS).contains(&timeout_secs)
+                    }) {
+                        return Err((
+                            StatusCode::BAD_REQUEST,
+                            "Scheduled timeout_secs must be between 1 and a day (86400)",
+                        )
+                            .into_response());
+                    }
+
                     let payload = CreateScheduledFunctionPayload {
                         name: manifest.function.name,
                         scope: manifest.function.scope,
                         cron: scheduled.cron.clone(),
-                        wasm_bytes,
+                        timezone: scheduled.timezone.clone(),
+                        run_history: scheduled.run_history,
+                        retry: scheduled.retry.clone(),
+                        overlap: scheduled.overlap,
+                        run_timeout_secs: scheduled.timeout_secs,
+                        limits: manifest.limits.clone().unwrap_or_default(),
+                        wasm_bytes: wasm_bytes.to_vec(),
+                        manifest: manifest_raw,
+                        deployed_by: Some(user.oid),
                     };
 
-                    function_service::create_scheduled_func(
+                    let scheduled_function = function_service::create_scheduled_func(
                         &state.db,
                         &*state.scheduler_manager,
+                        &state.invalidator,
                         &*state.storage_backend,
+                        &state.artifact_cache,
                         payload,
+                        state.app_config.revision_limit,
                     )
                     .await
                     .map_err(|e| e.into_response())?;
+
+                    // Spare the first execution the compilation
+                    state
+                        .artifact_cache
+                        .store(
+                            &domain::function::Function::wasm_file_name(
+                                "scheduled",
+                                &scheduled_function.uuid,
+                                &content_hash,
+                            ),
+                            &component,
+                        )
+                        .await;
                 } else {
                     return Err("Scheduled function must have scheduled section".into_response());
                 }
@@ -1,10 +1,17 @@
+use std::{convert::Infallible, time::Duration};
+
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
-    response::IntoResponse,
-    routing::{delete, get},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse,
+    },
+    routing::{delete, get, post, put},
     Json, Router,
 };
+use chrono::{DateTime, FixedOffset, Utc};
+use futures::{stream, Stream, StreamExt};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -12,24 +19,251 @@ use super::{
     domain::{self, function::WasmFunctionTrait},
     function_service, RuntimeStateRef,
 };
+use crate::{
+    invalidation::InvalidationEvent,
+    routing::rollout::CanaryOutcomes,
+    scheduler::schedule::Schedule,
+    services::{
+        dead_letter_service,
+        log_service::{self, LogFilter},
+        revision_service, rollout_service, run_service,
+    },
+    utils::ErrorResponse,
+};
 
 pub(super) fn router() -> Router<RuntimeStateRef> {
     Router::new()
         .route("/", get(list_scope_functions))
+        .route("/{function_id}/logs", get(list_function_logs))
+        .route("/{function_id}/logs/stream", get(stream_function_logs))
+        .route("/{function_id}/revisions", get(list_function_revisions))
+        .route("/{function_id}/schedule", get(get_function_schedule))
+        .route("/{function_id}/runs", get(list_function_runs))
+        .route("/{function_id}/runs/{run_id}", get(get_function_run))
+        .route(
+            "/{function_id}/dead-letters",
+            get(list_function_dead_letters),
+        )
+        .route(
+            "/{function_id}/dead-letters/{dead_letter_id}",
+            delete(discard_function_dead_letter),
+        )
+        .route(
+            "/{function_id}/dead-letters/{dead_letter_id}/replay",
+            post(replay_function_dead_letter),
+        )
+        .route("/{function_id}/rollback", post(rollback_function))
+        .route("/{function_id}/pin", delete(unpin_function))
+        .route("/{function_id}/rollout", get(get_function_rollout))
+        .route("/{function_id}/rollout", put(set_function_rollout))
+        .route("/{function_id}/rollout", delete(abort_function_rollout))
+        .route(
+            "/{function_id}/rollout/promote",
+            post(promote_function_rollout),
+        )
         .route("/http/{function_id}", delete(delete_http_function))
         .route(
             "/scheduled/{function_id}",
             delete(delete_scheduled_function),
         )
+        .route("/scheduled/{function_id}/run", post(run_scheduled_function))
 }
 
 #[derive(Deserialize)]
 struct FunctionPath {
-    #[allow(unused)]
     scope: String,
     function_id: Uuid,
 }
 
+impl FunctionPath {
+    /// Tells other replicas that the function changed
+    fn updated(&self) -> InvalidationEvent {
+        InvalidationEvent::FunctionUpdated {
+            scope: self.scope.clone(),
+            function_id: self.function_id,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct FunctionRunPath {
+    scope: String,
+    function_id: Uuid,
+    run_id: Uuid,
+}
+
+impl FunctionRunPath {
+    fn function(&self) -> FunctionPath {
+        FunctionPath {
+            scope: self.scope.clone(),
+            function_id: self.function_id,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct FunctionDeadLetterPath {
+    scope: String,
+    function_id: Uuid,
+    dead_letter_id: Uuid,
+}
+
+impl FunctionDeadLetterPath {
+    fn function(&self) -> FunctionPath {
+        FunctionPath {
+            scope: self.scope.clone(),
+            function_id: self.function_id,
+        }
+    }
+}
+
+/// Lines returned if no limit is requested
+const DEFAULT_LOG_LIMIT: u64 = 100;
+
+/// Runs returned if no limit is requested
+const DEFAULT_RUN_LIMIT: u64 = 20;
+
+/// Fire times previewed if no count is requested
+const DEFAULT_FIRE_TIME_COUNT: usize = 5;
+
+/// Fire times previewed at most
+const MAX_FIRE_TIME_COUNT: usize = 100;
+
+/// How often new lines are looked up while streaming logs
+const LOG_STREAM_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Deserialize)]
+struct FunctionLogsQuery {
+    limit: Option<u64>,
+    request_id: Option<String>,
+    level: Option<domain::log::LogLevel>,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+}
+
+impl From<&FunctionLogsQuery> for LogFilter {
+    fn from(query: &FunctionLogsQuery) -> Self {
+        Self {
+            request_id: query.request_id.clone(),
+            level: query.level,
+            since: query.since,
+            until: query.until,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct FunctionLogStreamQuery {
+    request_id: Option<String>,
+    level: Option<domain::log::LogLevel>,
+    /// Only stream lines logged from this point in time on, defaults to lines stored from now on
+    after: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize)]
+struct FunctionLogsResponse {
+    logs: Vec<domain::log::LogEntry>,
+}
+
+#[derive(Serialize)]
+struct FunctionRevisionItem {
+    #[serde(flatten)]
+    revision: domain::revision::FunctionRevision,
+    active: bool,
+}
+
+#[derive(Serialize)]
+struct FunctionRevisionsResponse {
+    pinned: bool,
+    revisions: Vec<FunctionRevisionItem>,
+}
+
+#[derive(Deserialize)]
+struct FunctionRunsQuery {
+    limit: Option<u64>,
+}
+
+#[derive(Serialize)]
+struct FunctionRunsResponse {
+    runs: Vec<domain::run::ScheduledRun>,
+}
+
+#[derive(Serialize)]
+struct FunctionRunResponse {
+    #[serde(flatten)]
+    run: domain::run::ScheduledRun,
+    logs: Vec<domain::log::LogEntry>,
+}
+
+#[derive(Deserialize)]
+struct FunctionScheduleQuery {
+    count: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct FireTime {
+    /// In the timezone of the function
+    local: DateTime<FixedOffset>,
+    utc: DateTime<Utc>,
+}
+
+#[derive(Serialize)]
+struct FunctionScheduleResponse {
+    cron: String,
+    timezone: String,
+    fire_times: Vec<FireTime>,
+}
+
+#[derive(Deserialize)]
+struct RunScheduledFunctionQuery {
+    /// Run this revision instead of the active one, e.g. to try it before it goes live
+    revision: Option<i32>,
+    /// Respond once the run finished, with its outcome and logs
+    #[serde(default)]
+    wait: bool,
+}
+
+#[derive(Serialize)]
+struct RunScheduledFunctionResponse {
+    run_id: Uuid,
+}
+
+#[derive(Serialize)]
+struct FunctionDeadLettersResponse {
+    dead_letters: Vec<domain::run::DeadLetter>,
+}
+
+#[derive(Deserialize)]
+struct RollbackFunctionPayload {
+    revision: i32,
+    /// Keep the revision active on later deploys
+    #[serde(default)]
+    pin: bool,
+}
+
+#[derive(Serialize)]
+struct RollbackFunctionResponse {
+    revision: i32,
+    pinned: bool,
+}
+
+#[derive(Deserialize)]
+struct SetFunctionRolloutPayload {
+    /// The revision receiving a share of the traffic
+    revision: i32,
+    #[serde(flatten)]
+    config: domain::rollout::RolloutConfig,
+}
+
+#[derive(Serialize)]
+struct FunctionRolloutResponse {
+    #[serde(flatten)]
+    rollout: domain::rollout::Rollout,
+    /// Canary requests observed by this runtime instance
+    #[serde(flatten)]
+    outcomes: CanaryOutcomes,
+}
+
 #[derive(Serialize)]
 struct ScopeFunctionItem {
     name: String,
@@ -72,15 +306,18 @@ async fn delete_http_function(
     State(state): State<RuntimeStateRef>,
     Path(path): Path<FunctionPath>,
 ) -> impl IntoResponse {
-    function_service::delete_http_func(
+    let deleted = function_service::delete_http_func(
         &state.db,
-        &*state.cache_backend,
+        &state.route_cache,
+        &state.invalidator,
         &*state.storage_backend,
+        &state.artifact_cache,
         &path.function_id,
     )
-    .await
-    .map(|_| StatusCode::ACCEPTED)
-    .into_response()
+    .await;
+    state.instance_pool.invalidate_function(path.function_id);
+
+    deleted.map(|_| StatusCode::ACCEPTED).into_response()
 }
 
 async fn delete_scheduled_function(
@@ -90,10 +327,551 @@ async fn delete_scheduled_function(
     function_service::delete_scheduled_func(
         &state.db,
         &*state.scheduler_manager,
+        &state.invalidator,
         &*state.storage_backend,
+        &state.artifact_cache,
         &path.function_id,
     )
     .await
     .map(|_| StatusCode::ACCEPTED)
     .into_response()
 }
+
+/// Finds the function of the scope, so functions are only exposed through their own scope
+async fn ensure_scope_function(
+    state: &RuntimeStateRef,
+    path: &FunctionPath,
+) -> Result<domain::function::Function, axum::response::Response> {
+    function_service::find_all_funcs(&state.db, &path.scope)
+        .await
+        .map_err(|err| err.into_response())?
+        .into_iter()
+        .find(|function| function.uuid() == path.function_id)
+        .ok_or_else(|| StatusCode::NOT_FOUND.into_response())
+}
+
+/// Finds the scheduled function of the scope, schedules, runs and dead letters only exist for scheduled functions
+async fn ensure_scope_scheduled_function(
+    state: &RuntimeStateRef,
+    path: &FunctionPath,
+) -> Result<domain::function::ScheduledFunction, axum::response::Response> {
+    match ensure_scope_function(state, path).await? {
+        domain::function::Function::Scheduled(function) => Ok(function),
+        domain::function::Function::Http(_) => Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                message: "Only available for scheduled functions",
+            }),
+        )
+            .into_response()),
+    }
+}
+
+async fn list_function_logs(
+    State(state): State<RuntimeStateRef>,
+    Path(path): Path<FunctionPath>,
+    Query(query): Query<FunctionLogsQuery>,
+) -> impl IntoResponse {
+    if let Err(response) = ensure_scope_function(&state, &path).await {
+        return response;
+    }
+
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_LOG_LIMIT)
+        .min(log_service::MAX_LOGS_PER_FUNCTION);
+
+    log_service::find_logs(&state.db, &path.function_id, &(&query).into(), limit)
+        .await
+        .map(|logs| Json(FunctionLogsResponse { logs }))
+        .into_response()
+}
+
+async fn stream_function_logs(
+    State(state): State<RuntimeStateRef>,
+    Path(path): Path<FunctionPath>,
+    Query(query): Query<FunctionLogStreamQuery>,
+) -> impl IntoResponse {
+    if let Err(response) = ensure_scope_function(&state, &path).await {
+        return response;
+    }
+
+    // Lines are stored after the invocation, so the stream follows the insertion order, not the timestamps
+    let after_sequence = match query.after {
+        Some(_) => 0,
+        None => match log_service::latest_log_sequence(&state.db, &path.function_id).await {
+            Ok(sequence) => sequence,
+            Err(err) => return err.into_response(),
+        },
+    };
+    let filter = LogFilter {
+        request_id: query.request_id,
+        level: query.level,
+        since: query.after,
+        ..Default::default()
+    };
+
+    Sse::new(tail_logs(state, path.function_id, filter, after_sequence))
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+/// Polls the stored logs and emits every new line as `log` event until the client disconnects
+fn tail_logs(
+    state: RuntimeStateRef,
+    function_id: Uuid,
+    filter: LogFilter,
+    after_sequence: i64,
+) -> impl Stream<Item = Result<Event, Infallible>> {
+    stream::unfold(after_sequence, move |after_sequence| {
+        let state = state.clone();
+        let filter = filter.clone();
+        async move {
+            tokio::time::sleep(LOG_STREAM_POLL_INTERVAL).await;
+            let logs = match log_service::find_logs_after(
+                &state.db,
+                &function_id,
+                &filter,
+                after_sequence,
+                log_service::MAX_LOGS_PER_FUNCTION,
+            )
+            .await
+            {
+                Ok(logs) => logs,
+                Err(err) => {
+                    tracing::error!("Failed to read logs of function {function_id}: {err}");
+                    Vec::new()
+                }
+            };
+            let next = logs
+                .last()
+                .map(|(sequence, _)| *sequence)
+                .unwrap_or(after_sequence);
+            Some((stream::iter(logs), next))
+        }
+    })
+    .flatten()
+    .filter_map(|(sequence, entry)| async move {
+        Event::default()
+            .event("log")
+            .id(sequence.to_string())
+            .json_data(entry)
+            .ok()
+    })
+    .map(Ok)
+}
+
+async fn list_function_revisions(
+    State(state): State<RuntimeStateRef>,
+    Path(path): Path<FunctionPath>,
+) -> impl IntoResponse {
+    let function = match ensure_scope_function(&state, &path).await {
+        Ok(function) => function,
+        Err(response) => return response,
+    };
+    let (active_revision, pinned) = match &function {
+        domain::function::Function::Http(function) => (function.revision, function.pinned),
+        domain::function::Function::Scheduled(function) => (function.revision, function.pinned),
+    };
+
+    revision_service::find_revisions(&state.db, &path.function_id)
+        .await
+        .map(|revisions| {
+            Json(FunctionRevisionsResponse {
+                pinned,
+                revisions: revisions
+                    .into_iter()
+                    .map(|revision| FunctionRevisionItem {
+                        active: Some(revision.revision) == active_revision,
+                        revision,
+                    })
+                    .collect(),
+            })
+        })
+        .into_response()
+}
+
+async fn list_function_runs(
+    State(state): State<RuntimeStateRef>,
+    Path(path): Path<FunctionPath>,
+    Query(query): Query<FunctionRunsQuery>,
+) -> impl IntoResponse {
+    if let Err(response) = ensure_scope_scheduled_function(&state, &path).await {
+        return response;
+    }
+
+    run_service::find_runs(
+        &state.db,
+        &path.function_id,
+        query.limit.unwrap_or(DEFAULT_RUN_LIMIT),
+    )
+    .await
+    .map(|runs| Json(FunctionRunsResponse { runs }))
+    .into_response()
+}
+
+async fn get_function_run(
+    State(state): State<RuntimeStateRef>,
+    Path(path): Path<FunctionRunPath>,
+) -> impl IntoResponse {
+    if let Err(response) = ensure_scope_function(&state, &path.function()).await {
+        return response;
+    }
+
+    let run = match run_service::find_run(&state.db, &path.function_id, &path.run_id).await {
+        Ok(Some(run)) => run,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(err) => return err.into_response(),
+    };
+
+    run_response(&state, run).await
+}
+
+/// Responds with the run and the logs it wrote
+async fn run_response(
+    state: &RuntimeStateRef,
+    run: domain::run::ScheduledRun,
+) -> axum::response::Response {
+    // The logs of a run are stored with its id as request id
+    let filter = LogFilter {
+        request_id: Some(run.id.to_string()),
+        ..Default::default()
+    };
+    log_service::find_logs(
+        &state.db,
+        &run.function_id,
+        &filter,
+        log_service::MAX_LOGS_PER_FUNCTION,
+    )
+    .await
+    .map(|logs| Json(FunctionRunResponse { run, logs }))
+    .into_response()
+}
+
+/// Previews the next fire times of the scheduled function
+async fn get_function_schedule(
+    State(state): State<RuntimeStateRef>,
+    Path(path): Path<FunctionPath>,
+    Query(query): Query<FunctionScheduleQuery>,
+) -> impl IntoResponse {
+    let function = match ensure_scope_scheduled_function(&state, &path).await {
+        Ok(function) => function,
+        Err(response) => return response,
+    };
+    // Functions deployed before schedules were validated may have an invalid one
+    let schedule = match Schedule::new(&function.cron, function.timezone.as_deref()) {
+        Ok(schedule) => schedule,
+        Err(err) => {
+            return (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(ErrorResponse {
+                    message: &err.to_string(),
+                }),
+            )
+                .into_response()
+        }
+    };
+
+    let count = query
+        .count
+        .unwrap_or(DEFAULT_FIRE_TIME_COUNT)
+        .min(MAX_FIRE_TIME_COUNT);
+    let fire_times = schedule
+        .fire_times(&Utc::now())
+        .take(count)
+        .map(|utc| FireTime {
+            local: utc.with_timezone(&schedule.timezone()).fixed_offset(),
+            utc,
+        })
+        .collect();
+
+    Json(FunctionScheduleResponse {
+        cron: function.cron,
+        timezone: schedule.timezone().name().to_owned(),
+        fire_times,
+    })
+    .into_response()
+}
+
+/// Runs the scheduled function right away, outside of its schedule and without retries
+async fn run_scheduled_function(
+    State(state): State<RuntimeStateRef>,
+    Path(path): Path<FunctionPath>,
+    Query(query): Query<RunScheduledFunctionQuery>,
+) -> impl IntoResponse {
+    let function = match ensure_scope_scheduled_function(&state, &path).await {
+        Ok(function) => function,
+        Err(response) => return response,
+    };
+
+    let function = match query.revision {
+        Some(revision) if function.revision != Some(revision) => {
+            match function_service::find_scheduled_func_revision(
+                &state.db,
+                &path.function_id,
+                revision,
+            )
+            .await
+            {
+                Ok(Some(function)) => function,
+                Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+                Err(err) => return err.into_response(),
+            }
+        }
+        _ => function,
+    };
+
+    let run_id = Uuid::new_v4();
+    if query.wait {
+        let run = state.scheduler_manager.run_now(&function, run_id).await;
+        return run_response(&state, run).await;
+    }
+
+    tokio::spawn(async move {
+        state.scheduler_manager.run_now(&function, run_id).await;
+    });
+    (
+        StatusCode::ACCEPTED,
+        Json(RunScheduledFunctionResponse { run_id }),
+    )
+        .into_response()
+}
+
+async fn list_function_dead_letters(
+    State(state): State<RuntimeStateRef>,
+    Path(path): Path<FunctionPath>,
+) -> impl IntoResponse {
+    if let Err(response) = ensure_scope_scheduled_function(&state, &path).await {
+        return response;
+    }
+
+    dead_letter_service::find_dead_letters(&state.db, &path.function_id)
+        .await
+        .map(|dead_letters| Json(FunctionDeadLettersResponse { dead_letters }))
+        .into_response()
+}
+
+/// Runs the function again with its active revision, the dead letter is parked anew if it keeps failing
+async fn replay_function_dead_letter(
+    State(state): State<RuntimeStateRef>,
+    Path(path): Path<FunctionDeadLetterPath>,
+) -> impl IntoResponse {
+    let function = match ensure_scope_scheduled_function(&state, &path.function()).await {
+        Ok(function) => function,
+        Err(response) => return response,
+    };
+
+    match dead_letter_service::delete_dead_letter(
+        &state.db,
+        &path.function_id,
+        &path.dead_letter_id,
+    )
+    .await
+    {
+        Ok(true) => {
+            state.scheduler_manager.dispatch(&function).await;
+            StatusCode::ACCEPTED.into_response()
+        }
+        Ok(false) => StatusCode::NOT_FOUND.into_response(),
+        Err(err) => err.into_response(),
+    }
+}
+
+async fn discard_function_dead_letter(
+    State(state): State<RuntimeStateRef>,
+    Path(path): Path<FunctionDeadLetterPath>,
+) -> impl IntoResponse {
+    if let Err(response) = ensure_scope_scheduled_function(&state, &path.function()).await {
+        return response;
+    }
+
+    match dead_letter_service::delete_dead_letter(
+        &state.db,
+        &path.function_id,
+        &path.dead_letter_id,
+    )
+    .await
+    {
+        Ok(true) => StatusCode::ACCEPTED.into_response(),
+        Ok(false) => StatusCode::NOT_FOUND.into_response(),
+        Err(err) => err.into_response(),
+    }
+}
+
+async fn rollback_function(
+    State(state): State<RuntimeStateRef>,
+    Path(path): Path<FunctionPath>,
+    Json(payload): Json<RollbackFunctionPayload>,
+) -> impl IntoResponse {
+    if let Err(response) = ensure_scope_function(&state, &path).await {
+        return response;
+    }
+
+    let function = match function_service::rollback_func(
+        &state.db,
+        &path.function_id,
+        payload.revision,
+        payload.pin,
+    )
+    .await
+    {
+        Ok(Some(function)) => function,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(err) => return err.into_response(),
+    };
+
+    // Drop everything that refers to the previously active revision
+    match &function {
+        domain::function::Function::Http(_) => {
+            state.route_cache.invalidate(&path.scope).await;
+            state.instance_pool.invalidate_function(path.function_id);
+        }
+        domain::function::Function::Scheduled(scheduled_function) => {
+            state.scheduler_manager.remove(&path.function_id).await;
+            state.scheduler_manager.add(scheduled_function).await;
+        }
+    }
+    state.invalidator.publish(path.updated()).await;
+
+    Json(RollbackFunctionResponse {
+        revision: payload.revision,
+        pinned: payload.pin,
+    })
+    .into_response()
+}
+
+async fn unpin_function(
+    State(state): State<RuntimeStateRef>,
+    Path(path): Path<FunctionPath>,
+) -> impl IntoResponse {
+    if let Err(response) = ensure_scope_function(&state, &path).await {
+        return response;
+    }
+
+    function_service::unpin_func(&state.db, &path.function_id)
+        .await
+        .map(|_| StatusCode::ACCEPTED)
+        .into_response()
+}
+
+async fn get_function_rollout(
+    State(state): State<RuntimeStateRef>,
+    Path(path): Path<FunctionPath>,
+) -> impl IntoResponse {
+    if let Err(response) = ensure_scope_function(&state, &path).await {
+        return response;
+    }
+
+    match rollout_service::find_rollout(&state.db, &path.function_id).await {
+        Ok(Some(rollout)) => Json(FunctionRolloutResponse {
+            outcomes: state
+                .rollout_stats
+                .outcomes(&path.function_id, rollout.revision),
+            rollout,
+        })
+        .into_response(),
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(err) => err.into_response(),
+    }
+}
+
+async fn set_function_rollout(
+    State(state): State<RuntimeStateRef>,
+    Path(path): Path<FunctionPath>,
+    Json(payload): Json<SetFunctionRolloutPayload>,
+) -> impl IntoResponse {
+    let function = match ensure_scope_function(&state, &path).await {
+        Ok(function) => function,
+        Err(response) => return response,
+    };
+
+    let invalid = match &function {
+        domain::function::Function::Scheduled(_) => Some("Only HTTP functions can be rolled out"),
+        domain::function::Function::Http(function)
+            if function.revision == Some(payload.revision) =>
+        {
+            Some("The revision is already active")
+        }
+        domain::function::Function::Http(_) => payload.config.validate().err(),
+    };
+    if let Some(message) = invalid {
+        return (StatusCode::BAD_REQUEST, Json(ErrorResponse { message })).into_response();
+    }
+
+    let rollout = match rollout_service::set_rollout(
+        &state.db,
+        &path.function_id,
+        payload.revision,
+        &payload.config,
+    )
+    .await
+    {
+        Ok(Some(rollout)) => rollout,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(err) => return err.into_response(),
+    };
+
+    // Routes carry the canary, changed thresholds start with fresh outcomes
+    state.route_cache.invalidate(&path.scope).await;
+    state.rollout_stats.reset(&path.function_id);
+    state.invalidator.publish(path.updated()).await;
+
+    Json(FunctionRolloutResponse {
+        rollout,
+        outcomes: CanaryOutcomes::default(),
+    })
+    .into_response()
+}
+
+async fn abort_function_rollout(
+    State(state): State<RuntimeStateRef>,
+    Path(path): Path<FunctionPath>,
+) -> impl IntoResponse {
+    if let Err(response) = ensure_scope_function(&state, &path).await {
+        return response;
+    }
+
+    let deleted = match rollout_service::delete_rollout(&state.db, &path.function_id, None).await {
+        Ok(deleted) => deleted,
+        Err(err) => return err.into_response(),
+    };
+
+    state.route_cache.invalidate(&path.scope).await;
+    state.instance_pool.invalidate_function(path.function_id);
+    state.rollout_stats.reset(&path.function_id);
+    state.invalidator.publish(path.updated()).await;
+
+    if deleted {
+        StatusCode::ACCEPTED.into_response()
+    } else {
+        StatusCode::NOT_FOUND.into_response()
+    }
+}
+
+async fn promote_function_rollout(
+    State(state): State<RuntimeStateRef>,
+    Path(path): Path<FunctionPath>,
+) -> impl IntoResponse {
+    if let Err(response) = ensure_scope_function(&state, &path).await {
+        return response;
+    }
+
+    let function = match rollout_service::promote_rollout(&state.db, &path.function_id, None).await
+    {
+        Ok(Some(domain::function::Function::Http(function))) => function,
+        Ok(_) => return StatusCode::NOT_FOUND.into_response(),
+        Err(err) => return err.into_response(),
+    };
+
+    // The canary revision now serves all traffic
+    state.route_cache.invalidate(&path.scope).await;
+    state.instance_pool.invalidate_function(path.function_id);
+    state.rollout_stats.reset(&path.function_id);
+    state.invalidator.publish(path.updated()).await;
+
+    Json(RollbackFunctionResponse {
+        revision: function.revision.unwrap_or_default(),
+        pinned: function.pinned,
+    })
+    .into_response()
+}
@@ -1,6 +1,7 @@
 mod deploy_handler;
 mod function_handler;
 mod scope_handler;
+mod secret_handler;
 mod variable_handler;
 
 use crate::{domain, server_state::RuntimeStateRef, services::function_service};
@@ -14,6 +15,7 @@ pub(crate) fn router(
         .nest("/deploy", deploy_handler::router())
         .nest("/scope", scope_handler::router())
         .nest("/scope/{scope}/variable", variable_handler::router())
+        .nest("/scope/{scope}/secret", secret_handler::router())
         .nest("/scope/{scope}/function", function_handler::router())
         .route_layer(axum::middleware::from_fn_with_state(
             app_state,
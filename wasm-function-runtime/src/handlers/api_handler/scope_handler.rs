@@ -7,7 +7,9 @@ use axum::{
 };
 use serde::Serialize;
 
-use crate::{domain, server_state::RuntimeStateRef, services::scope_service};
+use crate::{
+    domain, invalidation::InvalidationEvent, server_state::RuntimeStateRef, services::scope_service,
+};
 
 pub(super) fn router() -> Router<RuntimeStateRef> {
     Router::new()
@@ -38,8 +40,23 @@ async fn delete_scope(
     State(state): State<RuntimeStateRef>,
     Path(scope_name): Path<String>,
 ) -> impl IntoResponse {
-    scope_service::delete_scope(&state.db, &scope_name)
-        .await
-        .map(|_| StatusCode::ACCEPTED)
-        .into_response()
+    let deleted =
+        scope_service::delete_scope(&state.db, &*state.key_value_backend, &scope_name).await;
+    state.route_cache.invalidate(&scope_name).await;
+    state.instance_pool.invalidate_scope(&scope_name);
+
+    let function_ids = deleted.as_ref().cloned().unwrap_or_default();
+    for function_id in &function_ids {
+        state.rollout_stats.reset(function_id);
+        state.scheduler_manager.remove(function_id).await;
+    }
+    state
+        .invalidator
+        .publish(InvalidationEvent::ScopeDeleted {
+            scope: scope_name,
+            function_ids,
+        })
+        .await;
+
+    deleted.map(|_| StatusCode::ACCEPTED).into_response()
 }
@@ -0,0 +1,142 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{delete, get, post},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{domain, RuntimeStateRef};
+use crate::{invalidation::InvalidationEvent, services::secret_service, utils::ErrorResponse};
+
+// Secret values are write-only, none of the responses contain them
+pub(super) fn router() -> Router<RuntimeStateRef> {
+    Router::new()
+        .route("/", get(list_scope_secrets))
+        .route("/", post(set_scope_secret))
+        .route("/{secret_id}", delete(delete_scope_secret))
+}
+
+#[derive(Deserialize)]
+struct ScopeSecretPath {
+    scope: String,
+    secret_id: Uuid,
+}
+
+#[derive(Deserialize)]
+struct SetScopeSecretPayload {
+    name: String,
+    value: String,
+}
+
+#[derive(Serialize)]
+struct ScopeSecretItem {
+    uuid: String,
+    name: String,
+}
+
+#[derive(Serialize)]
+struct ScopeSecretListResponse {
+    secrets: Vec<ScopeSecretItem>,
+}
+
+impl From<domain::secret::Secret> for ScopeSecretItem {
+    fn from(secret: domain::secret::Secret) -> Self {
+        Self {
+            uuid: secret.uuid.to_string(),
+            name: secret.name,
+        }
+    }
+}
+
+impl From<Vec<domain::secret::Secret>> for ScopeSecretListResponse {
+    fn from(secrets: Vec<domain::secret::Secret>) -> Self {
+        Self {
+            secrets: secrets.into_iter().map(ScopeSecretItem::from).collect(),
+        }
+    }
+}
+
+async fn list_scope_secrets(
+    State(state): State<RuntimeStateRef>,
+    Path(scope_name): Path<String>,
+) -> impl IntoResponse {
+    secret_service::find_all_secrets(&state.db, &scope_name)
+        .await
+        .map(ScopeSecretListResponse::from)
+        .map(Json)
+        .into_response()
+}
+
+async fn set_scope_secret(
+    State(state): State<RuntimeStateRef>,
+    Path(scope_name): Path<String>,
+    Json(payload): Json<SetScopeSecretPayload>,
+) -> impl IntoResponse {
+    if !domain::secret::is_valid_secret_name(&payload.name) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                message: "Secret names may only contain ASCII letters, digits and underscores",
+            }),
+        )
+            .into_response();
+    }
+
+    let secret = secret_service::set_secret(
+        &state.db,
+        &state.secret_cipher,
+        &scope_name,
+        &payload.name,
+        &payload.value,
+    )
+    .await;
+    if let Ok(Some(_)) = &secret {
+        // Pooled functions captured the previous secrets
+        state.instance_pool.invalidate_scope(&scope_name);
+        state
+            .invalidator
+            .publish(InvalidationEvent::VariablesChanged { scope: scope_name })
+            .await;
+    }
+
+    secret
+        .map(|secret| match secret {
+            Some(secret) => {
+                (StatusCode::CREATED, Json(ScopeSecretItem::from(secret))).into_response()
+            }
+            None => StatusCode::NOT_FOUND.into_response(),
+        })
+        .into_response()
+}
+
+async fn delete_scope_secret(
+    State(state): State<RuntimeStateRef>,
+    Path(scope_secret_path): Path<ScopeSecretPath>,
+) -> impl IntoResponse {
+    match secret_service::delete_secret(
+        &state.db,
+        &scope_secret_path.scope,
+        &scope_secret_path.secret_id,
+    )
+    .await
+    {
+        Ok(true) => {}
+        Ok(false) => return StatusCode::NOT_FOUND.into_response(),
+        Err(err) => return err.into_response(),
+    }
+
+    state
+        .instance_pool
+        .invalidate_scope(&scope_secret_path.scope);
+    state
+        .invalidator
+        .publish(InvalidationEvent::VariablesChanged {
+            scope: scope_secret_path.scope,
+        })
+        .await;
+
+    StatusCode::ACCEPTED.into_response()
+}
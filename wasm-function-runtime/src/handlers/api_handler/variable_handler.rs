@@ -9,7 +9,7 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use super::{domain, RuntimeStateRef};
-use crate::services::variable_service;
+use crate::{invalidation::InvalidationEvent, services::variable_service};
 
 pub(super) fn router() -> Router<RuntimeStateRef> {
     Router::new()
@@ -115,8 +115,16 @@ async fn create_scope_variable(
     Path(scope_name): Path<String>,
     Json(payload): Json<CreateScopeVariablePayload>,
 ) -> impl IntoResponse {
-    variable_service::create_var(&state.db, &scope_name, &payload.name, &payload.value)
-        .await
+    let created =
+        variable_service::create_var(&state.db, &scope_name, &payload.name, &payload.value).await;
+    // Pooled functions captured the previous variables
+    state.instance_pool.invalidate_scope(&scope_name);
+    state
+        .invalidator
+        .publish(InvalidationEvent::VariablesChanged { scope: scope_name })
+        .await;
+
+    created
         .map(CreatedScopeVariableResponse::from)
         .map(Json)
         .into_response()
@@ -144,30 +152,50 @@ async fn update_scope_variable(
     Path(scope_variable_path): Path<ScopeVariablePath>,
     Json(payload): Json<UpdateScopeVariablePayload>,
 ) -> impl IntoResponse {
-    variable_service::update_var(
+    let updated = variable_service::update_var(
         &state.db,
         &scope_variable_path.variable_id,
         payload.name.as_deref(),
         payload.value.as_deref(),
     )
-    .await
-    .map(|model| {
-        if let Some(model) = model {
-            let response: ScopeVariableResponse = model.into();
-            Json(response).into_response()
-        } else {
-            StatusCode::NOT_FOUND.into_response()
-        }
-    })
-    .into_response()
+    .await;
+    state
+        .instance_pool
+        .invalidate_scope(&scope_variable_path.scope);
+    state
+        .invalidator
+        .publish(InvalidationEvent::VariablesChanged {
+            scope: scope_variable_path.scope,
+        })
+        .await;
+
+    updated
+        .map(|model| {
+            if let Some(model) = model {
+                let response: ScopeVariableResponse = model.into();
+                Json(response).into_response()
+            } else {
+                StatusCode::NOT_FOUND.into_response()
+            }
+        })
+        .into_response()
 }
 
 async fn delete_scope_variable(
     State(state): State<RuntimeStateRef>,
     Path(scope_variable_path): Path<ScopeVariablePath>,
 ) -> impl IntoResponse {
-    variable_service::delete_var_by_id(&state.db, &scope_variable_path.variable_id)
-        .await
-        .map(|_| StatusCode::ACCEPTED)
-        .into_response()
+    let deleted =
+        variable_service::delete_var_by_id(&state.db, &scope_variable_path.variable_id).await;
+    state
+        .instance_pool
+        .invalidate_scope(&scope_variable_path.scope);
+    state
+        .invalidator
+        .publish(InvalidationEvent::VariablesChanged {
+            scope: scope_variable_path.scope,
+        })
+        .await;
+
+    deleted.map(|_| StatusCode::ACCEPTED).into_response()
 }
@@ -1,23 +1,36 @@
 use axum::{
     body::Body,
     extract::{Path, Query, State},
-    http::{HeaderMap, StatusCode},
-    response::IntoResponse,
-    routing::method_routing::get,
+    http::{HeaderMap, Method, StatusCode, Uri},
+    response::Response,
+    routing::method_routing::any,
 };
+use tracing::{error, info};
 
 use crate::{
-    bindings_function_http,
-    domain::function::WasmFunctionTrait,
+    bindings_function_http, bindings_function_http_streaming,
+    component::{
+        errors::InvocationError,
+        http::{FunctionHttp, FunctionHttpBuilder, HttpFunctionInstance, PreparedFunctionHttp},
+        http_streaming, limits,
+        logs::LogCollector,
+        pool::InstancePoolKey,
+        wasi_http,
+    },
+    domain::{
+        function::{HttpFunction, WasmFunctionTrait},
+        rollout::RolloutVerdict,
+    },
+    invalidation::InvalidationEvent,
+    keyvalue::ScopedKeyValue,
+    middlewares::auth,
+    routing::{rollout::Canary, RouteParams},
     server_state::RuntimeStateRef,
-    services::{function_service, variable_service},
+    services::{function_service, log_service, rollout_service, secret_service, variable_service},
 };
 
 pub(crate) fn router() -> axum::Router<RuntimeStateRef> {
-    axum::Router::new().route(
-        "/{scope}/{*function_path}",
-        get(handle_get_request).post(handle_post_request),
-    )
+    axum::Router::new().route("/{scope}/{*function_path}", any(handle_request))
 }
 
 #[derive(Debug, serde::Deserialize)]
@@ -26,189 +39,637 @@ struct FunctionParams {
     function_path: String,
 }
 
-async fn handle_get_request(
+async fn handle_request(
     Path(path): Path<FunctionParams>,
     State(state): State<RuntimeStateRef>,
+    method: Method,
+    uri: Uri,
     Query(query_map): Query<std::collections::HashMap<String, String>>,
-    header_map: HeaderMap,
+    mut header_map: HeaderMap,
     body: Body,
-) -> impl IntoResponse {
+) -> Result<Response, InvocationError> {
+    // Announced oversized bodies are rejected right away, others fail once they exceed the limit
+    let max_body_bytes = state.app_config.max_request_body_bytes;
+    let content_length = header_map
+        .get(http::header::CONTENT_LENGTH)
+        .and_then(|length| length.to_str().ok())
+        .and_then(|length| length.parse::<u64>().ok());
+    if content_length.is_some_and(|length| length > max_body_bytes as u64) {
+        return Err(InvocationError::PayloadTooLarge(max_body_bytes));
+    }
+    let body = Body::new(http_body_util::Limited::new(body, max_body_bytes));
+
     // Bootstrap the function
-    let (function, mut function_store) =
-        if let Some(func) = bootstrap_function(state.clone(), &path, "GET").await {
-            func
-        } else {
-            return StatusCode::NOT_FOUND.into_response();
-        };
+    let (function, mut function_store, route_params, logs, canary) =
+        bootstrap_function(state.clone(), &path, method.as_str(), &mut header_map).await?;
 
-    // Prepare the request to be passed to the function
-    let req = bindings_function_http::Request {
-        path: format!("/{}", path.function_path),
-        query_params: collect_query_params(query_map),
-        headers: collect_headers(header_map),
-        method: bindings_function_http::Method::Get,
-        body: axum::body::to_bytes(body, usize::MAX)
+    let response = match function {
+        HttpFunctionInstance::FunctionHttp(function) => {
+            // Prepare the request to be passed to the function
+            let req = bindings_function_http::Request {
+                path: format!("/{}", path.function_path),
+                path_params: collect_path_params(route_params, |name, value| {
+                    bindings_function_http::PathParam { name, value }
+                }),
+                query_params: collect_query_params(query_map, |name, value| {
+                    bindings_function_http::QueryParam { name, value }
+                }),
+                headers: collect_headers(header_map, |name, value| {
+                    bindings_function_http::Header { name, value }
+                }),
+                method: collect_method(&method)
+                    .filter(|method| function.supports(*method))
+                    .ok_or(InvocationError::MethodNotAllowed)?,
+                body: axum::body::to_bytes(body, usize::MAX)
+                    .await
+                    .map_err(|err| body_error(err, max_body_bytes))?
+                    .to_vec(),
+            };
+
+            call_function_http(state.clone(), function, function_store, req, logs).await
+        }
+        HttpFunctionInstance::FunctionHttpStreaming(function) => {
+            // The body is passed as stream, the function reads it while it is received
+            let req = bindings_function_http_streaming::Request {
+                path: format!("/{}", path.function_path),
+                path_params: collect_path_params(route_params, |name, value| {
+                    bindings_function_http_streaming::PathParam { name, value }
+                }),
+                query_params: collect_query_params(query_map, |name, value| {
+                    bindings_function_http_streaming::QueryParam { name, value }
+                }),
+                headers: collect_headers(header_map, |name, value| {
+                    bindings_function_http_streaming::Header { name, value }
+                }),
+                method: collect_method(&method)
+                    .ok_or(InvocationError::MethodNotAllowed)?
+                    .into(),
+                body: http_streaming::request_body(&mut function_store, body, max_body_bytes)?,
+            };
+
+            let log_state = state.clone();
+            http_streaming::serve(function, function_store, req, move || {
+                persist_logs(log_state, logs)
+            })
             .await
-            .expect("Failed to read body")
-            .to_vec(),
+            .and_then(|(head, body)| {
+                into_http_response(
+                    head.status_code,
+                    head.headers
+                        .into_iter()
+                        .map(|header| (header.name, header.value)),
+                    body,
+                )
+            })
+        }
+        HttpFunctionInstance::WasiHttp(proxy) => {
+            let req = wasi_http_request(method, &uri, header_map, body)?;
+
+            // Bodies stream while the function runs, so its logs are complete once it returned
+            let log_state = state.clone();
+            wasi_http::serve(proxy, function_store, req, move || {
+                persist_logs(log_state, logs)
+            })
+            .await
+        }
     };
 
-    // Execute the function
-    let function_response = function
-        .call_handle_request(&mut function_store, &req)
-        .await
-        .expect("Failed to call function")
-        .expect("Function retunred a failure");
+    // Judge the canary by the requests it served
+    let Some(canary) = canary else {
+        return response;
+    };
+    match response {
+        Err(err) => {
+            record_canary_outcome(
+                &state,
+                &path.scope,
+                canary,
+                err.status_code().is_server_error(),
+            );
+            Err(err)
+        }
+        // Streamed bodies may still fail after the head was sent, the outcome is known once they ended
+        Ok(response) => Ok(response.map(|body| {
+            Body::new(ObservedBody {
+                body,
+                ended: Some(Box::new(move |failed| {
+                    record_canary_outcome(&state, &path.scope, canary, failed)
+                })),
+            })
+        })),
+    }
+}
 
-    // Return the response
-    function_response.into_response()
+fn record_canary_outcome(state: &RuntimeStateRef, scope: &str, canary: Canary, failed: bool) {
+    if let Some(verdict) = state.rollout_stats.record(&canary, failed) {
+        tokio::spawn(apply_rollout_verdict(
+            state.clone(),
+            scope.to_string(),
+            canary,
+            verdict,
+        ));
+    }
 }
 
-async fn handle_post_request(
-    Path(path): Path<FunctionParams>,
-    State(state): State<RuntimeStateRef>,
-    Query(query_map): Query<std::collections::HashMap<String, String>>,
-    header_map: HeaderMap,
+/// Response body telling whether it ended completely or failed.
+/// Bodies the client stopped reading early tell nothing, the function is not to blame.
+struct ObservedBody {
     body: Body,
-) -> impl IntoResponse {
-    // Bootstrap the function
-    let (function, mut function_store) =
-        if let Some(func) = bootstrap_function(state.clone(), &path, "POST").await {
-            func
-        } else {
-            return StatusCode::NOT_FOUND.into_response();
+    ended: Option<Box<dyn FnOnce(bool) + Send>>,
+}
+
+impl http_body::Body for ObservedBody {
+    type Data = bytes::Bytes;
+    type Error = axum::Error;
+
+    fn poll_frame(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Result<http_body::Frame<Self::Data>, Self::Error>>> {
+        let frame = std::pin::Pin::new(&mut self.body).poll_frame(cx);
+        let failed = match &frame {
+            std::task::Poll::Ready(None) => Some(false),
+            std::task::Poll::Ready(Some(Err(_))) => Some(true),
+            _ => None,
         };
+        if let Some(failed) = failed {
+            if let Some(ended) = self.ended.take() {
+                ended(failed);
+            }
+        }
+        frame
+    }
 
-    // Prepare the request to be passed to the function
-    let req = bindings_function_http::Request {
-        path: format!("/{}", path.function_path),
-        query_params: collect_query_params(query_map),
-        headers: collect_headers(header_map),
-        method: bindings_function_http::Method::Post,
-        body: axum::body::to_bytes(body, usize::MAX)
-            .await
-            .expect("Failed to read body")
-            .to_vec(),
+    fn is_end_stream(&self) -> bool {
+        self.body.is_end_stream()
+    }
+
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.body.size_hint()
+    }
+}
+
+impl Drop for ObservedBody {
+    fn drop(&mut self) {
+        // Complete bodies are not necessarily polled to their end
+        if let Some(ended) = self.ended.take() {
+            if http_body::Body::is_end_stream(&self.body) {
+                ended(false);
+            }
+        }
+    }
+}
+
+/// Calls a function of the `function-http` world, which receives and returns bodies as a whole
+async fn call_function_http(
+    state: RuntimeStateRef,
+    function: FunctionHttp,
+    mut function_store: wasmtime::Store<crate::component::ComponentState>,
+    req: bindings_function_http::Request,
+    logs: LogCollector,
+) -> Result<Response, InvocationError> {
+    // Execute the function within its resource limits
+    let timeout = function_store.data().limits().timeout();
+    let call_result = limits::with_timeout(
+        timeout,
+        function.call_handle_request(&mut function_store, req),
+    )
+    .await;
+
+    persist_logs(state, logs);
+
+    call_result
+        .map_err(InvocationError::from)
+        .and_then(|call_result| {
+            call_result.map_err(|err| InvocationError::from_call_error(&function_store, err))
+        })
+        .and_then(|function_result| function_result.map_err(InvocationError::Guest))
+        .and_then(|response| {
+            into_http_response(
+                response.status_code,
+                response
+                    .headers
+                    .into_iter()
+                    .map(|header| (header.name, header.value)),
+                response.body.into(),
+            )
+        })
+}
+
+/// Persists the captured logs without delaying the response
+fn persist_logs(state: RuntimeStateRef, logs: LogCollector) {
+    tokio::spawn(async move {
+        let function_id = logs.function_id();
+        if let Err(err) = log_service::store_logs(&state.db, &function_id, logs.finish()).await {
+            error!("Failed to store logs of function '{function_id}': {err:?}");
+        }
+    });
+}
+
+/// Promotes or aborts the rollout as decided from the observed canary requests
+async fn apply_rollout_verdict(
+    state: RuntimeStateRef,
+    scope: String,
+    canary: Canary,
+    verdict: RolloutVerdict,
+) {
+    let function_id = canary.function.uuid;
+    let applied = match verdict {
+        RolloutVerdict::Promote => {
+            rollout_service::promote_rollout(&state.db, &function_id, Some(canary.revision))
+                .await
+                .map(|function| function.is_some())
+        }
+        RolloutVerdict::Abort => {
+            rollout_service::delete_rollout(&state.db, &function_id, Some(canary.revision)).await
+        }
     };
 
-    // Execute the function
-    let function_response = function
-        .call_handle_request(&mut function_store, &req)
-        .await
-        .expect("Failed to call function")
-        .expect("Function retunred a failure");
+    match applied {
+        Ok(true) => info!(
+            "Applied rollout verdict {verdict:?} to revision {} of function '{function_id}'",
+            canary.revision
+        ),
+        // The rollout ended or a rollout of another revision replaced it meanwhile
+        Ok(false) => {
+            info!(
+                "Dropped rollout verdict {verdict:?} for revision {} of function '{function_id}', its rollout ended",
+                canary.revision
+            );
+            return;
+        }
+        Err(err) => {
+            error!(
+                "Failed to apply rollout verdict {verdict:?} to function '{function_id}': {err:?}"
+            )
+        }
+    }
 
-    // Return the response
-    function_response.into_response()
+    state.route_cache.invalidate(&scope).await;
+    state.instance_pool.invalidate_function(function_id);
+    state.rollout_stats.reset(&function_id);
+    state
+        .invalidator
+        .publish(InvalidationEvent::FunctionUpdated { scope, function_id })
+        .await;
 }
 
 async fn bootstrap_function(
     state: RuntimeStateRef,
     path: &FunctionParams,
     method: &str,
-) -> Option<(
-    bindings_function_http::FunctionHttp,
-    wasmtime::Store<crate::component::ComponentState>,
-)> {
-    let function_vars = variable_service::find_all_vars(&state.db, &path.scope)
+    header_map: &mut HeaderMap,
+) -> Result<
+    (
+        HttpFunctionInstance,
+        wasmtime::Store<crate::component::ComponentState>,
+        RouteParams,
+        LogCollector,
+        Option<Canary>,
+    ),
+    InvocationError,
+> {
+    // Extract the target funtion from the database
+    let (active_function, route_params, canary) =
+        function_service::find_http_func_by_scope_and_req(
+            &state.db,
+            &state.route_cache,
+            &path.scope,
+            &path.function_path,
+            method,
+        )
+        .await?
+        .ok_or(InvocationError::NotFound)?;
+
+    // Private functions require an authenticated caller.
+    // Scopes have no members, any caller with a valid token may call any private function.
+    if !active_function.is_public {
+        let token = auth::bearer_token(header_map)
+            .ok_or(InvocationError::Unauthorized(StatusCode::UNAUTHORIZED))?;
+        auth::authorize_user_by_token(token, &state.jwk_cache, &state.app_config.openid_connect)
+            .await
+            .map_err(InvocationError::Unauthorized)?;
+    }
+
+    let canary = select_variant(canary, active_function.is_public, header_map);
+    let http_function_details = match &canary {
+        Some(canary) => canary.function.clone(),
+        None => active_function,
+    };
+
+    // Tag the captured logs with the id assigned by the request id middleware
+    let request_id = header_map
+        .get("x-request-id")
+        .and_then(|request_id| request_id.to_str().ok())
+        .map(str::to_string);
+    let logs = LogCollector::new(http_function_details.uuid, request_id);
+
+    let pool_key = InstancePoolKey {
+        function_id: http_function_details.uuid,
+        content_hash: http_function_details.content_hash.clone(),
+    };
+
+    // Reuse the linked function of previous requests, compile and link it otherwise
+    let prepared_function = match state.instance_pool.get(&pool_key).await {
+        Some(prepared_function) => prepared_function,
+        None => {
+            let generation = state.instance_pool.generation();
+            let prepared_function = std::sync::Arc::new(
+                prepare_function(&state, &path.scope, &http_function_details).await?,
+            );
+            state
+                .instance_pool
+                .insert(pool_key, &path.scope, prepared_function.clone(), generation)
+                .await;
+            prepared_function
+        }
+    };
+
+    // Instantiate the function
+    let (function, function_store) = prepared_function
+        .instantiate(logs.clone())
         .await
-        .expect("Failed to find variables");
+        .map_err(InvocationError::Component)?;
 
-    // Extract the target funtion from the database
-    let http_function_details = function_service::find_http_func_by_scope_and_req(
+    Ok((function, function_store, route_params, logs, canary))
+}
+
+async fn prepare_function(
+    state: &RuntimeStateRef,
+    scope: &str,
+    http_function_details: &HttpFunction,
+) -> Result<PreparedFunctionHttp, InvocationError> {
+    let function_vars = variable_service::find_all_vars(&state.db, scope).await?;
+    let function_secrets = secret_service::find_secret_values(
         &state.db,
-        &path.scope,
-        &path.function_path,
-        method,
+        &state.secret_cipher,
+        &http_function_details.scope_id,
     )
-    .await
-    .expect("Failed to find function");
+    .await?;
 
-    let http_function_details = match http_function_details {
-        Some(details) => details,
-        None => return None,
-    };
+    let function_limits = http_function_details
+        .limits
+        .within(&state.app_config.function_limits);
 
-    let precompiled_cache_key = format!("pre-{}", http_function_details.related_wasm());
+    // Reuse the compiled function of previous deploys and requests
+    let component = state
+        .artifact_cache
+        .load(&http_function_details.related_wasm())
+        .await?;
+    let http_function_builder = FunctionHttpBuilder::from_component(&state.engine, component)
+        .map_err(InvocationError::Component)?;
 
-    // Try to get previously compiled function from the cache
-    if let Some(cached_function_bytes) = state
-        .cache_backend
-        .get(&precompiled_cache_key)
-        .await
-        .expect("Failed to interact with cache")
-    {
-        // Deserialize the function from the cache
-        let http_function_builder = unsafe {
-            crate::component::http::FunctionHttpBuilder::deserialize(
-                &state.engine,
-                &cached_function_bytes,
-            )
-            .with_variables(&function_vars)
-        };
+    // Link the function once, it is instantiated per request
+    http_function_builder
+        .with_variables(&function_vars)
+        .with_secrets(&function_secrets)
+        .with_limits(&function_limits)
+        .with_key_value(ScopedKeyValue::new(
+            state.key_value_backend.clone(),
+            http_function_details.scope_id,
+        ))
+        .prepare(http_function_details.world)
+        .map_err(InvocationError::Component)
+}
 
-        // Build the function
-        Some(http_function_builder.build().await)
-    } else {
-        // Extract the function from the storage backend
-        let function_bytes = state
-            .storage_backend
-            .extract_file_bytes(&http_function_details.related_wasm())
-            .await
-            .expect("Failed to get function");
+/// The request as a `wasi:http` function receives it, with the path relative to the scope
+fn wasi_http_request(
+    method: Method,
+    uri: &Uri,
+    header_map: HeaderMap,
+    body: Body,
+) -> Result<http::Request<Body>, InvocationError> {
+    // Only the scope is stripped, the path stays percent-encoded
+    let function_path = uri.path().splitn(3, '/').nth(2).unwrap_or_default();
+    let path_and_query = match uri.query() {
+        Some(query) => format!("/{function_path}?{query}"),
+        None => format!("/{function_path}"),
+    };
 
-        // Compile the function from the bytes
-        let http_function_builder = crate::component::http::FunctionHttpBuilder::from_binary(
-            &state.engine,
-            &function_bytes,
-        )
-        .with_variables(&function_vars);
+    // The function learns the authority from the URI of HTTP/2 requests and from the host header otherwise
+    if uri.authority().is_none() && !header_map.contains_key(http::header::HOST) {
+        return Err(InvocationError::InvalidRequest(
+            "missing host header".to_string(),
+        ));
+    }
+    let mut uri_parts = uri.clone().into_parts();
+    uri_parts.path_and_query = Some(path_and_query.parse().map_err(|_| {
+        InvocationError::InvalidRequest(format!("invalid path '{path_and_query}'"))
+    })?);
 
-        // Cache the compiled function
-        state
-            .cache_backend
-            .insert(&precompiled_cache_key, http_function_builder.serialize())
-            .await
-            .expect("Failed to cache function");
+    let mut request = http::Request::new(body);
+    *request.method_mut() = method;
+    *request.uri_mut() = Uri::from_parts(uri_parts)
+        .map_err(|err| InvocationError::InvalidRequest(err.to_string()))?;
+    *request.headers_mut() = header_map;
+    Ok(request)
+}
 
-        // Build the function
-        Some(http_function_builder.build().await)
+fn collect_method(method: &Method) -> Option<bindings_function_http::Method> {
+    match *method {
+        Method::GET => Some(bindings_function_http::Method::Get),
+        Method::POST => Some(bindings_function_http::Method::Post),
+        Method::PUT => Some(bindings_function_http::Method::Put),
+        Method::PATCH => Some(bindings_function_http::Method::Patch),
+        Method::DELETE => Some(bindings_function_http::Method::Delete),
+        Method::HEAD => Some(bindings_function_http::Method::Head),
+        Method::OPTIONS => Some(bindings_function_http::Method::Options),
+        _ => None,
     }
 }
 
-fn collect_query_params(
+/// Collects the route parameters as name-value records of the function's world
+fn collect_path_params<T>(
+    route_params: RouteParams,
+    record: impl Fn(String, String) -> T,
+) -> Vec<T> {
+    route_params
+        .into_iter()
+        .map(|(name, value)| record(name, value))
+        .collect()
+}
+
+fn collect_query_params<T>(
     query_map: std::collections::HashMap<String, String>,
-) -> Vec<bindings_function_http::QueryParam> {
+    record: impl Fn(String, String) -> T,
+) -> Vec<T> {
     query_map
-        .iter()
-        .map(|(key, value)| bindings_function_http::QueryParam {
-            name: key.clone(),
-            value: value.clone(),
-        })
+        .into_iter()
+        .map(|(name, value)| record(name, value))
         .collect()
 }
 
-fn collect_headers(header_map: HeaderMap) -> Vec<bindings_function_http::Header> {
+/// A rolled out function serves a share of the requests with its canary revision.
+/// The sticky key is read before the credentials of callers of private functions are stripped.
+fn select_variant(
+    canary: Option<Canary>,
+    is_public: bool,
+    header_map: &mut HeaderMap,
+) -> Option<Canary> {
+    let canary = canary.filter(|canary| canary.selects(header_map));
+    if !is_public {
+        strip_credentials(header_map);
+    }
+    canary
+}
+
+/// Removes the credentials of the caller, the function must not act on behalf of the caller with them
+fn strip_credentials(header_map: &mut HeaderMap) {
+    header_map.remove(http::header::AUTHORIZATION);
+    header_map.remove(http::header::COOKIE);
+}
+
+fn collect_headers<T>(header_map: HeaderMap, record: impl Fn(String, String) -> T) -> Vec<T> {
     header_map
         .iter()
-        .map(|(key, value)| bindings_function_http::Header {
-            name: key.as_str().to_string(),
-            value: value.to_str().unwrap().to_string(),
+        .map(|(key, value)| {
+            record(
+                key.as_str().to_string(),
+                String::from_utf8_lossy(value.as_bytes()).into_owned(),
+            )
         })
         .collect()
 }
 
-impl IntoResponse for bindings_function_http::Response {
-    fn into_response(self) -> axum::http::Response<Body> {
-        let mut response = axum::http::Response::new(self.body.into());
-        for header in self.headers {
-            response.headers_mut().insert(
-                http::HeaderName::from_bytes(header.name.as_bytes()).expect("Invalid header name"),
-                http::HeaderValue::from_str(&header.value).expect("Invalid header value"),
+fn into_http_response(
+    status_code: u16,
+    headers: impl IntoIterator<Item = (String, String)>,
+    body: Body,
+) -> Result<Response, InvocationError> {
+    let mut response = Response::new(body);
+    for (name, value) in headers {
+        response.headers_mut().append(
+            http::HeaderName::from_bytes(name.as_bytes()).map_err(|_| {
+                InvocationError::InvalidResponse(format!("invalid header name '{name}'"))
+            })?,
+            http::HeaderValue::from_str(&value).map_err(|_| {
+                InvocationError::InvalidResponse(format!("invalid value for header '{name}'"))
+            })?,
+        );
+    }
+    *response.status_mut() = http::StatusCode::from_u16(status_code).map_err(|_| {
+        InvocationError::InvalidResponse(format!("invalid status code {status_code}"))
+    })?;
+    Ok(response)
+}
+
+/// Bodies exceeding the maximum request size are rejected with 413 instead of being read any further
+fn body_error(err: axum::Error, max_body_bytes: usize) -> InvocationError {
+    if limits::exceeds_body_limit(&err) {
+        InvocationError::PayloadTooLarge(max_body_bytes)
+    } else {
+        InvocationError::InvalidBody(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use futures::StreamExt;
+    use http_body_util::BodyExt;
+
+    use super::*;
+    use crate::domain::rollout::RolloutConfig;
+
+    fn observed(body: Body) -> (ObservedBody, Arc<Mutex<Option<bool>>>) {
+        let outcome = Arc::new(Mutex::new(None));
+        let recorded = outcome.clone();
+        let body = ObservedBody {
+            body,
+            ended: Some(Box::new(move |failed| {
+                *recorded.lock().unwrap() = Some(failed)
+            })),
+        };
+        (body, outcome)
+    }
+
+    #[test]
+    fn strip_credentials_of_the_caller() {
+        let mut header_map = HeaderMap::new();
+        header_map.insert(http::header::AUTHORIZATION, "Bearer token".parse().unwrap());
+        header_map.insert(http::header::COOKIE, "session=secret".parse().unwrap());
+        header_map.insert(http::header::ACCEPT, "text/plain".parse().unwrap());
+
+        strip_credentials(&mut header_map);
+
+        assert_eq!(header_map.len(), 1);
+        assert!(header_map.contains_key(http::header::ACCEPT));
+    }
+
+    #[test]
+    fn pin_caller_of_private_function_by_sticky_cookie() {
+        let canary = Canary {
+            function: HttpFunction {
+                uuid: uuid::Uuid::new_v4(),
+                name: "private".to_string(),
+                path: "/private".to_string(),
+                method: "GET".to_string(),
+                is_public: false,
+                scope_id: uuid::Uuid::new_v4(),
+                content_hash: "hash".to_string(),
+                limits: Default::default(),
+                revision: Some(2),
+                pinned: false,
+                world: Default::default(),
+            },
+            revision: 2,
+            config: RolloutConfig {
+                weight: 50,
+                sticky_header: None,
+                sticky_cookie: Some("session".to_string()),
+                error_threshold: None,
+                min_requests: 1,
+                promote_after: None,
+            },
+        };
+        let caller = |session: usize| {
+            let mut header_map = HeaderMap::new();
+            header_map.insert(http::header::AUTHORIZATION, "Bearer token".parse().unwrap());
+            header_map.insert(
+                http::header::COOKIE,
+                format!("session={session}").parse().unwrap(),
             );
+            header_map
+        };
+        let pinned = (0..)
+            .find(|session| canary.selects(&caller(*session)))
+            .unwrap();
+
+        for _ in 0..20 {
+            let mut header_map = caller(pinned);
+            assert!(select_variant(Some(canary.clone()), false, &mut header_map).is_some());
+            assert!(header_map.is_empty());
         }
-        *response.status_mut() =
-            http::StatusCode::from_u16(self.status_code).expect("Invalid status code");
-        response
+    }
+
+    #[tokio::test]
+    async fn observe_failure_of_streamed_body() {
+        let chunks = futures::stream::iter([
+            Ok(bytes::Bytes::from("partial")),
+            Err(std::io::Error::other("function trapped")),
+        ]);
+        let (body, outcome) = observed(Body::from_stream(chunks));
+
+        assert!(body.collect().await.is_err());
+        assert_eq!(*outcome.lock().unwrap(), Some(true));
+    }
+
+    #[tokio::test]
+    async fn observe_complete_body() {
+        let (body, outcome) = observed(Body::from("complete"));
+
+        assert_eq!(body.collect().await.unwrap().to_bytes(), "complete");
+        assert_eq!(*outcome.lock().unwrap(), Some(false));
+    }
+
+    #[tokio::test]
+    async fn ignore_body_the_client_stopped_reading() {
+        let chunks = futures::stream::iter([Ok::<_, std::io::Error>(bytes::Bytes::from("a"))])
+            .chain(futures::stream::pending());
+        let (body, outcome) = observed(Body::from_stream(chunks));
+
+        drop(body);
+        assert_eq!(*outcome.lock().unwrap(), None);
     }
 }
@@ -0,0 +1,9 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub(crate) enum InvalidationError {
+    #[error("Unable to encode or decode invalidation event")]
+    Json(#[from] serde_json::Error),
+    #[error("Interaction with Redis failed")]
+    Redis(#[from] redis::RedisError),
+}
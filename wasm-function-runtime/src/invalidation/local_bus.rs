@@ -0,0 +1,28 @@
+use tokio::sync::broadcast;
+
+use super::{InvalidationError, InvalidationMessage};
+
+/// Delivers events in memory, for a single runtime replica without Redis
+pub(crate) struct LocalBus {
+    sender: broadcast::Sender<InvalidationMessage>,
+}
+
+impl Default for LocalBus {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(super::BUS_CAPACITY);
+        Self { sender }
+    }
+}
+
+#[async_trait::async_trait]
+impl super::InvalidationBus for LocalBus {
+    async fn publish(&self, message: &InvalidationMessage) -> Result<(), InvalidationError> {
+        // Nobody missed the event if nobody subscribed
+        let _ = self.sender.send(message.clone());
+        Ok(())
+    }
+
+    fn subscribe(&self) -> broadcast::Receiver<InvalidationMessage> {
+        self.sender.subscribe()
+    }
+}
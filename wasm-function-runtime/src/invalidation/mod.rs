@@ -0,0 +1,288 @@
+pub(crate) mod error;
+pub(crate) mod local_bus;
+pub(crate) mod redis_bus;
+
+pub(crate) use error::InvalidationError;
+pub(crate) use local_bus::LocalBus;
+pub(crate) use redis_bus::RedisBus;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::{debug, error, warn};
+use uuid::Uuid;
+
+use crate::{server_state::RuntimeStateRef, services::function_service};
+
+/// Events buffered per subscriber before a slow subscriber misses some
+const BUS_CAPACITY: usize = 1024;
+
+/// A change that makes the in-process state of every runtime replica stale
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum InvalidationEvent {
+    FunctionCreated {
+        scope: String,
+        function_id: Uuid,
+    },
+    FunctionUpdated {
+        scope: String,
+        function_id: Uuid,
+    },
+    FunctionDeleted {
+        scope: String,
+        function_id: Uuid,
+    },
+    /// Variables or secrets of the scope changed
+    VariablesChanged {
+        scope: String,
+    },
+    ScopeDeleted {
+        scope: String,
+        /// Functions deleted with the scope, their timers only run on the replicas
+        #[serde(default)]
+        function_ids: Vec<Uuid>,
+    },
+    /// Events of other replicas may have been missed, e.g. after a lag or a lost Redis subscription.
+    /// Only raised by the bus or the subscription of the replica itself.
+    Resync,
+}
+
+/// An event and the replica it originates from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct InvalidationMessage {
+    origin: Uuid,
+    event: InvalidationEvent,
+}
+
+/// Broadcasts invalidation events to all runtime replicas
+#[async_trait::async_trait]
+pub(crate) trait InvalidationBus: Send + Sync {
+    async fn publish(&self, message: &InvalidationMessage) -> Result<(), InvalidationError>;
+    fn subscribe(&self) -> broadcast::Receiver<InvalidationMessage>;
+}
+
+/// Tells the other runtime replicas about changes this replica applied to its own state
+pub(crate) struct Invalidator {
+    bus: std::sync::Arc<dyn InvalidationBus>,
+    /// Identifies this runtime replica
+    replica_id: Uuid,
+}
+
+impl Invalidator {
+    pub(crate) fn new(bus: std::sync::Arc<dyn InvalidationBus>) -> Self {
+        Self {
+            bus,
+            replica_id: Uuid::new_v4(),
+        }
+    }
+
+    /// Publishes the event to the other replicas.
+    /// The change is already persisted, so failures are only logged.
+    pub(crate) async fn publish(&self, event: InvalidationEvent) {
+        let message = InvalidationMessage {
+            origin: self.replica_id,
+            event,
+        };
+        if let Err(err) = self.bus.publish(&message).await {
+            error!("Failed to publish {:?}: {err:?}", message.event);
+        }
+    }
+
+    /// Subscribes to the events of the other replicas
+    pub(crate) fn subscribe(&self) -> InvalidationSubscription {
+        InvalidationSubscription {
+            receiver: self.bus.subscribe(),
+            replica_id: self.replica_id,
+        }
+    }
+}
+
+pub(crate) struct InvalidationSubscription {
+    receiver: broadcast::Receiver<InvalidationMessage>,
+    replica_id: Uuid,
+}
+
+impl InvalidationSubscription {
+    /// Waits for the next event of another replica, the events of this replica are already applied
+    pub(crate) async fn next(&mut self) -> Option<InvalidationEvent> {
+        loop {
+            match self.receiver.recv().await {
+                Ok(message) if message.origin == self.replica_id => continue,
+                Ok(message) => return Some(message.event),
+                Err(RecvError::Lagged(missed)) => {
+                    warn!("Missed {missed} invalidation events of other replicas, resyncing");
+                    return Some(InvalidationEvent::Resync);
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+/// Applies the changes of other replicas to the caches and the scheduler of this replica
+pub(crate) fn spawn_reconciler(state: RuntimeStateRef) {
+    let mut subscription = state.invalidator.subscribe();
+    tokio::spawn(async move {
+        while let Some(event) = subscription.next().await {
+            debug!("Reconcile {event:?} of another replica");
+            reconcile(&state, &event).await;
+        }
+    });
+}
+
+async fn reconcile(state: &RuntimeStateRef, event: &InvalidationEvent) {
+    match event {
+        InvalidationEvent::FunctionCreated { scope, function_id }
+        | InvalidationEvent::FunctionUpdated { scope, function_id } => {
+            state.route_cache.invalidate(scope).await;
+            state.instance_pool.invalidate_function(*function_id);
+            state.rollout_stats.reset(function_id);
+
+            // Schedule the active revision, HTTP functions have no job
+            state.scheduler_manager.remove(function_id).await;
+            match function_service::find_scheduled_func(&state.db, function_id).await {
+                Ok(Some(function)) => state.scheduler_manager.add(&function).await,
+                Ok(None) => {}
+                Err(err) => error!("Failed to reschedule function '{function_id}': {err:?}"),
+            }
+        }
+        InvalidationEvent::FunctionDeleted { scope, function_id } => {
+            state.route_cache.invalidate(scope).await;
+            state.instance_pool.invalidate_function(*function_id);
+            state.rollout_stats.reset(function_id);
+            state.scheduler_manager.remove(function_id).await;
+        }
+        InvalidationEvent::VariablesChanged { scope } => {
+            state.instance_pool.invalidate_scope(scope);
+        }
+        InvalidationEvent::ScopeDeleted {
+            scope,
+            function_ids,
+        } => {
+            state.route_cache.invalidate(scope).await;
+            state.instance_pool.invalidate_scope(scope);
+            for function_id in function_ids {
+                state.rollout_stats.reset(function_id);
+                state.scheduler_manager.remove(function_id).await;
+            }
+        }
+        InvalidationEvent::Resync => resync(state).await,
+    }
+}
+
+/// Drops all cached state and schedules exactly the functions of the database
+async fn resync(state: &RuntimeStateRef) {
+    state.route_cache.invalidate_all();
+    state.instance_pool.invalidate_all();
+    resync_schedule(state).await;
+}
+
+/// Without a bus reaching the other replicas, the elected replica learns about scheduled functions
+/// deployed or deleted through them only from the database, so every replica resyncs periodically
+pub(crate) fn spawn_schedule_resync(state: RuntimeStateRef, period: std::time::Duration) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        // The scheduler was just started with the functions of the database
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            resync_schedule(&state).await;
+        }
+    });
+}
+
+/// Schedules exactly the functions of the database
+async fn resync_schedule(state: &RuntimeStateRef) {
+    let functions = match function_service::find_all_scheduled_func(&state.db).await {
+        Ok(functions) => functions,
+        Err(err) => {
+            error!("Failed to resync scheduled functions: {err:?}");
+            return;
+        }
+    };
+    let function_ids = functions.iter().map(|function| function.uuid).collect();
+    state.scheduler_manager.retain(&function_ids).await;
+    for function in &functions {
+        state.scheduler_manager.add(function).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variables_changed(scope: &str) -> InvalidationEvent {
+        InvalidationEvent::VariablesChanged {
+            scope: scope.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn deliver_events_of_other_replicas_only() {
+        let bus: std::sync::Arc<dyn InvalidationBus> = std::sync::Arc::new(LocalBus::default());
+        let replica_a = Invalidator::new(bus.clone());
+        let replica_b = Invalidator::new(bus);
+        let mut subscription = replica_b.subscribe();
+
+        replica_a.publish(variables_changed("first")).await;
+        replica_b.publish(variables_changed("own")).await;
+        replica_a.publish(variables_changed("second")).await;
+
+        assert_eq!(subscription.next().await, Some(variables_changed("first")));
+        assert_eq!(subscription.next().await, Some(variables_changed("second")));
+    }
+
+    #[tokio::test]
+    async fn resync_after_missed_events() {
+        let bus: std::sync::Arc<dyn InvalidationBus> = std::sync::Arc::new(LocalBus::default());
+        let replica_a = Invalidator::new(bus.clone());
+        let replica_b = Invalidator::new(bus);
+        let mut subscription = replica_b.subscribe();
+
+        for index in 0..=BUS_CAPACITY {
+            replica_a
+                .publish(variables_changed(&format!("scope-{index}")))
+                .await;
+        }
+
+        assert_eq!(subscription.next().await, Some(InvalidationEvent::Resync));
+        assert_eq!(
+            subscription.next().await,
+            Some(variables_changed("scope-1"))
+        );
+    }
+
+    #[test]
+    fn decode_scope_deleted_without_function_ids() {
+        let decoded: InvalidationEvent =
+            serde_json::from_str(r#"{"type":"scope_deleted","scope":"shop"}"#).unwrap();
+
+        assert_eq!(
+            decoded,
+            InvalidationEvent::ScopeDeleted {
+                scope: "shop".to_string(),
+                function_ids: Vec::new(),
+            }
+        );
+    }
+
+    #[test]
+    fn encode_event_with_its_type() {
+        let function_id = Uuid::new_v4();
+        let message = InvalidationMessage {
+            origin: Uuid::new_v4(),
+            event: InvalidationEvent::FunctionDeleted {
+                scope: "shop".to_string(),
+                function_id,
+            },
+        };
+
+        let encoded = serde_json::to_value(&message).unwrap();
+        assert_eq!(encoded["event"]["type"], "function_deleted");
+        assert_eq!(encoded["event"]["scope"], "shop");
+
+        let decoded: InvalidationMessage = serde_json::from_value(encoded).unwrap();
+        assert_eq!(decoded.event, message.event);
+    }
+}
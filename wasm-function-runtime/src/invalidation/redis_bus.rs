@@ -0,0 +1,87 @@
+use futures::StreamExt;
+use redis::{aio::ConnectionManager, AsyncCommands};
+use tokio::sync::broadcast;
+use tracing::{error, warn};
+
+use super::{InvalidationError, InvalidationEvent, InvalidationMessage};
+
+/// Channel all runtime replicas publish their invalidation events to
+const INVALIDATION_CHANNEL: &str = "functions:invalidation";
+
+/// Delivers events to all runtime replicas through Redis pub/sub
+pub(crate) struct RedisBus {
+    client: ConnectionManager,
+    sender: broadcast::Sender<InvalidationMessage>,
+}
+
+impl RedisBus {
+    pub(crate) async fn new(connection_str: &str) -> Self {
+        let redis_client =
+            redis::Client::open(connection_str).expect("Failed to create Redis client");
+        let client = redis_client
+            .get_connection_manager()
+            .await
+            .expect("Failed to create Redis connection manager");
+        let (sender, _) = broadcast::channel(super::BUS_CAPACITY);
+
+        tokio::spawn(forward_messages(redis_client, sender.clone()));
+
+        Self { client, sender }
+    }
+}
+
+/// Forwards the messages of the channel to the subscribers of this replica.
+/// Pub/sub connections don't reconnect on their own, so the channel is subscribed again once the connection is lost.
+/// Events published in between are lost, so the subscribers resync after subscribing again.
+async fn forward_messages(
+    redis_client: redis::Client,
+    sender: broadcast::Sender<InvalidationMessage>,
+) {
+    let mut subscribed_before = false;
+    loop {
+        match redis_client.get_async_pubsub().await {
+            Ok(mut pubsub) => match pubsub.subscribe(INVALIDATION_CHANNEL).await {
+                Ok(()) => {
+                    if std::mem::replace(&mut subscribed_before, true) {
+                        let _ = sender.send(InvalidationMessage {
+                            origin: uuid::Uuid::nil(),
+                            event: InvalidationEvent::Resync,
+                        });
+                    }
+                    let mut messages = pubsub.on_message();
+                    while let Some(message) = messages.next().await {
+                        let decoded = message
+                            .get_payload::<String>()
+                            .map_err(InvalidationError::from)
+                            .and_then(|payload| Ok(serde_json::from_str(&payload)?));
+                        match decoded {
+                            Ok(message) => {
+                                let _ = sender.send(message);
+                            }
+                            Err(err) => error!("Failed to decode invalidation event: {err:?}"),
+                        }
+                    }
+                    warn!("Lost subscription to invalidation events, events of other replicas are missed until it is back");
+                }
+                Err(err) => warn!("Failed to subscribe to invalidation events: {err:?}"),
+            },
+            Err(err) => warn!("Failed to connect to Redis for invalidation events: {err:?}"),
+        }
+
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+}
+
+#[async_trait::async_trait]
+impl super::InvalidationBus for RedisBus {
+    async fn publish(&self, message: &InvalidationMessage) -> Result<(), InvalidationError> {
+        let payload = serde_json::to_string(message)?;
+        let mut con = self.client.clone();
+        let _: () = con.publish(INVALIDATION_CHANNEL, payload).await?;
+        Ok(())
+    }
+
+    fn subscribe(&self) -> broadcast::Receiver<InvalidationMessage> {
+        self.sender.subscribe()
+    }
+}
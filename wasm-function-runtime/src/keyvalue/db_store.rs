@@ -0,0 +1,86 @@
+use sea_orm::{prelude::*, sea_query::OnConflict, QuerySelect, Set};
+
+use super::KeyValueError;
+use crate::db::DbPool;
+
+/// Stores values in the `key_value` table of the runtime database
+pub(crate) struct DbKeyValueStore {
+    db_pool: DbPool,
+}
+
+impl DbKeyValueStore {
+    pub(crate) fn new(db_pool: DbPool) -> Self {
+        Self { db_pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl super::KeyValueBackend for DbKeyValueStore {
+    async fn get(&self, scope_id: &Uuid, key: &str) -> Result<Option<Vec<u8>>, KeyValueError> {
+        Ok(entity::key_value::Entity::find()
+            .filter(entity::key_value::Column::ScopeId.eq(*scope_id))
+            .filter(entity::key_value::Column::Key.eq(key))
+            .one(&self.db_pool)
+            .await?
+            .map(|model| model.value))
+    }
+
+    async fn set(&self, scope_id: &Uuid, key: &str, value: Vec<u8>) -> Result<(), KeyValueError> {
+        entity::key_value::Entity::insert(entity::key_value::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            scope_id: Set(*scope_id),
+            key: Set(key.to_string()),
+            value: Set(value),
+        })
+        .on_conflict(
+            OnConflict::columns([
+                entity::key_value::Column::ScopeId,
+                entity::key_value::Column::Key,
+            ])
+            .update_column(entity::key_value::Column::Value)
+            .to_owned(),
+        )
+        .exec(&self.db_pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn delete(&self, scope_id: &Uuid, key: &str) -> Result<(), KeyValueError> {
+        entity::key_value::Entity::delete_many()
+            .filter(entity::key_value::Column::ScopeId.eq(*scope_id))
+            .filter(entity::key_value::Column::Key.eq(key))
+            .exec(&self.db_pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn list_keys(
+        &self,
+        scope_id: &Uuid,
+        prefix: Option<&str>,
+    ) -> Result<Vec<String>, KeyValueError> {
+        let mut query = entity::key_value::Entity::find()
+            .select_only()
+            .column(entity::key_value::Column::Key)
+            .filter(entity::key_value::Column::ScopeId.eq(*scope_id));
+        if let Some(prefix) = prefix {
+            query = query.filter(entity::key_value::Column::Key.starts_with(prefix));
+        }
+
+        let mut keys: Vec<String> = query.into_tuple().all(&self.db_pool).await?;
+        // LIKE treats wildcards in the prefix and, on SQLite, the case loosely
+        if let Some(prefix) = prefix {
+            keys.retain(|key| key.starts_with(prefix));
+        }
+        keys.sort();
+        Ok(keys)
+    }
+
+    async fn clear(&self, scope_id: &Uuid) -> Result<(), KeyValueError> {
+        entity::key_value::Entity::delete_many()
+            .filter(entity::key_value::Column::ScopeId.eq(*scope_id))
+            .exec(&self.db_pool)
+            .await?;
+        Ok(())
+    }
+}
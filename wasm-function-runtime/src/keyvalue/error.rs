@@ -0,0 +1,13 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub(crate) enum KeyValueError {
+    #[error("Key is empty or too long")]
+    InvalidKey,
+    #[error("Value is too large")]
+    ValueTooLarge,
+    #[error("Interaction with Database failed")]
+    Db(#[from] sea_orm::DbErr),
+    #[error("Interaction with Redis failed")]
+    Redis(#[from] redis::RedisError),
+}
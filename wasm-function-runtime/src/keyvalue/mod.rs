@@ -0,0 +1,91 @@
+pub(crate) mod db_store;
+pub(crate) mod error;
+
+pub(crate) use db_store::DbKeyValueStore;
+pub(crate) use error::KeyValueError;
+
+/// Maximum length of a key in bytes
+pub(crate) const MAX_KEY_BYTES: usize = 512;
+/// Maximum size of a single value in bytes
+pub(crate) const MAX_VALUE_BYTES: usize = 1024 * 1024;
+
+/// Persists values of functions, namespaced by the scope of the function
+#[async_trait::async_trait]
+pub(crate) trait KeyValueBackend: Send + Sync {
+    async fn get(&self, scope_id: &uuid::Uuid, key: &str)
+        -> Result<Option<Vec<u8>>, KeyValueError>;
+    async fn set(
+        &self,
+        scope_id: &uuid::Uuid,
+        key: &str,
+        value: Vec<u8>,
+    ) -> Result<(), KeyValueError>;
+    async fn delete(&self, scope_id: &uuid::Uuid, key: &str) -> Result<(), KeyValueError>;
+    async fn list_keys(
+        &self,
+        scope_id: &uuid::Uuid,
+        prefix: Option<&str>,
+    ) -> Result<Vec<String>, KeyValueError>;
+    /// Removes every key of the scope, e.g. after the scope was deleted
+    async fn clear(&self, scope_id: &uuid::Uuid) -> Result<(), KeyValueError>;
+}
+
+/// The key-value namespace of a single scope, handed to function instances
+#[derive(Clone)]
+pub(crate) struct ScopedKeyValue {
+    backend: std::sync::Arc<dyn KeyValueBackend>,
+    scope_id: uuid::Uuid,
+}
+
+impl ScopedKeyValue {
+    pub(crate) fn new(backend: std::sync::Arc<dyn KeyValueBackend>, scope_id: uuid::Uuid) -> Self {
+        Self { backend, scope_id }
+    }
+
+    pub(crate) async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, KeyValueError> {
+        validate_key(key)?;
+        self.backend.get(&self.scope_id, key).await
+    }
+
+    pub(crate) async fn set(&self, key: &str, value: Vec<u8>) -> Result<(), KeyValueError> {
+        validate_key(key)?;
+        if value.len() > MAX_VALUE_BYTES {
+            return Err(KeyValueError::ValueTooLarge);
+        }
+        self.backend.set(&self.scope_id, key, value).await
+    }
+
+    pub(crate) async fn delete(&self, key: &str) -> Result<(), KeyValueError> {
+        validate_key(key)?;
+        self.backend.delete(&self.scope_id, key).await
+    }
+
+    pub(crate) async fn list_keys(
+        &self,
+        prefix: Option<&str>,
+    ) -> Result<Vec<String>, KeyValueError> {
+        self.backend.list_keys(&self.scope_id, prefix).await
+    }
+}
+
+fn validate_key(key: &str) -> Result<(), KeyValueError> {
+    if key.is_empty() || key.len() > MAX_KEY_BYTES {
+        return Err(KeyValueError::InvalidKey);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reject_empty_and_oversized_keys() {
+        assert!(validate_key("cursor").is_ok());
+        assert!(matches!(validate_key(""), Err(KeyValueError::InvalidKey)));
+        assert!(matches!(
+            validate_key(&"k".repeat(MAX_KEY_BYTES + 1)),
+            Err(KeyValueError::InvalidKey)
+        ));
+    }
+}
@@ -6,9 +6,13 @@ pub(crate) mod config;
 mod db;
 pub(crate) mod domain;
 pub(crate) mod handlers;
+pub(crate) mod invalidation;
+pub(crate) mod keyvalue;
 pub(crate) mod middlewares;
 mod routes;
+pub(crate) mod routing;
 pub(crate) mod scheduler;
+pub(crate) mod secrets;
 pub(crate) mod server_state;
 pub(crate) mod services;
 pub(crate) mod startup;
@@ -23,11 +27,61 @@ pub(crate) mod bindings_function_http {
     });
 }
 
+/// HTTP functions built before path parameters and the `function-error` of guests
+pub(crate) mod bindings_function_http_v0_1 {
+    wasmtime::component::bindgen!({
+        world: "function-http",
+        path: "./wit-http-0.1/",
+        async: true,
+    });
+}
+
+pub(crate) mod bindings_function_http_streaming {
+    wasmtime::component::bindgen!({
+        world: "function-http-streaming",
+        path: "./wit-http/",
+        async: true,
+        // Streams are the ones of the WASI implementation, host implementations are shared with HTTP functions
+        with: {
+            "wasi:io": wasmtime_wasi::bindings::io,
+            "jontze:keyvalue/store": crate::bindings_function_http::jontze::keyvalue::store,
+            "jontze:log/logging": crate::bindings_function_http::jontze::log::logging,
+        },
+    });
+}
+
 pub(crate) mod bindings_function_scheduled {
     wasmtime::component::bindgen!({
         world: "function-scheduled",
         path: "./wit-scheduled/",
-        async: true
+        async: true,
+        // Share the host implementations with HTTP functions
+        with: {
+            "jontze:keyvalue/store": crate::bindings_function_http::jontze::keyvalue::store,
+            "jontze:log/logging": crate::bindings_function_http::jontze::log::logging,
+        },
+    });
+}
+
+/// Scheduled functions built before the job context was passed to them
+pub(crate) mod bindings_function_scheduled_v0_2 {
+    wasmtime::component::bindgen!({
+        world: "function-scheduled",
+        path: "./wit-scheduled-0.2/",
+        async: true,
+        with: {
+            "jontze:keyvalue/store": crate::bindings_function_http::jontze::keyvalue::store,
+            "jontze:log/logging": crate::bindings_function_http::jontze::log::logging,
+        },
+    });
+}
+
+/// Scheduled functions built before the `function-error` of guests
+pub(crate) mod bindings_function_scheduled_v0_1 {
+    wasmtime::component::bindgen!({
+        world: "function-scheduled",
+        path: "./wit-scheduled-0.1/",
+        async: true,
     });
 }
 
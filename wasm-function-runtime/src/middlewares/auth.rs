@@ -10,7 +10,6 @@ use tracing::error;
 
 const JWKS_ENTRY_CACHE_KEY: &str = "jwks";
 
-#[allow(unused)]
 #[derive(Debug, Clone, Deserialize)]
 pub(crate) struct AuthenticatedUser {
     /// Object ID of the user
@@ -22,18 +21,7 @@ pub(crate) async fn auth(
     mut req: Request,
     next: Next,
 ) -> Result<Response, StatusCode> {
-    let auth_header = req
-        .headers()
-        .get(http::header::AUTHORIZATION)
-        .and_then(|header| header.to_str().ok())
-        .and_then(|auth_header_value| {
-            if auth_header_value.starts_with("Bearer ") {
-                Some(auth_header_value.trim_start_matches("Bearer "))
-            } else {
-                None
-            }
-        })
-        .ok_or(StatusCode::UNAUTHORIZED)?;
+    let auth_header = bearer_token(req.headers()).ok_or(StatusCode::UNAUTHORIZED)?;
 
     let authenticated_user = authorize_user_by_token(
         auth_header,
@@ -47,7 +35,21 @@ pub(crate) async fn auth(
     Ok(next.run(req).await)
 }
 
-async fn authorize_user_by_token(
+/// Extracts the bearer token from the `Authorization` header, if present
+pub(crate) fn bearer_token(headers: &http::HeaderMap) -> Option<&str> {
+    headers
+        .get(http::header::AUTHORIZATION)
+        .and_then(|header| header.to_str().ok())
+        .and_then(|auth_header_value| {
+            if auth_header_value.starts_with("Bearer ") {
+                Some(auth_header_value.trim_start_matches("Bearer "))
+            } else {
+                None
+            }
+        })
+}
+
+pub(crate) async fn authorize_user_by_token(
     token: &str,
     jwks_cache: &crate::server_state::JwkSetCache,
     oidc_config: &crate::config::OpenIdConnectConfig,
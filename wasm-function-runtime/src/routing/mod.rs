@@ -0,0 +1,270 @@
+pub(crate) mod rollout;
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    sync::Arc,
+};
+
+use tracing::error;
+use uuid::Uuid;
+
+use crate::domain::function::HttpFunction;
+use rollout::Canary;
+
+/// Caches the resolved routes of a scope, keyed by the scope name
+pub(crate) type RouteCache = moka::future::Cache<String, Arc<ScopeRoutes>>;
+
+/// Path parameters extracted while matching a route, e.g. `("id", "42")` for `/users/{id}`
+pub(crate) type RouteParams = Vec<(String, String)>;
+
+pub(crate) fn new_route_cache() -> RouteCache {
+    moka::future::Cache::builder()
+        .time_to_live(std::time::Duration::from_secs(
+            60 * 5, /* 5 Minutes, deploys and deletes invalidate explicitly */
+        ))
+        .build()
+}
+
+/// All HTTP function routes of a single scope, loaded into a matching trie
+pub(crate) struct ScopeRoutes {
+    router: matchit::Router<Vec<HttpFunction>>,
+    /// Canaries receiving a share of the traffic, keyed by the function they roll out
+    canaries: HashMap<Uuid, Canary>,
+}
+
+impl ScopeRoutes {
+    pub(crate) fn new(functions: Vec<HttpFunction>) -> Self {
+        // Functions with different methods may share the same route
+        let mut functions_by_route: BTreeMap<String, Vec<HttpFunction>> = BTreeMap::new();
+        for function in functions {
+            functions_by_route
+                .entry(route_pattern(&function.path))
+                .or_default()
+                .push(function);
+        }
+
+        let mut router = matchit::Router::new();
+        for (route, functions) in functions_by_route {
+            if let Err(err) = router.insert(route.as_str(), functions) {
+                error!("Failed to register function route '{route}': {err}");
+            }
+        }
+
+        Self {
+            router,
+            canaries: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn with_canaries(mut self, canaries: HashMap<Uuid, Canary>) -> Self {
+        self.canaries = canaries;
+        self
+    }
+
+    /// The canary of a resolved function, if it is rolled out
+    pub(crate) fn canary(&self, function_id: &Uuid) -> Option<&Canary> {
+        self.canaries.get(function_id)
+    }
+
+    /// Resolves the most specific route for the path and picks the function handling the method.
+    /// Static segments win over parameters, parameters win over wildcards.
+    pub(crate) fn resolve(&self, path: &str, method: &str) -> Option<(HttpFunction, RouteParams)> {
+        let matched = self.router.at(path).ok()?;
+
+        // Prefer functions that explicitly declare the method over catch-all ones
+        let function = matched
+            .value
+            .iter()
+            .find(|func| {
+                func.methods()
+                    .any(|declared| declared.eq_ignore_ascii_case(method))
+            })
+            .or_else(|| {
+                matched
+                    .value
+                    .iter()
+                    .find(|func| func.accepts_method(method))
+            })?;
+
+        let params = matched
+            .params
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+
+        Some((function.clone(), params))
+    }
+}
+
+/// Translates a manifest path into the route syntax of the matcher.
+/// Wildcard segments may be written as `*rest` or `{*rest}`, parameters as `{id}`.
+pub(crate) fn route_pattern(path: &str) -> String {
+    let path = path.trim_start_matches('/');
+
+    let segments = path
+        .split('/')
+        .map(|segment| match segment.strip_prefix('*') {
+            Some(name) => format!("{{*{name}}}"),
+            None => segment.to_string(),
+        })
+        .collect::<Vec<String>>();
+
+    format!("/{}", segments.join("/"))
+}
+
+/// Checks that a manifest path can be registered as a route
+pub(crate) fn validate_route(path: &str) -> Result<(), String> {
+    matchit::Router::new()
+        .insert(route_pattern(path), ())
+        .map_err(|err| err.to_string())
+}
+
+/// Checks that a function with the path and methods can be routed next to the other functions of its scope.
+/// Conflicting routes or methods would leave one of the functions unreachable.
+pub(crate) fn check_route_conflicts(
+    others: &[HttpFunction],
+    path: &str,
+    method: &str,
+) -> Result<(), String> {
+    let route = route_pattern(path);
+    let methods = method.split(',').map(str::trim).collect::<Vec<&str>>();
+
+    let mut other_routes = BTreeSet::new();
+    for other in others {
+        let other_route = route_pattern(&other.path);
+        if other_route == route {
+            // Functions may share a route if they handle different methods
+            if let Some(shared) = methods.iter().find(|method| {
+                other
+                    .methods()
+                    .any(|declared| declared.eq_ignore_ascii_case(method))
+            }) {
+                return Err(format!(
+                    "Function '{}' already handles {shared} requests on '{}'",
+                    other.name, other.path
+                ));
+            }
+        } else {
+            other_routes.insert(other_route);
+        }
+    }
+
+    // Routes shared by other functions are registered once, like in the routes of the scope
+    let mut router = matchit::Router::new();
+    for other_route in other_routes {
+        if let Err(err) = router.insert(other_route.as_str(), ()) {
+            // Already conflicting routes can't be told apart from the new one
+            error!("Failed to check route '{other_route}': {err}");
+        }
+    }
+
+    match router.insert(route, ()) {
+        Ok(()) => Ok(()),
+        Err(err) => Err(format!(
+            "Path '{path}' conflicts with another function: {err}"
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn http_function(path: &str, method: &str) -> HttpFunction {
+        HttpFunction {
+            uuid: uuid::Uuid::new_v4(),
+            name: format!("{method} {path}"),
+            path: path.to_string(),
+            method: method.to_string(),
+            is_public: true,
+            scope_id: uuid::Uuid::new_v4(),
+            content_hash: "hash".to_string(),
+            limits: Default::default(),
+            revision: None,
+            pinned: false,
+            world: Default::default(),
+        }
+    }
+
+    #[test]
+    fn translate_wildcard_segments() {
+        assert_eq!(route_pattern("/files/*rest"), "/files/{*rest}");
+        assert_eq!(route_pattern("files/{*rest}"), "/files/{*rest}");
+        assert_eq!(route_pattern("/users/{id}"), "/users/{id}");
+    }
+
+    #[test]
+    fn resolve_path_parameters() {
+        let routes = ScopeRoutes::new(vec![http_function("/users/{id}", "GET")]);
+
+        let (function, params) = routes.resolve("/users/42", "GET").unwrap();
+
+        assert_eq!(function.path, "/users/{id}");
+        assert_eq!(params, vec![("id".to_string(), "42".to_string())]);
+        assert!(routes.resolve("/users/42", "POST").is_none());
+    }
+
+    #[test]
+    fn resolve_most_specific_route() {
+        let routes = ScopeRoutes::new(vec![
+            http_function("/files/*rest", "GET"),
+            http_function("/files/index", "GET"),
+            http_function("/users/{id}", "GET"),
+            http_function("/users/me", "GET"),
+        ]);
+
+        let (function, _) = routes.resolve("/users/me", "GET").unwrap();
+        assert_eq!(function.path, "/users/me");
+
+        let (function, _) = routes.resolve("/users/42", "GET").unwrap();
+        assert_eq!(function.path, "/users/{id}");
+
+        let (function, _) = routes.resolve("/files/index", "GET").unwrap();
+        assert_eq!(function.path, "/files/index");
+
+        let (function, params) = routes.resolve("/files/docs/readme", "GET").unwrap();
+        assert_eq!(function.path, "/files/*rest");
+        assert_eq!(
+            params,
+            vec![("rest".to_string(), "docs/readme".to_string())]
+        );
+    }
+
+    #[test]
+    fn prefer_explicit_method_over_any() {
+        let routes = ScopeRoutes::new(vec![
+            http_function("/items", "ANY"),
+            http_function("/items", "POST"),
+        ]);
+
+        let (function, _) = routes.resolve("/items", "POST").unwrap();
+        assert_eq!(function.method, "POST");
+
+        let (function, _) = routes.resolve("/items", "DELETE").unwrap();
+        assert_eq!(function.method, "ANY");
+    }
+
+    #[test]
+    fn detect_conflicting_routes() {
+        let others = vec![
+            http_function("/users/{id}", "GET"),
+            http_function("/users/{id}", "DELETE"),
+            http_function("/items", "ANY"),
+            http_function("/files/*rest", "GET,POST"),
+        ];
+
+        assert!(check_route_conflicts(&others, "/users/{id}", "POST").is_ok());
+        assert!(check_route_conflicts(&others, "/users/me", "GET").is_ok());
+        assert!(check_route_conflicts(&others, "/items", "POST").is_ok());
+        assert!(check_route_conflicts(&others, "/users/{name}", "GET").is_err());
+        assert!(check_route_conflicts(&others, "/users/{id}", "get").is_err());
+        assert!(check_route_conflicts(&others, "/items", "ANY").is_err());
+        assert!(check_route_conflicts(&others, "/files/{*path}", "DELETE").is_err());
+    }
+
+    #[test]
+    fn reject_invalid_routes() {
+        assert!(validate_route("/users/{id}").is_ok());
+        assert!(validate_route("/users/{id").is_err());
+    }
+}
@@ -0,0 +1,214 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use axum::http::{header, HeaderMap};
+use serde::Serialize;
+use sha2::Digest;
+use uuid::Uuid;
+
+use crate::domain::{
+    function::HttpFunction,
+    rollout::{RolloutConfig, RolloutVerdict},
+};
+
+/// The canary variant of an HTTP function, resolved together with its route
+#[derive(Clone)]
+pub(crate) struct Canary {
+    /// The function as deployed with the canary revision.
+    /// Path, method and visibility stay the ones of the active revision until it is promoted.
+    pub function: HttpFunction,
+    pub revision: i32,
+    pub config: RolloutConfig,
+}
+
+impl Canary {
+    /// Picks the variant serving the request, requests with a sticky key always get the same one
+    pub(crate) fn selects(&self, headers: &HeaderMap) -> bool {
+        let bucket = match self.sticky_key(headers) {
+            Some(key) => sticky_bucket(&self.function.uuid, key),
+            None => (Uuid::new_v4().as_u128() % 100) as u8,
+        };
+        bucket < self.config.weight
+    }
+
+    fn sticky_key<'a>(&self, headers: &'a HeaderMap) -> Option<&'a str> {
+        let header_value = self.config.sticky_header.as_deref().and_then(|name| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .filter(|value| !value.is_empty())
+        });
+        header_value.or_else(|| {
+            self.config
+                .sticky_cookie
+                .as_deref()
+                .and_then(|name| cookie_value(headers, name))
+        })
+    }
+}
+
+/// Maps the sticky key onto one of 100 buckets, stable across requests and runtime instances
+fn sticky_bucket(function_id: &Uuid, key: &str) -> u8 {
+    let digest = sha2::Sha256::new()
+        .chain_update(function_id.as_bytes())
+        .chain_update(key.as_bytes())
+        .finalize();
+    let mut prefix = [0u8; 8];
+    prefix.copy_from_slice(&digest[..8]);
+    (u64::from_be_bytes(prefix) % 100) as u8
+}
+
+fn cookie_value<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|cookies| cookies.split(';'))
+        .filter_map(|cookie| cookie.trim().split_once('='))
+        .find(|(cookie_name, _)| *cookie_name == name)
+        .map(|(_, value)| value)
+        .filter(|value| !value.is_empty())
+}
+
+/// Requests served by a canary and how many of them failed
+#[derive(Serialize, Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct CanaryOutcomes {
+    pub requests: u32,
+    pub failures: u32,
+    #[serde(skip)]
+    decided: bool,
+}
+
+/// Outcomes of the canary requests served by this runtime instance,
+/// keyed by the function and its canary revision
+#[derive(Default)]
+pub(crate) struct RolloutStats {
+    outcomes: Mutex<HashMap<(Uuid, i32), CanaryOutcomes>>,
+}
+
+impl RolloutStats {
+    /// Records a request served by the canary.
+    /// Returns the verdict the first time the observed outcomes call for one.
+    pub(crate) fn record(&self, canary: &Canary, failed: bool) -> Option<RolloutVerdict> {
+        let mut outcomes = self.outcomes.lock().unwrap();
+        let outcome = outcomes
+            .entry((canary.function.uuid, canary.revision))
+            .or_default();
+        if outcome.decided {
+            return None;
+        }
+
+        outcome.requests = outcome.requests.saturating_add(1);
+        if failed {
+            outcome.failures = outcome.failures.saturating_add(1);
+        }
+
+        let verdict = canary.config.evaluate(outcome.requests, outcome.failures);
+        outcome.decided = verdict.is_some();
+        verdict
+    }
+
+    pub(crate) fn outcomes(&self, function_id: &Uuid, revision: i32) -> CanaryOutcomes {
+        self.outcomes
+            .lock()
+            .unwrap()
+            .get(&(*function_id, revision))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Forgets the outcomes of all canaries of the function
+    pub(crate) fn reset(&self, function_id: &Uuid) {
+        self.outcomes
+            .lock()
+            .unwrap()
+            .retain(|(outcome_function_id, _), _| outcome_function_id != function_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderValue;
+
+    use super::*;
+
+    fn canary(weight: u8) -> Canary {
+        Canary {
+            function: HttpFunction {
+                uuid: Uuid::new_v4(),
+                name: "canary".to_string(),
+                path: "/canary".to_string(),
+                method: "GET".to_string(),
+                is_public: true,
+                scope_id: Uuid::new_v4(),
+                content_hash: "hash".to_string(),
+                limits: Default::default(),
+                revision: Some(2),
+                pinned: false,
+                world: Default::default(),
+            },
+            revision: 2,
+            config: RolloutConfig {
+                weight,
+                sticky_header: Some("x-user-id".to_string()),
+                sticky_cookie: Some("session".to_string()),
+                error_threshold: Some(10.0),
+                min_requests: 2,
+                promote_after: None,
+            },
+        }
+    }
+
+    #[test]
+    fn route_by_weight() {
+        let headers = HeaderMap::new();
+
+        assert!(!canary(0).selects(&headers));
+        assert!(canary(100).selects(&headers));
+    }
+
+    #[test]
+    fn sticky_assignment_is_stable() {
+        let canary = canary(50);
+        let mut headers = HeaderMap::new();
+        headers.insert("x-user-id", HeaderValue::from_static("user-42"));
+
+        let selected = canary.selects(&headers);
+        assert!((0..20).all(|_| canary.selects(&headers) == selected));
+    }
+
+    #[test]
+    fn read_sticky_key_from_header_or_cookie() {
+        let canary = canary(50);
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::COOKIE,
+            HeaderValue::from_static("theme=dark; session=abc"),
+        );
+        assert_eq!(canary.sticky_key(&headers), Some("abc"));
+
+        headers.insert("x-user-id", HeaderValue::from_static("user-42"));
+        assert_eq!(canary.sticky_key(&headers), Some("user-42"));
+    }
+
+    #[test]
+    fn decide_rollout_once() {
+        let stats = RolloutStats::default();
+        let canary = canary(50);
+
+        assert_eq!(stats.record(&canary, true), None);
+        assert_eq!(stats.record(&canary, true), Some(RolloutVerdict::Abort));
+        assert_eq!(stats.record(&canary, true), None);
+        assert_eq!(
+            stats
+                .outcomes(&canary.function.uuid, canary.revision)
+                .failures,
+            2
+        );
+
+        stats.reset(&canary.function.uuid);
+        assert_eq!(
+            stats.outcomes(&canary.function.uuid, canary.revision),
+            CanaryOutcomes::default()
+        );
+    }
+}
@@ -1,16 +1,45 @@
-use tracing::{debug, error};
+use tracing::{debug, error, warn};
 use wasmtime::Store;
 
 use crate::{
     bindings_function_scheduled,
-    component::ComponentState,
-    services::{function_service, variable_service},
+    component::{
+        artifact::ArtifactCache,
+        errors::InvocationError,
+        limits,
+        logs::LogCollector,
+        scheduled::{FunctionScheduled, FunctionScheduledBuilder},
+        ComponentState,
+    },
+    domain::{
+        self, function::WasmFunctionTrait, limits::ResourceLimits, retry::RetryPolicy,
+        run::ScheduledRun,
+    },
+    keyvalue::ScopedKeyValue,
+    scheduler::{lease::Fence, overlap::OverlapGuard, schedule::Schedule},
+    secrets::SecretCipher,
+    services::{
+        dead_letter_service, log_service, run_service, scope_service, secret_service,
+        variable_service,
+    },
 };
 
 #[async_trait::async_trait]
 pub(crate) trait FunctionSchedulerManagerTrait: Send + Sync {
-    async fn add(&self, function_id: &uuid::Uuid, cron_syntax: &str);
+    async fn add(&self, function: &domain::function::ScheduledFunction);
     async fn remove(&self, function_id: &uuid::Uuid);
+    /// Removes the timers of all functions except the given ones
+    async fn retain(&self, function_ids: &std::collections::HashSet<uuid::Uuid>);
+    /// Runs the function once outside of its schedule, retried like a scheduled run
+    async fn dispatch(&self, function: &domain::function::ScheduledFunction);
+    /// Runs the function once right away without retries and returns the recorded run.
+    /// The run isn't left to the leader, it runs on this replica. It still follows the
+    /// overlap policy and timeout of the function.
+    async fn run_now(
+        &self,
+        function: &domain::function::ScheduledFunction,
+        run_id: uuid::Uuid,
+    ) -> ScheduledRun;
 }
 
 #[async_trait::async_trait]
@@ -19,24 +48,36 @@ pub(crate) trait FunctionSchedulerExecutorTrait {
 }
 
 pub(crate) struct FunctionSchedulerImpl {
-    inner_scheduler: tokio_cron_scheduler::JobScheduler,
+    /// Timers only fire once the scheduler runs
+    running: tokio::sync::watch::Sender<bool>,
     state: crate::scheduler::state::SchedulerState,
 }
 
 impl FunctionSchedulerImpl {
+    #[allow(clippy::too_many_arguments)]
     pub(crate) async fn new(
         db_pool: crate::db::DbPool,
         wasm_engine: wasmtime::Engine,
-        storage_backend: std::sync::Arc<dyn crate::storage::StorageBackend>,
+        artifact_cache: std::sync::Arc<crate::component::artifact::ArtifactCache>,
+        default_limits: ResourceLimits,
+        key_value_backend: std::sync::Arc<dyn crate::keyvalue::KeyValueBackend>,
+        secret_cipher: std::sync::Arc<crate::secrets::SecretCipher>,
+        run_history_limit: u64,
+        leader_election: Option<std::sync::Arc<super::lease::LeaderElection>>,
     ) -> Self {
-        let inner_scheduler = tokio_cron_scheduler::JobScheduler::new()
-            .await
-            .expect("Failed to create scheduler");
-        let state =
-            crate::scheduler::state::SchedulerState::new(db_pool, wasm_engine, storage_backend)
-                .await;
+        let state = crate::scheduler::state::SchedulerState::new(
+            db_pool,
+            wasm_engine,
+            artifact_cache,
+            default_limits,
+            key_value_backend,
+            secret_cipher,
+            run_history_limit,
+            leader_election,
+        )
+        .await;
         Self {
-            inner_scheduler,
+            running: tokio::sync::watch::Sender::new(false),
             state,
         }
     }
@@ -44,125 +85,516 @@ impl FunctionSchedulerImpl {
 
 #[async_trait::async_trait]
 impl FunctionSchedulerManagerTrait for FunctionSchedulerImpl {
-    async fn add(&self, function_id: &uuid::Uuid, cron_syntax: &str) {
-        // Prepare variables to move into the job
-        let db_pool = self.state.db_pool.clone();
-        let engine = self.state.engine.clone();
-        let function_id = *function_id;
-        let binary_cache = self.state.binary_cache.clone();
-        let storage_backend = self.state.storage_backend.clone();
-
-        let cron_job = tokio_cron_scheduler::Job::new_async(cron_syntax, move |job_uuid, _lock| {
-            // Prepare variables to move into the async block
-            let db_pool = db_pool.clone();
-            let engine = engine.clone();
-            let binary_cache = binary_cache.clone();
-            let storage_backend = storage_backend.clone();
-
-            Box::pin(async move {
-                debug!("Execute scheduled function '{function_id}' ({job_uuid})",);
-                // Extract function variables
-                let funct_vars =
-                    variable_service::find_vars_by_scheduled_func_id(&db_pool, &function_id)
-                        .await
-                        .expect("Failed to find variables for function")
-                        .expect("Failed to find variables for function");
-
-                // Check if the function is in the cache
-                let (func, mut func_store): (
-                    bindings_function_scheduled::FunctionScheduled,
-                    Store<ComponentState>,
-                ) = if let Some(cached_serialized_bytes) = binary_cache.get(&function_id).await {
-                    // If it is, deserialize the function from the cache and execute
-                    let mut func_builder = unsafe {
-                        crate::component::scheduled::FunctionScheduledBuilder::deserialize(
-                            &engine,
-                            &cached_serialized_bytes,
-                        )
-                    };
-
-                    // Add the variables to the function store
-                    func_builder = func_builder.with_variables(&funct_vars);
-
-                    Some(func_builder.build().await)
-                } else {
-                    // Otherwise, fetch the function from the database
-                    if let Some((_, bytes)) = function_service::find_scheduled_func(
-                        &db_pool,
-                        &*storage_backend,
-                        &function_id,
-                    )
-                    .await
-                    .expect("Failed to find function")
-                    {
-                        // Exract the function from the storage
-                        let mut func_builder =
-                            crate::component::scheduled::FunctionScheduledBuilder::from_binary(
-                                &engine, &bytes,
-                            );
+    async fn add(&self, function: &domain::function::ScheduledFunction) {
+        let function_id = function.uuid;
+        // Deploys validate the schedule, older functions may still carry an invalid one
+        let schedule = match Schedule::new(&function.cron, function.timezone.as_deref()) {
+            Ok(schedule) => schedule,
+            Err(err) => {
+                error!("Failed to schedule function '{function_id}': {err}");
+                return;
+            }
+        };
+        let job = ScheduledJob::new(&self.state, function);
+        let leader_election = self.state.leader_election.clone();
+        let mut running = self.running.subscribe();
 
-                        // Serialize the function to the cache to speed up further executions
-                        let serialized_bytes = func_builder.serialize();
-                        binary_cache
-                            .insert(function_id.to_owned(), serialized_bytes)
-                            .await;
+        let timer = tokio::spawn(async move {
+            if running.wait_for(|running| *running).await.is_err() {
+                return;
+            }
 
-                        // Add the variables to the function store
-                        func_builder = func_builder.with_variables(&funct_vars);
+            let mut last_fire_time = chrono::Utc::now();
+            while let Some(fire_time) = schedule.next_after(&last_fire_time) {
+                let delay = (fire_time - chrono::Utc::now())
+                    .to_std()
+                    .unwrap_or_default();
+                tokio::time::sleep(delay).await;
+                // Fire times missed while the replica was stalled aren't caught up
+                last_fire_time = fire_time.max(chrono::Utc::now());
 
-                        Some(func_builder.build().await)
+                let job = job.clone();
+                let leader_election = leader_election.clone();
+                tokio::spawn(async move {
+                    // Every replica schedules the job, only the leader runs it
+                    let fence = if let Some(leader_election) = leader_election {
+                        let Some(fence) = leader_election.fence().await else {
+                            debug!(
+                                "Skip scheduled function '{function_id}', another replica leads"
+                            );
+                            return;
+                        };
+                        debug!(
+                            "Execute scheduled function '{function_id}' ({fire_time}) as leader (token {})",
+                            fence.token()
+                        );
+                        Some(fence)
                     } else {
+                        debug!("Execute scheduled function '{function_id}' ({fire_time})");
                         None
-                    }
-                }
-                .expect("Failed to find function");
-
-                // Execute the function
-                match func
-                    .call_run_job(&mut func_store)
-                    .await
-                    .expect("Failed to call function")
-                {
-                    Ok(_) => {
-                        debug!("Scheduled function executed successfully");
-                    }
-                    Err(e) => {
-                        error!("Scheduled function failed: {e:?}");
-                    }
-                };
-            })
-        })
-        .expect("Failed to setup cron job");
-
-        let job_id = self
-            .inner_scheduler
-            .add(cron_job)
-            .await
-            .expect("Failed to add job");
+                    };
+                    job.tick(fire_time, fence).await;
+                });
+            }
+        });
 
+        // A function has a single timer, also if it is added again without removing it first
+        if let Some(previous) = self.state.cache.remove(&function_id).await {
+            previous.abort();
+        }
         self.state
             .cache
-            .insert(function_id.to_owned(), job_id)
+            .insert(function_id, timer.abort_handle())
             .await;
     }
 
     async fn remove(&self, function_id: &uuid::Uuid) {
-        if let Some(job_id) = self.state.cache.remove(function_id).await {
-            self.inner_scheduler
-                .remove(&job_id)
+        // Runs already started aren't aborted with the timer
+        if let Some(timer) = self.state.cache.remove(function_id).await {
+            timer.abort();
+        }
+        self.state.overlap_guards.release(function_id);
+    }
+
+    async fn retain(&self, function_ids: &std::collections::HashSet<uuid::Uuid>) {
+        let stale: Vec<uuid::Uuid> = self
+            .state
+            .cache
+            .iter()
+            .map(|(function_id, _)| *function_id)
+            .filter(|function_id| !function_ids.contains(function_id))
+            .collect();
+        for function_id in stale {
+            self.remove(&function_id).await;
+        }
+    }
+
+    async fn dispatch(&self, function: &domain::function::ScheduledFunction) {
+        let job = ScheduledJob::new(&self.state, function);
+        tokio::spawn(async move {
+            job.run(uuid::Uuid::new_v4(), RunTrigger::manual(), None)
                 .await
-                .expect("Failed to remove function from scheduler");
-            self.state.binary_cache.remove(function_id).await;
+        });
+    }
+
+    async fn run_now(
+        &self,
+        function: &domain::function::ScheduledFunction,
+        run_id: uuid::Uuid,
+    ) -> ScheduledRun {
+        let job = ScheduledJob {
+            retry: None,
+            ..ScheduledJob::new(&self.state, function)
+        };
+        job.guarded(run_id, RunTrigger::manual(), None).await
+    }
+}
+
+/// What started a run, handed to the function with each attempt
+#[derive(Clone, Copy)]
+struct RunTrigger {
+    /// The fire time, or when a manual run was triggered
+    scheduled_at: chrono::DateTime<chrono::Utc>,
+    manual: bool,
+}
+
+impl RunTrigger {
+    fn scheduled(fire_time: chrono::DateTime<chrono::Utc>) -> Self {
+        Self {
+            scheduled_at: fire_time,
+            manual: false,
+        }
+    }
+
+    fn manual() -> Self {
+        Self {
+            scheduled_at: chrono::Utc::now(),
+            manual: true,
+        }
+    }
+}
+
+/// Everything a run of a scheduled function needs, moved into its timer
+#[derive(Clone)]
+struct ScheduledJob {
+    db_pool: crate::db::DbPool,
+    engine: wasmtime::Engine,
+    artifact_cache: std::sync::Arc<ArtifactCache>,
+    function_id: uuid::Uuid,
+    function_name: String,
+    revision: Option<i32>,
+    related_wasm: String,
+    scope_id: uuid::Uuid,
+    limits: ResourceLimits,
+    key_value: ScopedKeyValue,
+    secret_cipher: std::sync::Arc<SecretCipher>,
+    run_history_limit: u64,
+    retry: Option<RetryPolicy>,
+    overlap: OverlapGuard,
+    /// The longest a run may take, including its retries
+    timeout: Option<std::time::Duration>,
+}
+
+impl ScheduledJob {
+    fn new(
+        state: &crate::scheduler::state::SchedulerState,
+        function: &domain::function::ScheduledFunction,
+    ) -> Self {
+        Self {
+            db_pool: state.db_pool.clone(),
+            engine: state.engine.clone(),
+            artifact_cache: state.artifact_cache.clone(),
+            function_id: function.uuid,
+            function_name: function.name.clone(),
+            revision: function.revision,
+            related_wasm: function.related_wasm(),
+            scope_id: function.scope_id,
+            limits: function.limits.within(&state.default_limits),
+            key_value: ScopedKeyValue::new(state.key_value_backend.clone(), function.scope_id),
+            secret_cipher: state.secret_cipher.clone(),
+            run_history_limit: function
+                .run_history
+                .map_or(state.run_history_limit, u64::from),
+            retry: function.retry.clone(),
+            overlap: state.overlap_guards.get(function.uuid, function.overlap),
+            timeout: function
+                .run_timeout_secs
+                .map(std::time::Duration::from_secs),
+        }
+    }
+
+    /// Runs the function on a cron tick, unless its overlap policy skips the tick
+    async fn tick(&self, fire_time: chrono::DateTime<chrono::Utc>, fence: Option<Fence>) {
+        self.guarded(
+            uuid::Uuid::new_v4(),
+            RunTrigger::scheduled(fire_time),
+            fence,
+        )
+        .await;
+    }
+
+    /// Runs the function unless its overlap policy skips the run, returns the last recorded run
+    async fn guarded(
+        &self,
+        run_id: uuid::Uuid,
+        trigger: RunTrigger,
+        fence: Option<Fence>,
+    ) -> ScheduledRun {
+        let Some(_permit) = self.overlap.enter().await else {
+            debug!(
+                "Skip run of scheduled function '{}', the previous run is still going",
+                self.function_id
+            );
+            let run = ScheduledRun::skipped(run_id, self.function_id, self.revision);
+            self.record(&run, fence.as_ref()).await;
+            return run;
+        };
+
+        self.run(run_id, trigger, fence).await
+    }
+
+    /// Runs the function, failed attempts are retried as its retry policy allows.
+    /// Runs of functions with a retry policy that fail for good are parked as dead letter.
+    /// A run started by the leader stops once the replica lost the lease of its `fence`.
+    /// The first attempt is recorded as `run_id`, the last recorded attempt is returned.
+    async fn run(
+        &self,
+        mut run_id: uuid::Uuid,
+        trigger: RunTrigger,
+        fence: Option<Fence>,
+    ) -> ScheduledRun {
+        let fence = fence.as_ref();
+        let function_id = self.function_id;
+        // The timeout bounds the whole run, including the delays between its attempts.
+        // A timeout too long to represent is no deadline at all.
+        let deadline = self
+            .timeout
+            .and_then(|timeout| tokio::time::Instant::now().checked_add(timeout));
+        let mut attempt = 1;
+
+        loop {
+            let started_at = chrono::Utc::now();
+            // The logs of the run are found by its id, they outlive an attempt that timed out
+            let logs = LogCollector::new(function_id, Some(run_id.to_string()));
+            let Some((run, result)) = within(
+                deadline,
+                self.attempt(run_id, attempt, started_at, trigger, &logs, fence),
+            )
+            .await
+            else {
+                warn!(
+                    "Scheduled function '{function_id}' exceeded its timeout (attempt {attempt})"
+                );
+                self.store_logs(&logs).await;
+                let run = ScheduledRun::timed_out(
+                    run_id,
+                    function_id,
+                    self.revision,
+                    attempt,
+                    started_at,
+                    self.timeout.unwrap_or_default(),
+                );
+                self.record(&run, fence).await;
+                if self.retry.is_some() {
+                    self.park(&run, fence).await;
+                }
+                return run;
+            };
+            let Err(err) = &result else {
+                debug!("Scheduled function executed successfully");
+                return run;
+            };
+            // Debug output contains the wasm backtrace of traps
+            error!("Scheduled function '{function_id}' failed (attempt {attempt}): {err:?}");
+
+            let Some(retry) = &self.retry else {
+                return run;
+            };
+            if !retry.retries(attempt, err.is_retryable()) {
+                self.park(&run, fence).await;
+                return run;
+            }
+
+            let delay = retry.delay(attempt);
+            if deadline.is_some_and(|deadline| {
+                tokio::time::Instant::now()
+                    .checked_add(delay)
+                    .is_none_or(|retry_at| retry_at >= deadline)
+            }) {
+                warn!("Stop retrying scheduled function '{function_id}', the next attempt would exceed its timeout");
+                self.park(&run, fence).await;
+                return run;
+            }
+            debug!("Retry scheduled function '{function_id}' in {delay:?}");
+            tokio::time::sleep(delay).await;
+            // The new leader runs the next tick, this replica must not retry in parallel
+            if deposed(fence).await {
+                warn!("Stop retrying scheduled function '{function_id}', this replica no longer leads");
+                return run;
+            }
+            run_id = uuid::Uuid::new_v4();
+            attempt += 1;
+        }
+    }
+
+    /// Executes the function once and records the attempt in its run history
+    async fn attempt(
+        &self,
+        run_id: uuid::Uuid,
+        attempt: u32,
+        started_at: chrono::DateTime<chrono::Utc>,
+        trigger: RunTrigger,
+        logs: &LogCollector,
+        fence: Option<&Fence>,
+    ) -> (ScheduledRun, Result<(), InvocationError>) {
+        let result =
+            execute_scheduled_function(self, &run_id, attempt, started_at, trigger, logs).await;
+        self.store_logs(logs).await;
+
+        let run = ScheduledRun::finished(
+            run_id,
+            self.function_id,
+            self.revision,
+            attempt,
+            started_at,
+            &result,
+        );
+        self.record(&run, fence).await;
+
+        (run, result)
+    }
+
+    /// Persists the logs captured so far, failing to do so doesn't fail the run
+    async fn store_logs(&self, logs: &LogCollector) {
+        if let Err(err) =
+            log_service::store_logs(&self.db_pool, &self.function_id, logs.finish()).await
+        {
+            error!(
+                "Failed to store logs of function '{}': {err:?}",
+                self.function_id
+            );
+        }
+    }
+
+    /// Records the run, unless the replica started it as leader and lost the lease since
+    async fn record(&self, run: &ScheduledRun, fence: Option<&Fence>) {
+        if deposed(fence).await {
+            warn!(
+                "Discard run of function '{}', this replica no longer leads",
+                self.function_id
+            );
+            return;
+        }
+        if let Err(err) = run_service::record_run(&self.db_pool, run, self.run_history_limit).await
+        {
+            error!(
+                "Failed to record run of function '{}': {err:?}",
+                self.function_id
+            );
+        }
+    }
+
+    /// Parks a run that failed for good as dead letter, unless the replica lost the lease since
+    async fn park(&self, run: &ScheduledRun, fence: Option<&Fence>) {
+        if deposed(fence).await {
+            return;
+        }
+        if let Err(err) = dead_letter_service::park_run(&self.db_pool, run).await {
+            error!(
+                "Failed to park run of function '{}': {err:?}",
+                self.function_id
+            );
         }
     }
 }
 
+/// Whether the replica lost the lease the run was started under
+async fn deposed(fence: Option<&Fence>) -> bool {
+    match fence {
+        Some(fence) => !fence.holds().await,
+        None => false,
+    }
+}
+
+/// Awaits the future unless the deadline passes first
+async fn within<T>(
+    deadline: Option<tokio::time::Instant>,
+    future: impl std::future::Future<Output = T>,
+) -> Option<T> {
+    match deadline {
+        Some(deadline) => tokio::time::timeout_at(deadline, future).await.ok(),
+        None => Some(future.await),
+    }
+}
+
+async fn execute_scheduled_function(
+    job: &ScheduledJob,
+    run_id: &uuid::Uuid,
+    attempt: u32,
+    started_at: chrono::DateTime<chrono::Utc>,
+    trigger: RunTrigger,
+    logs: &LogCollector,
+) -> Result<(), InvocationError> {
+    let db_pool = &job.db_pool;
+    let function_id = &job.function_id;
+
+    let scope = scope_service::get_scope_by_id(db_pool, &job.scope_id)
+        .await?
+        .ok_or(InvocationError::NotFound)?;
+    let context = bindings_function_scheduled::JobContext {
+        run_id: run_id.to_string(),
+        function_name: job.function_name.clone(),
+        scope: scope.name,
+        scheduled_at_ms: unix_millis(&trigger.scheduled_at),
+        started_at_ms: unix_millis(&started_at),
+        attempt,
+        manual: trigger.manual,
+    };
+
+    // Extract function variables and secrets
+    let funct_vars = variable_service::find_vars_by_scheduled_func_id(db_pool, function_id)
+        .await?
+        .ok_or(InvocationError::NotFound)?;
+    let funct_secrets =
+        secret_service::find_secret_values(db_pool, &job.secret_cipher, &job.scope_id).await?;
+
+    // Reuse the compiled function of previous deploys and executions
+    let component = job.artifact_cache.load(&job.related_wasm).await?;
+    let func_builder = FunctionScheduledBuilder::from_component(&job.engine, component)
+        .map_err(InvocationError::Component)?;
+
+    // Add the variables and limits to the function store
+    let (func, mut func_store): (FunctionScheduled, Store<ComponentState>) = func_builder
+        .with_variables(&funct_vars)
+        .with_secrets(&funct_secrets)
+        .with_limits(&job.limits)
+        .with_key_value(job.key_value.clone())
+        .with_log_collector(logs.clone())
+        .build()
+        .await
+        .map_err(InvocationError::Component)?;
+
+    // Execute the function within its resource limits
+    let timeout = func_store.data().limits().timeout();
+    limits::with_timeout(timeout, func.call_run_job(&mut func_store, &context))
+        .await?
+        .map_err(|err| InvocationError::from_call_error(&func_store, err))?
+        .map_err(InvocationError::Guest)
+}
+
+fn unix_millis(time: &chrono::DateTime<chrono::Utc>) -> u64 {
+    u64::try_from(time.timestamp_millis()).unwrap_or_default()
+}
+
 #[async_trait::async_trait]
 impl FunctionSchedulerExecutorTrait for FunctionSchedulerImpl {
     async fn run(&self) {
-        self.inner_scheduler
-            .start()
+        self.running.send_replace(true);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{overlap::OverlapPolicy, run::RunOutcome};
+
+    #[tokio::test]
+    async fn skip_manual_run_while_previous_runs() {
+        let db_pool = crate::db::init_pool("sqlite::memory:").await;
+        crate::db::run_migrations(&db_pool).await;
+        let engine = crate::component::setup_engine(false);
+        let key = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, [1; 32]);
+        let scheduler = FunctionSchedulerImpl::new(
+            db_pool.clone(),
+            engine.clone(),
+            std::sync::Arc::new(ArtifactCache::new(
+                engine,
+                std::sync::Arc::new(crate::cache::LocalCache::default()),
+                std::sync::Arc::new(crate::storage::file_system::FileSystemStorage::default()),
+                false,
+            )),
+            ResourceLimits::default(),
+            std::sync::Arc::new(crate::keyvalue::db_store::DbKeyValueStore::new(
+                db_pool.clone(),
+            )),
+            std::sync::Arc::new(SecretCipher::new(&key, &[]).unwrap()),
+            10,
+            None,
+        )
+        .await;
+        let function = domain::function::ScheduledFunction {
+            name: "report".to_string(),
+            uuid: uuid::Uuid::new_v4(),
+            cron: "0 * * * * *".to_string(),
+            timezone: None,
+            scope_id: uuid::Uuid::new_v4(),
+            content_hash: "hash".to_string(),
+            limits: ResourceLimits::default(),
+            revision: Some(1),
+            pinned: false,
+            run_history: None,
+            retry: None,
+            overlap: OverlapPolicy::Skip,
+            run_timeout_secs: None,
+        };
+
+        // A tick of the function is still running
+        let _running = scheduler
+            .state
+            .overlap_guards
+            .get(function.uuid, function.overlap)
+            .enter()
+            .await
+            .unwrap();
+
+        let run_id = uuid::Uuid::new_v4();
+        let run = scheduler.run_now(&function, run_id).await;
+        assert_eq!(run.id, run_id);
+        assert_eq!(run.outcome, RunOutcome::Skipped);
+        let recorded = run_service::find_run(&db_pool, &function.uuid, &run_id)
             .await
-            .expect("Failed to start scheduler");
+            .unwrap()
+            .unwrap();
+        assert_eq!(recorded.outcome, RunOutcome::Skipped);
     }
 }
@@ -0,0 +1,350 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use sea_orm::{prelude::*, sea_query::OnConflict, Condition, DbBackend, Set, Statement};
+use thiserror::Error;
+use tracing::{info, warn};
+
+use crate::db::DbPool;
+
+/// Name of the lease on running scheduled functions
+const SCHEDULER_LEASE: &str = "scheduler";
+
+#[derive(Debug, Error)]
+pub(crate) enum LeaseError {
+    #[error("Interaction with Database failed")]
+    Db(#[from] sea_orm::DbErr),
+    #[error("Interaction with Redis failed")]
+    Redis(#[from] redis::RedisError),
+}
+
+/// Stores the lease on running the scheduled functions of all runtime replicas
+#[async_trait::async_trait]
+pub(crate) trait LeaseBackend: Send + Sync {
+    /// Acquires the lease for the holder or extends it if the holder already owns it.
+    /// Returns the fencing token of the lease, which increases whenever the lease changes its holder.
+    async fn acquire(&self, holder: &str, ttl: Duration) -> Result<Option<u64>, LeaseError>;
+    /// Whether the holder still owns the lease with the token
+    async fn is_held(&self, holder: &str, token: u64) -> Result<bool, LeaseError>;
+}
+
+/// Stores the lease in the `scheduler_lease` table of the runtime database
+pub(crate) struct DbLease {
+    db_pool: DbPool,
+}
+
+impl DbLease {
+    pub(crate) fn new(db_pool: DbPool) -> Self {
+        Self { db_pool }
+    }
+
+    /// The clock of the database, replicas with skewed clocks still agree on when the lease expires
+    async fn now(&self) -> Result<chrono::DateTime<chrono::Utc>, LeaseError> {
+        let backend = self.db_pool.get_database_backend();
+        let sql = match backend {
+            DbBackend::Sqlite => "SELECT strftime('%Y-%m-%dT%H:%M:%fZ', 'now')",
+            _ => "SELECT CURRENT_TIMESTAMP",
+        };
+        let row = self
+            .db_pool
+            .query_one(Statement::from_string(backend, sql))
+            .await?
+            .ok_or_else(|| DbErr::RecordNotFound("current time of the database".to_string()))?;
+
+        match backend {
+            // SQLite has no timestamp type, the time is returned as text
+            DbBackend::Sqlite => {
+                let now: String = row.try_get_by_index(0)?;
+                chrono::DateTime::parse_from_rfc3339(&now)
+                    .map(|now| now.to_utc())
+                    .map_err(|err| DbErr::Type(err.to_string()).into())
+            }
+            _ => Ok(row.try_get_by_index::<DateTimeWithTimeZone>(0)?.to_utc()),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl LeaseBackend for DbLease {
+    async fn acquire(&self, holder: &str, ttl: Duration) -> Result<Option<u64>, LeaseError> {
+        let now = self.now().await?;
+        let expires_at = now + ttl;
+
+        let Some(lease) = entity::scheduler_lease::Entity::find_by_id(SCHEDULER_LEASE)
+            .one(&self.db_pool)
+            .await?
+        else {
+            // Replicas starting at the same time race for the first lease
+            let inserted =
+                entity::scheduler_lease::Entity::insert(entity::scheduler_lease::ActiveModel {
+                    name: Set(SCHEDULER_LEASE.to_string()),
+                    holder: Set(holder.to_string()),
+                    token: Set(1),
+                    expires_at: Set(expires_at.into()),
+                })
+                .on_conflict(
+                    OnConflict::column(entity::scheduler_lease::Column::Name)
+                        .do_nothing()
+                        .to_owned(),
+                )
+                .exec_without_returning(&self.db_pool)
+                .await?;
+            return Ok((inserted > 0).then_some(1));
+        };
+
+        let token = if lease.holder == holder {
+            lease.token
+        } else if lease.expires_at.to_utc() <= now {
+            lease.token + 1
+        } else {
+            return Ok(None);
+        };
+
+        // Only one replica updates the lease it read, the others lose the race
+        let updated = entity::scheduler_lease::Entity::update_many()
+            .col_expr(entity::scheduler_lease::Column::Holder, Expr::value(holder))
+            .col_expr(entity::scheduler_lease::Column::Token, Expr::value(token))
+            .col_expr(
+                entity::scheduler_lease::Column::ExpiresAt,
+                Expr::value(DateTimeWithTimeZone::from(expires_at)),
+            )
+            .filter(entity::scheduler_lease::Column::Name.eq(SCHEDULER_LEASE))
+            .filter(entity::scheduler_lease::Column::Holder.eq(lease.holder))
+            .filter(entity::scheduler_lease::Column::Token.eq(lease.token))
+            // Another holder's lease must still be expired by the time of the database
+            .filter(
+                Condition::any()
+                    .add(entity::scheduler_lease::Column::Holder.eq(holder))
+                    .add(
+                        entity::scheduler_lease::Column::ExpiresAt
+                            .lte(DateTimeWithTimeZone::from(now)),
+                    ),
+            )
+            .exec(&self.db_pool)
+            .await?;
+
+        Ok((updated.rows_affected > 0).then(|| token.try_into().unwrap_or_default()))
+    }
+
+    async fn is_held(&self, holder: &str, token: u64) -> Result<bool, LeaseError> {
+        let now = self.now().await?;
+        Ok(entity::scheduler_lease::Entity::find_by_id(SCHEDULER_LEASE)
+            .one(&self.db_pool)
+            .await?
+            .is_some_and(|lease| {
+                lease.holder == holder
+                    && u64::try_from(lease.token).is_ok_and(|lease_token| lease_token == token)
+                    && lease.expires_at.to_utc() > now
+            }))
+    }
+}
+
+struct HeldLease {
+    token: u64,
+    /// The lease is given up locally at this point, even if the backend can't be reached
+    valid_until: Instant,
+}
+
+/// Elects the replica running the scheduled functions, so every cron tick runs once across replicas.
+///
+/// Every replica keeps trying to acquire the lease, another replica takes over
+/// at the latest one TTL after the leader stopped renewing it.
+pub(crate) struct LeaderElection {
+    backend: Arc<dyn LeaseBackend>,
+    /// Identifies this runtime replica
+    holder: String,
+    ttl: Duration,
+    lease: Mutex<Option<HeldLease>>,
+}
+
+impl LeaderElection {
+    pub(crate) fn new(backend: Arc<dyn LeaseBackend>, ttl: Duration) -> Self {
+        Self {
+            backend,
+            holder: uuid::Uuid::new_v4().to_string(),
+            ttl,
+            lease: Mutex::new(None),
+        }
+    }
+
+    /// Acquires and renews the lease in the background, renewing well before it expires
+    pub(crate) fn spawn(self: &Arc<Self>) {
+        let election = self.clone();
+        tokio::spawn(async move {
+            loop {
+                election.renew().await;
+                tokio::time::sleep(election.ttl / 3).await;
+            }
+        });
+    }
+
+    async fn renew(&self) {
+        let requested_at = Instant::now();
+        let result = self.backend.acquire(&self.holder, self.ttl).await;
+
+        let mut lease = self.lease.lock().unwrap();
+        let previous_token = lease.as_ref().map(|lease| lease.token);
+        match result {
+            Ok(Some(token)) => {
+                if previous_token != Some(token) {
+                    info!(
+                        "Replica '{}' leads the scheduler (token {token})",
+                        self.holder
+                    );
+                }
+                *lease = Some(HeldLease {
+                    token,
+                    valid_until: requested_at + self.ttl,
+                });
+            }
+            Ok(None) => {
+                if previous_token.is_some() {
+                    warn!("Replica '{}' lost the scheduler lease", self.holder);
+                }
+                *lease = None;
+            }
+            // Keep the lease until it expires, the backend may be back before
+            Err(err) => warn!("Failed to renew the scheduler lease: {err:?}"),
+        }
+    }
+
+    /// Fences the work of this replica as leader, which must stop once another replica took over
+    pub(crate) async fn fence(self: &Arc<Self>) -> Option<Fence> {
+        Some(Fence {
+            token: self.fencing_token().await?,
+            election: self.clone(),
+        })
+    }
+
+    /// Returns the fencing token if this replica leads.
+    /// The token is checked against the backend, so a replica that lost the lease doesn't start a run.
+    pub(crate) async fn fencing_token(&self) -> Option<u64> {
+        let token = {
+            let lease = self.lease.lock().unwrap();
+            lease
+                .as_ref()
+                .filter(|lease| lease.valid_until > Instant::now())
+                .map(|lease| lease.token)
+        }?;
+
+        match self.backend.is_held(&self.holder, token).await {
+            Ok(true) => Some(token),
+            Ok(false) => {
+                self.lease.lock().unwrap().take();
+                None
+            }
+            Err(err) => {
+                warn!("Failed to check the scheduler lease: {err:?}");
+                None
+            }
+        }
+    }
+}
+
+/// The lease a run was started under
+#[derive(Clone)]
+pub(crate) struct Fence {
+    election: Arc<LeaderElection>,
+    token: u64,
+}
+
+impl Fence {
+    pub(crate) fn token(&self) -> u64 {
+        self.token
+    }
+
+    /// Whether this replica still leads with the same lease, a deposed leader must not continue the run
+    pub(crate) async fn holds(&self) -> bool {
+        self.election.fencing_token().await == Some(self.token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn db_lease() -> DbLease {
+        let db_pool = crate::db::init_pool("sqlite::memory:").await;
+        crate::db::run_migrations(&db_pool).await;
+        DbLease::new(db_pool)
+    }
+
+    #[tokio::test]
+    async fn grant_lease_to_one_holder() {
+        let lease = db_lease().await;
+        let ttl = Duration::from_secs(30);
+
+        assert_eq!(lease.acquire("replica-a", ttl).await.unwrap(), Some(1));
+        assert_eq!(lease.acquire("replica-b", ttl).await.unwrap(), None);
+        // Renewing keeps the token
+        assert_eq!(lease.acquire("replica-a", ttl).await.unwrap(), Some(1));
+
+        assert!(lease.is_held("replica-a", 1).await.unwrap());
+        assert!(!lease.is_held("replica-b", 1).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn fence_previous_holder_after_takeover() {
+        let lease = db_lease().await;
+
+        assert_eq!(
+            lease.acquire("replica-a", Duration::ZERO).await.unwrap(),
+            Some(1)
+        );
+        assert_eq!(
+            lease
+                .acquire("replica-b", Duration::from_secs(30))
+                .await
+                .unwrap(),
+            Some(2)
+        );
+
+        assert!(!lease.is_held("replica-a", 1).await.unwrap());
+        assert!(lease.is_held("replica-b", 2).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn lead_once_lease_is_acquired() {
+        let election = Arc::new(LeaderElection::new(
+            Arc::new(db_lease().await),
+            Duration::from_secs(30),
+        ));
+        assert_eq!(election.fencing_token().await, None);
+
+        election.renew().await;
+
+        assert_eq!(election.fencing_token().await, Some(1));
+    }
+
+    #[tokio::test]
+    async fn deposed_leader_no_longer_holds_its_fence() {
+        let lease = Arc::new(db_lease().await);
+        let election = Arc::new(LeaderElection::new(lease.clone(), Duration::from_secs(30)));
+        election.renew().await;
+        let fence = election.fence().await.unwrap();
+        assert!(fence.holds().await);
+
+        // The lease expires in the database while the leader still considers it valid
+        entity::scheduler_lease::Entity::update_many()
+            .col_expr(
+                entity::scheduler_lease::Column::ExpiresAt,
+                Expr::value(DateTimeWithTimeZone::from(
+                    chrono::Utc::now() - chrono::Duration::minutes(1),
+                )),
+            )
+            .exec(&lease.db_pool)
+            .await
+            .unwrap();
+        assert_eq!(
+            lease
+                .acquire("replica-b", Duration::from_secs(30))
+                .await
+                .unwrap(),
+            Some(2)
+        );
+
+        assert!(!fence.holds().await);
+    }
+}
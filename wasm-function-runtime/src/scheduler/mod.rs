@@ -1,6 +1,9 @@
 use function_scheduler::FunctionSchedulerExecutorTrait;
 
 pub(crate) mod function_scheduler;
+pub(crate) mod lease;
+pub(crate) mod overlap;
+pub(crate) mod schedule;
 pub(crate) mod state;
 
 pub(crate) use function_scheduler::{FunctionSchedulerImpl, FunctionSchedulerManagerTrait};
@@ -19,7 +22,7 @@ where
     let count = funcs.len();
 
     for func in funcs {
-        scheduler.add(&func.uuid, &func.cron).await;
+        scheduler.add(&func).await;
     }
 
     scheduler.run().await;
@@ -0,0 +1,173 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use tokio::sync::{Mutex, OwnedMutexGuard};
+use uuid::Uuid;
+
+use crate::domain::overlap::OverlapPolicy;
+
+/// Enforces the overlap policy across the ticks of a single cron job.
+/// Clones share the running tick, whatever policy they enforce.
+#[derive(Clone)]
+pub(crate) struct OverlapGuard {
+    policy: OverlapPolicy,
+    running: Arc<Mutex<()>>,
+    /// Whether a tick already waits for the running one
+    queued: Arc<AtomicBool>,
+}
+
+/// Held by a tick while it runs, following ticks may run once it is dropped
+pub(crate) struct OverlapPermit {
+    _running: Option<OwnedMutexGuard<()>>,
+}
+
+impl OverlapGuard {
+    pub(crate) fn new(policy: OverlapPolicy) -> Self {
+        Self {
+            policy,
+            running: Arc::new(Mutex::new(())),
+            queued: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// The guard sharing the running tick of this one, enforcing another policy
+    fn with_policy(&self, policy: OverlapPolicy) -> Self {
+        Self {
+            policy,
+            running: self.running.clone(),
+            queued: self.queued.clone(),
+        }
+    }
+
+    /// Waits until the tick may run. Returns `None` if the tick is skipped.
+    pub(crate) async fn enter(&self) -> Option<OverlapPermit> {
+        let running = match self.policy {
+            OverlapPolicy::Allow => None,
+            OverlapPolicy::Skip => Some(self.running.clone().try_lock_owned().ok()?),
+            OverlapPolicy::Queue => match self.running.clone().try_lock_owned() {
+                Ok(running) => Some(running),
+                // Only one tick waits, so slow jobs don't pile up waiting ticks
+                Err(_) if self.queued.swap(true, Ordering::SeqCst) => return None,
+                Err(_) => {
+                    let running = self.running.clone().lock_owned().await;
+                    self.queued.store(false, Ordering::SeqCst);
+                    Some(running)
+                }
+            },
+        };
+
+        Some(OverlapPermit { _running: running })
+    }
+}
+
+/// The overlap guards of all scheduled functions, keyed by the function.
+/// A redeployed job gets the guard of its previous revision, so it never runs next to it.
+#[derive(Default)]
+pub(crate) struct OverlapGuards {
+    guards: std::sync::Mutex<HashMap<Uuid, OverlapGuard>>,
+}
+
+impl OverlapGuards {
+    /// The guard of the function, enforcing the policy of its latest deploy
+    pub(crate) fn get(&self, function_id: Uuid, policy: OverlapPolicy) -> OverlapGuard {
+        let mut guards = self.guards.lock().unwrap_or_else(|err| err.into_inner());
+        guards
+            .entry(function_id)
+            .or_insert_with(|| OverlapGuard::new(policy))
+            .with_policy(policy)
+    }
+
+    /// Forgets the guard of a removed function once no job and no tick holds it anymore
+    pub(crate) fn release(&self, function_id: &Uuid) {
+        let mut guards = self.guards.lock().unwrap_or_else(|err| err.into_inner());
+        if guards
+            .get(function_id)
+            .is_some_and(|guard| Arc::strong_count(&guard.running) == 1)
+        {
+            guards.remove(function_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn skip_tick_while_previous_runs() {
+        let guard = OverlapGuard::new(OverlapPolicy::Skip);
+
+        let permit = guard.enter().await;
+        assert!(permit.is_some());
+        assert!(guard.enter().await.is_none());
+
+        drop(permit);
+        assert!(guard.enter().await.is_some());
+    }
+
+    #[tokio::test]
+    async fn queue_single_tick_while_previous_runs() {
+        let guard = OverlapGuard::new(OverlapPolicy::Queue);
+        let permit = guard.enter().await.unwrap();
+
+        let queued = tokio::spawn({
+            let guard = guard.clone();
+            async move { guard.enter().await.is_some() }
+        });
+        while !guard.queued.load(Ordering::SeqCst) {
+            tokio::task::yield_now().await;
+        }
+
+        // A second tick doesn't wait next to the queued one
+        assert!(guard.enter().await.is_none());
+
+        drop(permit);
+        assert!(queued.await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn share_running_tick_with_redeployed_job() {
+        let guards = OverlapGuards::default();
+        let function_id = Uuid::new_v4();
+
+        let deployed = guards.get(function_id, OverlapPolicy::Skip);
+        let permit = deployed.enter().await;
+        assert!(permit.is_some());
+
+        // The job is removed and added again while its tick still runs
+        drop(deployed);
+        guards.release(&function_id);
+        let redeployed = guards.get(function_id, OverlapPolicy::Skip);
+        assert!(redeployed.enter().await.is_none());
+
+        drop(permit);
+        assert!(redeployed.enter().await.is_some());
+    }
+
+    #[tokio::test]
+    async fn release_idle_guard_only() {
+        let guards = OverlapGuards::default();
+        let function_id = Uuid::new_v4();
+
+        let job = guards.get(function_id, OverlapPolicy::Skip);
+        guards.release(&function_id);
+        assert!(guards.guards.lock().unwrap().contains_key(&function_id));
+
+        drop(job);
+        guards.release(&function_id);
+        assert!(guards.guards.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn allow_ticks_next_to_each_other() {
+        let guard = OverlapGuard::new(OverlapPolicy::Allow);
+
+        let _permit = guard.enter().await.unwrap();
+        assert!(guard.enter().await.is_some());
+    }
+}
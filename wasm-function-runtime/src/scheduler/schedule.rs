@@ -0,0 +1,158 @@
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub(crate) enum ScheduleError {
+    #[error("Invalid cron expression '{0}': {1}")]
+    InvalidCron(String, String),
+    #[error("Unknown timezone '{0}'")]
+    UnknownTimezone(String),
+}
+
+/// When a scheduled function runs, its cron expression follows the wall clock of its timezone
+#[derive(Debug, Clone)]
+pub(crate) struct Schedule {
+    cron: croner::Cron,
+    timezone: Tz,
+}
+
+impl Schedule {
+    /// Parses the cron expression, with or without seconds.
+    /// Without a timezone the expression is evaluated in UTC.
+    pub(crate) fn new(cron: &str, timezone: Option<&str>) -> Result<Self, ScheduleError> {
+        let parsed = croner::Cron::new(cron)
+            .with_seconds_optional()
+            .with_dom_and_dow()
+            .parse()
+            .map_err(|err| ScheduleError::InvalidCron(cron.to_owned(), err.to_string()))?;
+        let timezone = timezone.map_or(Ok(Tz::UTC), |name| {
+            name.parse::<Tz>()
+                .map_err(|_| ScheduleError::UnknownTimezone(name.to_owned()))
+        })?;
+
+        Ok(Self {
+            cron: parsed,
+            timezone,
+        })
+    }
+
+    pub(crate) fn timezone(&self) -> Tz {
+        self.timezone
+    }
+
+    /// The first fire time after the instant.
+    /// Fire times skipped by a clock change run once the clock moved on, repeated ones only run the first time.
+    pub(crate) fn next_after(&self, instant: &DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut local = instant.with_timezone(&self.timezone);
+        loop {
+            local = self.cron.find_next_occurrence(&local, false).ok()?;
+            // Croner resolves repeated wall clock times to their first occurrence, which may lie before the instant
+            if local > *instant {
+                return Some(local.with_timezone(&Utc));
+            }
+        }
+    }
+
+    /// The fire times following the instant
+    pub(crate) fn fire_times(
+        &self,
+        instant: &DateTime<Utc>,
+    ) -> impl Iterator<Item = DateTime<Utc>> + '_ {
+        std::iter::successors(self.next_after(instant), |fire_time| {
+            self.next_after(fire_time)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn utc(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn evaluate_cron_in_utc_without_timezone() {
+        let schedule = Schedule::new("0 30 9 * * Mon-Fri", None).unwrap();
+
+        let fire_times: Vec<_> = schedule
+            .fire_times(&utc(2026, 10, 16, 12, 0))
+            .take(2)
+            .collect();
+        assert_eq!(
+            fire_times,
+            vec![utc(2026, 10, 19, 9, 30), utc(2026, 10, 20, 9, 30)]
+        );
+    }
+
+    #[test]
+    fn follow_wall_clock_across_daylight_saving_time() {
+        let schedule = Schedule::new("0 30 2 * * *", Some("Europe/Berlin")).unwrap();
+
+        let spring: Vec<_> = schedule
+            .fire_times(&utc(2026, 3, 27, 12, 0))
+            .take(3)
+            .collect();
+        // 02:30 is skipped on March 29, the run follows once the clock jumped to 03:00 summer time
+        assert_eq!(
+            spring,
+            vec![
+                utc(2026, 3, 28, 1, 30),
+                utc(2026, 3, 29, 1, 0),
+                utc(2026, 3, 30, 0, 30)
+            ]
+        );
+
+        let autumn: Vec<_> = schedule
+            .fire_times(&utc(2026, 10, 24, 12, 0))
+            .take(2)
+            .collect();
+        // 02:30 occurs twice on October 25, only the first one runs
+        assert_eq!(
+            autumn,
+            vec![utc(2026, 10, 25, 0, 30), utc(2026, 10, 26, 1, 30)]
+        );
+    }
+
+    #[test]
+    fn skip_repeated_wall_clock_time_after_it_fired() {
+        let schedule = Schedule::new("0 30 2 * * *", Some("Europe/Berlin")).unwrap();
+
+        // 02:15 after the clock was set back, 02:30 already fired an hour earlier
+        assert_eq!(
+            schedule.next_after(&utc(2026, 10, 25, 1, 15)),
+            Some(utc(2026, 10, 26, 1, 30))
+        );
+    }
+
+    #[test]
+    fn accept_cron_without_seconds() {
+        let schedule = Schedule::new("*/15 * * * *", None).unwrap();
+
+        assert_eq!(
+            schedule.next_after(&utc(2026, 10, 18, 12, 7)),
+            Some(utc(2026, 10, 18, 12, 15))
+        );
+    }
+
+    #[test]
+    fn reject_invalid_cron_and_timezone() {
+        assert!(matches!(
+            Schedule::new("0 61 * * * *", None),
+            Err(ScheduleError::InvalidCron(..))
+        ));
+        assert!(matches!(
+            Schedule::new("every day", None),
+            Err(ScheduleError::InvalidCron(..))
+        ));
+        assert!(matches!(
+            Schedule::new("0 0 * * * *", Some("Mars/Olympus_Mons")),
+            Err(ScheduleError::UnknownTimezone(..))
+        ));
+    }
+}
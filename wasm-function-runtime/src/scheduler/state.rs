@@ -1,34 +1,47 @@
-pub(crate) type SchedulerCache = moka::future::Cache<uuid::Uuid, uuid::Uuid>;
-
-pub(crate) type BinaryCache = moka::future::Cache<uuid::Uuid, Vec<u8>>;
+/// The timer of each scheduled function
+pub(crate) type SchedulerCache = moka::future::Cache<uuid::Uuid, tokio::task::AbortHandle>;
 
 pub(crate) struct SchedulerState {
     pub db_pool: crate::db::DbPool,
     pub engine: wasmtime::Engine,
     pub cache: SchedulerCache,
-    pub binary_cache: BinaryCache,
-    pub storage_backend: std::sync::Arc<dyn crate::storage::StorageBackend>,
+    pub artifact_cache: std::sync::Arc<crate::component::artifact::ArtifactCache>,
+    pub default_limits: crate::domain::limits::ResourceLimits,
+    pub key_value_backend: std::sync::Arc<dyn crate::keyvalue::KeyValueBackend>,
+    pub secret_cipher: std::sync::Arc<crate::secrets::SecretCipher>,
+    /// Runs kept per function without a `run_history` in its manifest
+    pub run_history_limit: u64,
+    /// Cron ticks only run on the elected replica, every replica runs them without an election
+    pub leader_election: Option<std::sync::Arc<crate::scheduler::lease::LeaderElection>>,
+    /// Outlive the timers, so a redeployed function doesn't run next to its previous revision
+    pub overlap_guards: crate::scheduler::overlap::OverlapGuards,
 }
 
 impl SchedulerState {
+    #[allow(clippy::too_many_arguments)]
     pub(crate) async fn new(
         db_pool: crate::db::DbPool,
         engine: wasmtime::Engine,
-        storage_backend: std::sync::Arc<dyn crate::storage::StorageBackend>,
+        artifact_cache: std::sync::Arc<crate::component::artifact::ArtifactCache>,
+        default_limits: crate::domain::limits::ResourceLimits,
+        key_value_backend: std::sync::Arc<dyn crate::keyvalue::KeyValueBackend>,
+        secret_cipher: std::sync::Arc<crate::secrets::SecretCipher>,
+        run_history_limit: u64,
+        leader_election: Option<std::sync::Arc<crate::scheduler::lease::LeaderElection>>,
     ) -> Self {
         let cache = moka::future::Cache::builder().build();
-        let binary_cache = moka::future::Cache::builder()
-            .time_to_idle(std::time::Duration::from_secs(
-                60 * 10, /* 10 Minutes cache */
-            ))
-            .build();
 
         Self {
             db_pool,
             engine,
             cache,
-            binary_cache,
-            storage_backend,
+            artifact_cache,
+            default_limits,
+            key_value_backend,
+            secret_cipher,
+            run_history_limit,
+            leader_election,
+            overlap_guards: Default::default(),
         }
     }
 }
@@ -0,0 +1,27 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub(crate) enum SecretError {
+    #[error("Secret encryption key is not configured")]
+    NotConfigured,
+    #[error("Encryption key must be 32 base64 encoded bytes")]
+    InvalidKey,
+    #[error("Encrypted value has an unknown format")]
+    InvalidFormat,
+    #[error("Value was encrypted with an unknown key '{0}'")]
+    UnknownKey(String),
+    #[error("Failed to encrypt or decrypt the value")]
+    Crypto,
+}
+
+impl From<aes_gcm::Error> for SecretError {
+    fn from(_: aes_gcm::Error) -> Self {
+        Self::Crypto
+    }
+}
+
+impl From<base64::DecodeError> for SecretError {
+    fn from(_: base64::DecodeError) -> Self {
+        Self::InvalidFormat
+    }
+}
@@ -0,0 +1,298 @@
+//! Envelope encryption of scope secrets.
+//!
+//! Every secret value is encrypted with its own random data key. The data key is wrapped with the
+//! key encryption key from the runtime config, so rotating that key only requires rewrapping the
+//! data keys instead of touching the values. The scope and name of the secret are authenticated as
+//! associated data, so a stored value can't be moved to another secret.
+
+pub(crate) mod error;
+
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+    Aes256Gcm, Key, Nonce,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+pub(crate) use error::SecretError;
+
+/// Version prefix of the stored format `v1:<key id>:<wrapped data key>:<ciphertext>`
+const FORMAT_VERSION: &str = "v1";
+const NONCE_LEN: usize = 12;
+
+/// A key encryption key and the id it is referenced by in stored values
+struct KeyEncryptionKey {
+    id: String,
+    cipher: Aes256Gcm,
+}
+
+impl KeyEncryptionKey {
+    fn from_base64(encoded: &str) -> Result<Self, SecretError> {
+        let bytes = STANDARD
+            .decode(encoded.trim())
+            .map_err(|_| SecretError::InvalidKey)?;
+        if bytes.len() != 32 {
+            return Err(SecretError::InvalidKey);
+        }
+
+        Ok(Self {
+            id: hex::encode(&Sha256::digest(&bytes)[..8]),
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&bytes)),
+        })
+    }
+}
+
+/// Encrypts new values with the primary key and decrypts values of the primary and previous keys
+pub(crate) struct SecretCipher {
+    /// Without a key, secrets can neither be stored nor read
+    primary: Option<KeyEncryptionKey>,
+    previous: Vec<KeyEncryptionKey>,
+}
+
+/// The parts of a stored value
+struct Envelope<'a> {
+    key_id: &'a str,
+    wrapped_key: Vec<u8>,
+    ciphertext: Vec<u8>,
+}
+
+impl<'a> Envelope<'a> {
+    fn parse(stored: &'a str) -> Result<Self, SecretError> {
+        let mut parts = stored.split(':');
+        let (Some(FORMAT_VERSION), Some(key_id), Some(wrapped_key), Some(ciphertext), None) = (
+            parts.next(),
+            parts.next(),
+            parts.next(),
+            parts.next(),
+            parts.next(),
+        ) else {
+            return Err(SecretError::InvalidFormat);
+        };
+
+        Ok(Self {
+            key_id,
+            wrapped_key: STANDARD.decode(wrapped_key)?,
+            ciphertext: STANDARD.decode(ciphertext)?,
+        })
+    }
+}
+
+/// The secret a value belongs to, authenticated along with its ciphertext
+fn associated_data(scope_id: &Uuid, name: &str) -> Vec<u8> {
+    format!("{scope_id}/{name}").into_bytes()
+}
+
+/// Encrypts the plaintext with a fresh nonce, which is prepended to the ciphertext
+fn seal(cipher: &Aes256Gcm, plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>, SecretError> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let mut sealed = nonce.to_vec();
+    sealed.extend(cipher.encrypt(
+        &nonce,
+        Payload {
+            msg: plaintext,
+            aad,
+        },
+    )?);
+    Ok(sealed)
+}
+
+fn open(cipher: &Aes256Gcm, sealed: &[u8], aad: &[u8]) -> Result<Vec<u8>, SecretError> {
+    if sealed.len() < NONCE_LEN {
+        return Err(SecretError::InvalidFormat);
+    }
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    Ok(cipher.decrypt(
+        Nonce::from_slice(nonce),
+        Payload {
+            msg: ciphertext,
+            aad,
+        },
+    )?)
+}
+
+impl SecretCipher {
+    /// Creates the cipher from base64 encoded 32 byte keys
+    pub(crate) fn new(primary_key: &str, previous_keys: &[String]) -> Result<Self, SecretError> {
+        Ok(Self {
+            primary: Some(KeyEncryptionKey::from_base64(primary_key)?),
+            previous: previous_keys
+                .iter()
+                .map(|key| KeyEncryptionKey::from_base64(key))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// A cipher for runtimes without encryption key, which refuses all secret operations
+    pub(crate) fn disabled() -> Self {
+        Self {
+            primary: None,
+            previous: Vec::new(),
+        }
+    }
+
+    fn primary(&self) -> Result<&KeyEncryptionKey, SecretError> {
+        self.primary.as_ref().ok_or(SecretError::NotConfigured)
+    }
+
+    fn key(&self, key_id: &str) -> Result<&KeyEncryptionKey, SecretError> {
+        std::iter::once(self.primary()?)
+            .chain(&self.previous)
+            .find(|key| key.id == key_id)
+            .ok_or_else(|| SecretError::UnknownKey(key_id.to_owned()))
+    }
+
+    fn wrap(&self, data_key: &[u8], ciphertext: &[u8]) -> Result<String, SecretError> {
+        let primary = self.primary()?;
+        Ok(format!(
+            "{FORMAT_VERSION}:{}:{}:{}",
+            primary.id,
+            STANDARD.encode(seal(&primary.cipher, data_key, &[])?),
+            STANDARD.encode(ciphertext)
+        ))
+    }
+
+    fn unwrap_data_key(&self, envelope: &Envelope) -> Result<Aes256Gcm, SecretError> {
+        let data_key = open(
+            &self.key(envelope.key_id)?.cipher,
+            &envelope.wrapped_key,
+            &[],
+        )?;
+        Aes256Gcm::new_from_slice(&data_key).map_err(|_| SecretError::InvalidFormat)
+    }
+
+    /// Encrypts the value of the secret `name` in the scope
+    pub(crate) fn encrypt(
+        &self,
+        plaintext: &str,
+        scope_id: &Uuid,
+        name: &str,
+    ) -> Result<String, SecretError> {
+        let data_key = Aes256Gcm::generate_key(&mut OsRng);
+        let ciphertext = seal(
+            &Aes256Gcm::new(&data_key),
+            plaintext.as_bytes(),
+            &associated_data(scope_id, name),
+        )?;
+        self.wrap(&data_key, &ciphertext)
+    }
+
+    /// Decrypts the value, which fails if it was stored for another secret
+    pub(crate) fn decrypt(
+        &self,
+        stored: &str,
+        scope_id: &Uuid,
+        name: &str,
+    ) -> Result<String, SecretError> {
+        let envelope = Envelope::parse(stored)?;
+        let plaintext = open(
+            &self.unwrap_data_key(&envelope)?,
+            &envelope.ciphertext,
+            &associated_data(scope_id, name),
+        )?;
+        String::from_utf8(plaintext).map_err(|_| SecretError::InvalidFormat)
+    }
+
+    /// Whether the value was encrypted with a key other than the primary key
+    pub(crate) fn needs_rotation(&self, stored: &str) -> Result<bool, SecretError> {
+        Ok(Envelope::parse(stored)?.key_id != self.primary()?.id)
+    }
+
+    /// Wraps the data key of the value with the primary key, the ciphertext stays the same
+    pub(crate) fn rotate(&self, stored: &str) -> Result<String, SecretError> {
+        let envelope = Envelope::parse(stored)?;
+        let data_key = open(
+            &self.key(envelope.key_id)?.cipher,
+            &envelope.wrapped_key,
+            &[],
+        )?;
+        self.wrap(&data_key, &envelope.ciphertext)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCOPE: Uuid = Uuid::from_u128(1);
+
+    fn key(byte: u8) -> String {
+        STANDARD.encode([byte; 32])
+    }
+
+    #[test]
+    fn encrypts_and_decrypts_values() {
+        let cipher = SecretCipher::new(&key(1), &[]).unwrap();
+
+        let stored = cipher.encrypt("hunter2", &SCOPE, "TOKEN").unwrap();
+
+        assert!(!stored.contains("hunter2"));
+        assert_eq!(cipher.decrypt(&stored, &SCOPE, "TOKEN").unwrap(), "hunter2");
+        assert!(!cipher.needs_rotation(&stored).unwrap());
+    }
+
+    #[test]
+    fn refuses_values_of_other_secrets() {
+        let cipher = SecretCipher::new(&key(1), &[]).unwrap();
+
+        let stored = cipher.encrypt("hunter2", &SCOPE, "TOKEN").unwrap();
+
+        assert!(matches!(
+            cipher.decrypt(&stored, &Uuid::from_u128(2), "TOKEN"),
+            Err(SecretError::Crypto)
+        ));
+        assert!(matches!(
+            cipher.decrypt(&stored, &SCOPE, "PASSWORD"),
+            Err(SecretError::Crypto)
+        ));
+    }
+
+    #[test]
+    fn rotates_values_to_the_primary_key() {
+        let old_cipher = SecretCipher::new(&key(1), &[]).unwrap();
+        let stored = old_cipher.encrypt("hunter2", &SCOPE, "TOKEN").unwrap();
+
+        let cipher = SecretCipher::new(&key(2), &[key(1)]).unwrap();
+        assert!(cipher.needs_rotation(&stored).unwrap());
+
+        let rotated = cipher.rotate(&stored).unwrap();
+        assert!(!cipher.needs_rotation(&rotated).unwrap());
+
+        // The old key is no longer needed after rotating
+        let new_cipher = SecretCipher::new(&key(2), &[]).unwrap();
+        assert_eq!(
+            new_cipher.decrypt(&rotated, &SCOPE, "TOKEN").unwrap(),
+            "hunter2"
+        );
+        assert!(matches!(
+            new_cipher.decrypt(&stored, &SCOPE, "TOKEN"),
+            Err(SecretError::UnknownKey(_))
+        ));
+    }
+
+    #[test]
+    fn refuses_values_without_key() {
+        let stored = SecretCipher::new(&key(1), &[])
+            .unwrap()
+            .encrypt("hunter2", &SCOPE, "TOKEN")
+            .unwrap();
+        let cipher = SecretCipher::disabled();
+
+        assert!(matches!(
+            cipher.encrypt("hunter2", &SCOPE, "TOKEN"),
+            Err(SecretError::NotConfigured)
+        ));
+        assert!(matches!(
+            cipher.decrypt(&stored, &SCOPE, "TOKEN"),
+            Err(SecretError::NotConfigured)
+        ));
+    }
+
+    #[test]
+    fn rejects_invalid_keys() {
+        assert!(matches!(
+            SecretCipher::new("too-short", &[]),
+            Err(SecretError::InvalidKey)
+        ));
+    }
+}
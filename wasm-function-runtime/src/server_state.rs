@@ -4,15 +4,23 @@ pub(crate) type JwkSetCache = moka::future::Cache<String, jsonwebtoken::jwk::Jwk
 
 pub(crate) struct RuntimeState {
     pub jwk_cache: JwkSetCache,
+    pub route_cache: crate::routing::RouteCache,
+    pub rollout_stats: crate::routing::rollout::RolloutStats,
+    pub instance_pool: crate::component::pool::InstancePool,
     pub engine: wasmtime::Engine,
     pub db: crate::db::DbPool,
     pub app_config: crate::config::AppConfig,
     pub scheduler_manager: Box<dyn crate::scheduler::FunctionSchedulerManagerTrait>,
     pub storage_backend: std::sync::Arc<dyn crate::storage::StorageBackend>,
     pub cache_backend: std::sync::Arc<dyn crate::cache::CacheBackend>,
+    pub artifact_cache: std::sync::Arc<crate::component::artifact::ArtifactCache>,
+    pub key_value_backend: std::sync::Arc<dyn crate::keyvalue::KeyValueBackend>,
+    pub secret_cipher: std::sync::Arc<crate::secrets::SecretCipher>,
+    pub invalidator: crate::invalidation::Invalidator,
 }
 
 impl RuntimeState {
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         db: crate::db::DbPool,
         wasm_engine: wasmtime::Engine,
@@ -20,6 +28,10 @@ impl RuntimeState {
         scheduler_manager: Box<dyn crate::scheduler::FunctionSchedulerManagerTrait>,
         storage_backend: std::sync::Arc<dyn crate::storage::StorageBackend>,
         cache_backend: std::sync::Arc<dyn crate::cache::CacheBackend>,
+        artifact_cache: std::sync::Arc<crate::component::artifact::ArtifactCache>,
+        key_value_backend: std::sync::Arc<dyn crate::keyvalue::KeyValueBackend>,
+        secret_cipher: std::sync::Arc<crate::secrets::SecretCipher>,
+        invalidator: crate::invalidation::Invalidator,
     ) -> Self {
         let jwk_cache = moka::future::Cache::builder()
             .time_to_live(std::time::Duration::from_secs(
@@ -29,12 +41,21 @@ impl RuntimeState {
 
         Self {
             jwk_cache,
+            route_cache: crate::routing::new_route_cache(),
+            rollout_stats: Default::default(),
+            instance_pool: crate::component::pool::InstancePool::new(
+                1000, /* Linked functions kept in memory */
+            ),
             engine: wasm_engine,
             db,
             app_config,
             scheduler_manager,
             storage_backend,
             cache_backend,
+            artifact_cache,
+            key_value_backend,
+            secret_cipher,
+            invalidator,
         }
     }
 }
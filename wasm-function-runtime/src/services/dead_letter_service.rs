@@ -0,0 +1,87 @@
+use sea_orm::{prelude::*, QueryOrder, QuerySelect, Set};
+
+use super::errors::ServiceError;
+use crate::domain::run::{DeadLetter, ScheduledRun};
+
+/// Dead letters kept per function, older ones are removed when new runs are parked
+pub(crate) const MAX_DEAD_LETTERS_PER_FUNCTION: u64 = 100;
+
+/// Parks the last attempt of a run that failed for good
+pub(crate) async fn park_run(
+    db_pool: &crate::db::DbPool,
+    run: &ScheduledRun,
+) -> Result<DeadLetter, ServiceError> {
+    let dead_letter: DeadLetter = entity::dead_letter::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        function_id: Set(run.function_id),
+        revision: Set(run.revision),
+        run_id: Set(run.id),
+        attempts: Set(run.attempt.try_into().unwrap_or(i32::MAX)),
+        error: Set(run.error.clone()),
+        created_at: Set(chrono::Utc::now().into()),
+    }
+    .insert(db_pool)
+    .await?
+    .into();
+
+    let expired_ids: Vec<Uuid> = entity::dead_letter::Entity::find()
+        .select_only()
+        .column(entity::dead_letter::Column::Id)
+        .filter(entity::dead_letter::Column::FunctionId.eq(run.function_id))
+        .order_by_desc(entity::dead_letter::Column::CreatedAt)
+        .offset(MAX_DEAD_LETTERS_PER_FUNCTION)
+        // SQLite only supports an offset together with a limit
+        .limit(i64::MAX as u64)
+        .into_tuple()
+        .all(db_pool)
+        .await?;
+    if !expired_ids.is_empty() {
+        entity::dead_letter::Entity::delete_many()
+            .filter(entity::dead_letter::Column::Id.is_in(expired_ids))
+            .exec(db_pool)
+            .await?;
+    }
+
+    Ok(dead_letter)
+}
+
+/// Returns the dead letters of the function, ordered from new to old
+pub(crate) async fn find_dead_letters(
+    db_pool: &crate::db::DbPool,
+    function_id: &Uuid,
+) -> Result<Vec<DeadLetter>, ServiceError> {
+    Ok(entity::dead_letter::Entity::find()
+        .filter(entity::dead_letter::Column::FunctionId.eq(*function_id))
+        .order_by_desc(entity::dead_letter::Column::CreatedAt)
+        .all(db_pool)
+        .await?
+        .into_iter()
+        .map(DeadLetter::from)
+        .collect())
+}
+
+/// Removes the dead letter, e.g. because it is replayed or discarded.
+/// Returns `false` if the function has no such dead letter.
+pub(crate) async fn delete_dead_letter(
+    db_pool: &crate::db::DbPool,
+    function_id: &Uuid,
+    dead_letter_id: &Uuid,
+) -> Result<bool, ServiceError> {
+    let deleted = entity::dead_letter::Entity::delete_many()
+        .filter(entity::dead_letter::Column::Id.eq(*dead_letter_id))
+        .filter(entity::dead_letter::Column::FunctionId.eq(*function_id))
+        .exec(db_pool)
+        .await?;
+    Ok(deleted.rows_affected > 0)
+}
+
+pub(crate) async fn delete_dead_letters(
+    db_pool: &crate::db::DbPool,
+    function_ids: Vec<Uuid>,
+) -> Result<(), ServiceError> {
+    entity::dead_letter::Entity::delete_many()
+        .filter(entity::dead_letter::Column::FunctionId.is_in(function_ids))
+        .exec(db_pool)
+        .await?;
+    Ok(())
+}
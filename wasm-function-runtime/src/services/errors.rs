@@ -15,18 +15,54 @@ pub(crate) enum ServiceError {
     Storage(#[from] crate::storage::errors::StorageError),
     #[error("Interaction with Cache Backend failed")]
     Cache(#[from] crate::cache::error::CacheError),
+    #[error("Interaction with Key-Value Store failed")]
+    KeyValue(#[from] crate::keyvalue::KeyValueError),
+    #[error("Encryption of a secret failed")]
+    Secret(#[from] crate::secrets::SecretError),
+    #[error("Secret '{name}' can't be decrypted")]
+    UndecryptableSecret {
+        name: String,
+        #[source]
+        source: crate::secrets::SecretError,
+    },
+    #[error("Stored revision doesn't match the function")]
+    InvalidRevision,
+    #[error("{0}")]
+    RouteConflict(String),
 }
 
 impl IntoResponse for ServiceError {
     fn into_response(self) -> Response {
         match self {
-            ServiceError::Db(_) => (
+            ServiceError::Secret(crate::secrets::SecretError::NotConfigured) => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(ErrorResponse {
+                    message: "Secrets are disabled, the runtime has no encryption key",
+                }),
+            )
+                .into_response(),
+            ServiceError::Db(_)
+            | ServiceError::KeyValue(_)
+            | ServiceError::Secret(_)
+            | ServiceError::InvalidRevision => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(ErrorResponse {
                     message: "Internal server error",
                 }),
             )
                 .into_response(),
+            ServiceError::UndecryptableSecret { .. } => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    message: &self.to_string(),
+                }),
+            )
+                .into_response(),
+            ServiceError::RouteConflict(message) => (
+                StatusCode::CONFLICT,
+                Json(ErrorResponse { message: &message }),
+            )
+                .into_response(),
             ServiceError::Storage(storage_err) => storage_err.into_response(),
             ServiceError::Cache(cache_err) => cache_err.into_response(),
         }
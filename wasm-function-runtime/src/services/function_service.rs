@@ -1,29 +1,61 @@
-use sea_orm::{prelude::*, IntoActiveModel, Set};
+use sea_orm::{prelude::*, ConnectionTrait, IntoActiveModel, Set};
 use std::ops::Deref;
 
 use crate::{
+    component::artifact::ArtifactCache,
     db::DbPool,
-    domain::{self, function::WasmFunctionTrait},
+    domain::{self, function::WasmFunctionTrait, retry::RetryPolicy},
     handlers::api_handler::{CreateHttpFunctionPayload, CreateScheduledFunctionPayload},
-    services::scope_service,
+    invalidation::{InvalidationEvent, Invalidator},
+    routing,
+    services::{revision_service, rollout_service, scope_service},
     storage,
 };
+use tracing::warn;
 
 use super::errors::ServiceError;
 
 pub(crate) async fn find_http_func_by_scope_and_req(
     db_pool: &DbPool,
+    route_cache: &routing::RouteCache,
     scope_name: &str,
     function_path: &str,
     function_method: &str,
-) -> Result<Option<domain::function::HttpFunction>, ServiceError> {
-    let scope = match entity::scope::Entity::find()
-        .filter(entity::scope::Column::Name.eq(scope_name))
-        .one(db_pool)
-        .await?
-    {
-        Some(scope) => scope,
-        None => return Ok(None),
+) -> Result<
+    Option<(
+        domain::function::HttpFunction,
+        routing::RouteParams,
+        Option<routing::rollout::Canary>,
+    )>,
+    ServiceError,
+> {
+    let scope_routes = match route_cache.get(scope_name).await {
+        Some(scope_routes) => scope_routes,
+        None => {
+            let scope = match scope_service::get_scope_by_name(db_pool, scope_name).await? {
+                Some(scope) => scope,
+                None => return Ok(None),
+            };
+
+            let http_functions: Vec<domain::function::HttpFunction> =
+                entity::http_function::Entity::find()
+                    .filter(entity::http_function::Column::ScopeId.eq(scope.uuid))
+                    .all(db_pool)
+                    .await?
+                    .into_iter()
+                    .map(domain::function::HttpFunction::from)
+                    .collect();
+
+            let canaries = rollout_service::find_canaries(db_pool, &http_functions).await?;
+
+            let scope_routes = std::sync::Arc::new(
+                routing::ScopeRoutes::new(http_functions).with_canaries(canaries),
+            );
+            route_cache
+                .insert(scope_name.to_string(), scope_routes.clone())
+                .await;
+            scope_routes
+        }
     };
 
     let path = if function_path.starts_with('/') {
@@ -32,14 +64,12 @@ pub(crate) async fn find_http_func_by_scope_and_req(
         format!("/{}", function_path)
     };
 
-    let http_function = entity::http_function::Entity::find()
-        .filter(entity::http_function::Column::Path.eq(&path))
-        .filter(entity::http_function::Column::Method.eq(function_method))
-        .filter(entity::http_function::Column::ScopeId.eq(scope.id))
-        .one(db_pool)
-        .await?;
-
-    Ok(http_function.map(domain::function::HttpFunction::from))
+    Ok(scope_routes
+        .resolve(&path, function_method)
+        .map(|(function, params)| {
+            let canary = scope_routes.canary(&function.uuid).cloned();
+            (function, params, canary)
+        }))
 }
 
 pub(crate) async fn find_all_funcs(
@@ -88,6 +118,48 @@ pub(crate) async fn find_all_funcs(
     }
 }
 
+pub(crate) async fn find_scheduled_func(
+    db_pool: &DbPool,
+    function_id: &Uuid,
+) -> Result<Option<domain::function::ScheduledFunction>, ServiceError> {
+    Ok(entity::scheduled_function::Entity::find_by_id(*function_id)
+        .one(db_pool)
+        .await?
+        .map(|model| model.into()))
+}
+
+/// The scheduled function as it runs with the revision, without activating the revision.
+/// Returns `None` if the function or the revision doesn't exist.
+pub(crate) async fn find_scheduled_func_revision(
+    db_pool: &DbPool,
+    function_id: &Uuid,
+    revision: i32,
+) -> Result<Option<domain::function::ScheduledFunction>, ServiceError> {
+    let Some(function) = find_scheduled_func(db_pool, function_id).await? else {
+        return Ok(None);
+    };
+    let Some(target) = revision_service::find_revision(db_pool, function_id, revision).await?
+    else {
+        return Ok(None);
+    };
+    let manifest = toml::from_str::<domain::manifest::Manifest>(&target.manifest)
+        .map_err(|_| ServiceError::InvalidRevision)?;
+    let scheduled = manifest.scheduled.ok_or(ServiceError::InvalidRevision)?;
+
+    Ok(Some(domain::function::ScheduledFunction {
+        cron: scheduled.cron,
+        timezone: scheduled.timezone,
+        content_hash: target.content_hash,
+        limits: manifest.limits.unwrap_or_default(),
+        revision: Some(target.revision),
+        run_history: scheduled.run_history,
+        retry: scheduled.retry,
+        overlap: scheduled.overlap,
+        run_timeout_secs: scheduled.timeout_secs,
+        ..function
+    }))
+}
+
 pub(crate) async fn find_all_scheduled_func(
     db_pool: &DbPool,
 ) -> Result<Vec<domain::function::ScheduledFunction>, ServiceError> {
@@ -101,111 +173,206 @@ pub(crate) async fn find_all_scheduled_func(
 
 pub(crate) async fn delete_http_func(
     db_pool: &DbPool,
-    cache_backend: &dyn crate::cache::CacheBackend,
+    route_cache: &routing::RouteCache,
+    invalidator: &Invalidator,
     storage_backend: &dyn storage::StorageBackend,
+    artifact_cache: &ArtifactCache,
     function_id: &uuid::Uuid,
 ) -> Result<(), ServiceError> {
     let http_function = entity::http_function::Entity::find()
         .filter(entity::http_function::Column::Id.eq(*function_id))
+        .find_also_related(entity::scope::Entity)
         .one(db_pool)
         .await?;
 
-    if let Some(http_function) = http_function {
+    if let Some((http_function, func_scope)) = http_function {
         http_function.clone().delete(db_pool).await?;
+        super::log_service::delete_logs(db_pool, vec![http_function.id]).await?;
+
+        if let Some(func_scope) = func_scope {
+            route_cache.invalidate(&func_scope.name).await;
+            invalidator
+                .publish(InvalidationEvent::FunctionDeleted {
+                    scope: func_scope.name,
+                    function_id: http_function.id,
+                })
+                .await;
+        }
 
         let http_function: domain::function::HttpFunction = http_function.into();
         storage_backend
             .delete_file(&http_function.related_wasm())
             .await?;
 
-        cache_backend
-            .invalidate(&http_function.related_wasm())
-            .await?;
-    }
-    Ok(())
-}
+        artifact_cache.remove(&http_function.related_wasm()).await?;
 
-pub(crate) async fn find_scheduled_func(
-    db_pool: &DbPool,
-    storage_backend: &dyn storage::StorageBackend,
-    function_id: &uuid::Uuid,
-) -> Result<Option<(domain::function::ScheduledFunction, Vec<u8>)>, ServiceError> {
-    let func: Option<domain::function::ScheduledFunction> =
-        entity::scheduled_function::Entity::find()
-            .filter(entity::scheduled_function::Column::Id.eq(*function_id))
-            .one(db_pool)
-            .await?
-            .map(|model| model.into());
-
-    if let Some(func) = func {
-        let file_name = func.related_wasm();
-        Ok(Some((
-            func,
-            storage_backend.extract_file_bytes(&file_name).await?,
-        )))
-    } else {
-        Ok(None)
+        revision_service::delete_revisions(
+            db_pool,
+            storage_backend,
+            artifact_cache,
+            "http",
+            &http_function.uuid,
+            &http_function.content_hash,
+        )
+        .await?;
     }
+    Ok(())
 }
 
 pub(crate) async fn delete_scheduled_func(
     db_pool: &DbPool,
     cache: &dyn crate::scheduler::FunctionSchedulerManagerTrait,
+    invalidator: &Invalidator,
     storage_backend: &dyn storage::StorageBackend,
+    artifact_cache: &ArtifactCache,
     function_id: &uuid::Uuid,
 ) -> Result<(), ServiceError> {
     let scheduled_function = entity::scheduled_function::Entity::find()
         .filter(entity::scheduled_function::Column::Id.eq(*function_id))
+        .find_also_related(entity::scope::Entity)
         .one(db_pool)
         .await?;
 
-    if let Some(scheduled_function) = scheduled_function {
+    if let Some((scheduled_function, func_scope)) = scheduled_function {
         scheduled_function.clone().delete(db_pool).await?;
+        super::log_service::delete_logs(db_pool, vec![scheduled_function.id]).await?;
+        super::run_service::delete_runs(db_pool, vec![scheduled_function.id]).await?;
+        super::dead_letter_service::delete_dead_letters(db_pool, vec![scheduled_function.id])
+            .await?;
 
         let scheduled_function: domain::function::ScheduledFunction = scheduled_function.into();
         storage_backend
             .delete_file(&scheduled_function.related_wasm())
             .await?;
+        artifact_cache
+            .remove(&scheduled_function.related_wasm())
+            .await?;
 
         cache.remove(&scheduled_function.uuid).await;
+        if let Some(func_scope) = func_scope {
+            invalidator
+                .publish(InvalidationEvent::FunctionDeleted {
+                    scope: func_scope.name,
+                    function_id: scheduled_function.uuid,
+                })
+                .await;
+        }
+
+        revision_service::delete_revisions(
+            db_pool,
+            storage_backend,
+            artifact_cache,
+            "scheduled",
+            &scheduled_function.uuid,
+            &scheduled_function.content_hash,
+        )
+        .await?;
     }
     Ok(())
 }
 
 pub(crate) async fn create_http_func(
     db_pool: &DbPool,
+    route_cache: &routing::RouteCache,
+    invalidator: &Invalidator,
     storage_backend: &dyn crate::storage::StorageBackend,
+    artifact_cache: &ArtifactCache,
     payload: CreateHttpFunctionPayload,
+    revision_limit: u64,
 ) -> Result<domain::function::HttpFunction, ServiceError> {
     let transaction = db_pool.start_transaction().await;
 
     let scope =
         crate::services::scope_service::create_or_find_scope(&transaction, &payload.scope).await?;
 
-    let http_function: domain::function::HttpFunction = match entity::http_function::Entity::find()
+    let [timeout_secs, fuel, max_memory_mb, max_table_elements] = payload.limits.to_columns();
+    let content_hash = domain::function::Function::hash(&payload.wasm_bytes);
+
+    let existing_http_function = entity::http_function::Entity::find()
         .filter(entity::http_function::Column::ScopeId.eq(scope.uuid))
         .filter(entity::http_function::Column::Name.eq(&payload.name))
         .one(transaction.deref())
-        .await?
-    {
+        .await?;
+    let function_id = existing_http_function
+        .as_ref()
+        .map_or_else(Uuid::new_v4, |function| function.id);
+    let deployed = deploy_event(
+        existing_http_function.is_some(),
+        &payload.scope,
+        function_id,
+    );
+    // New functions have no active revision to split the traffic with
+    let starts_rollout = payload.rollout.is_some()
+        && existing_http_function
+            .as_ref()
+            .is_some_and(|function| !function.pinned);
+
+    ensure_route_available(
+        transaction.deref(),
+        &scope.uuid,
+        &function_id,
+        &payload.path,
+        &payload.method,
+    )
+    .await?;
+
+    let revision = revision_service::record_revision(
+        transaction.deref(),
+        &function_id,
+        &content_hash,
+        &payload.manifest,
+        payload.deployed_by.as_deref(),
+    )
+    .await?;
+
+    if let Some(rollout) = payload.rollout.as_ref().filter(|_| starts_rollout) {
+        rollout_service::upsert_rollout(transaction.deref(), &function_id, revision, rollout)
+            .await?;
+    }
+
+    let http_function: domain::function::HttpFunction = match existing_http_function {
+        // Pinned functions keep their active revision, the new one can be rolled back to.
+        // Rolled out revisions only receive their share of the traffic until promoted.
+        Some(existing_http_function) if existing_http_function.pinned || starts_rollout => {
+            existing_http_function
+        }
         Some(existing_http_function) => {
+            // The new revision replaces whatever was rolled out before
+            entity::function_rollout::Entity::delete_by_id(function_id)
+                .exec(transaction.deref())
+                .await?;
+
             let mut existing_http_function = existing_http_function.into_active_model();
             existing_http_function.method = Set(payload.method);
             existing_http_function.scope_id = Set(scope.uuid);
             existing_http_function.path = Set(payload.path);
             existing_http_function.is_public = Set(payload.is_public);
+            existing_http_function.world = Set(payload.world.to_column());
+            existing_http_function.content_hash = Set(content_hash.clone());
+            existing_http_function.timeout_secs = Set(timeout_secs);
+            existing_http_function.fuel = Set(fuel);
+            existing_http_function.max_memory_mb = Set(max_memory_mb);
+            existing_http_function.max_table_elements = Set(max_table_elements);
+            existing_http_function.revision = Set(Some(revision));
 
             existing_http_function.update(transaction.deref()).await?
         }
         None => {
             entity::http_function::ActiveModel {
-                id: Set(Uuid::new_v4()),
+                id: Set(function_id),
                 name: Set(payload.name),
                 method: Set(payload.method),
                 path: Set(payload.path),
                 is_public: Set(payload.is_public),
+                world: Set(payload.world.to_column()),
                 scope_id: Set(scope.uuid),
-                content_hash: Set(domain::function::Function::hash(&payload.wasm_bytes)),
+                content_hash: Set(content_hash.clone()),
+                timeout_secs: Set(timeout_secs),
+                fuel: Set(fuel),
+                max_memory_mb: Set(max_memory_mb),
+                max_table_elements: Set(max_table_elements),
+                revision: Set(Some(revision)),
+                pinned: Set(false),
             }
             .insert(transaction.deref())
             .await?
@@ -214,70 +381,343 @@ pub(crate) async fn create_http_func(
     .into();
 
     storage_backend
-        .store_file(payload.wasm_bytes, &http_function.related_wasm())
+        .store_file(
+            payload.wasm_bytes,
+            &domain::function::Function::wasm_file_name("http", &function_id, &content_hash),
+        )
         .await?;
 
     transaction.commit().await;
+
+    route_cache.invalidate(&payload.scope).await;
+    invalidator.publish(deployed).await;
+
+    // The deploy succeeded, failing to clean up old revisions is only logged
+    if let Err(err) = revision_service::prune_revisions(
+        db_pool,
+        storage_backend,
+        artifact_cache,
+        "http",
+        &function_id,
+        http_function.revision,
+        revision_limit,
+    )
+    .await
+    {
+        warn!("Failed to prune revisions of function '{function_id}': {err:?}");
+    }
+
     Ok(http_function)
 }
 
 pub(crate) async fn create_scheduled_func(
     db_pool: &DbPool,
     func_scheduler: &dyn crate::scheduler::FunctionSchedulerManagerTrait,
+    invalidator: &Invalidator,
     storage_backend: &dyn crate::storage::StorageBackend,
+    artifact_cache: &ArtifactCache,
     payload: CreateScheduledFunctionPayload,
+    revision_limit: u64,
 ) -> Result<domain::function::ScheduledFunction, ServiceError> {
     let transaction = db_pool.start_transaction().await;
 
     let scope =
         crate::services::scope_service::create_or_find_scope(&transaction, &payload.scope).await?;
 
-    let mut previous_scheduled_func: Option<entity::scheduled_function::Model> = None;
-
-    let scheduled_function: domain::function::ScheduledFunction =
-        match entity::scheduled_function::Entity::find()
-            .filter(entity::scheduled_function::Column::ScopeId.eq(scope.uuid))
-            .filter(entity::scheduled_function::Column::Name.eq(&payload.name))
-            .one(transaction.deref())
-            .await?
-        {
-            Some(existing_scheduled_func) => {
-                previous_scheduled_func = Some(existing_scheduled_func.clone());
+    let [timeout_secs, fuel, max_memory_mb, max_table_elements] = payload.limits.to_columns();
+    let run_history = run_history_column(payload.run_history);
+    let (retry_max_attempts, retry_backoff_secs, retry_max_backoff_secs, retry_jitter) =
+        RetryPolicy::to_columns(payload.retry.as_ref());
+    let overlap = payload.overlap.to_column();
+    let run_timeout_secs = run_timeout_column(payload.run_timeout_secs);
+    let content_hash = domain::function::Function::hash(&payload.wasm_bytes);
+
+    let existing_scheduled_func = entity::scheduled_function::Entity::find()
+        .filter(entity::scheduled_function::Column::ScopeId.eq(scope.uuid))
+        .filter(entity::scheduled_function::Column::Name.eq(&payload.name))
+        .one(transaction.deref())
+        .await?;
+    let function_id = existing_scheduled_func
+        .as_ref()
+        .map_or_else(Uuid::new_v4, |function| function.id);
+    let deployed = deploy_event(
+        existing_scheduled_func.is_some(),
+        &payload.scope,
+        function_id,
+    );
+    let is_pinned = existing_scheduled_func
+        .as_ref()
+        .is_some_and(|function| function.pinned);
+
+    let revision = revision_service::record_revision(
+        transaction.deref(),
+        &function_id,
+        &content_hash,
+        &payload.manifest,
+        payload.deployed_by.as_deref(),
+    )
+    .await?;
 
-                let mut existing_scheduled_func = existing_scheduled_func.into_active_model();
-                existing_scheduled_func.scope_id = Set(scope.uuid);
-                existing_scheduled_func.cron = Set(payload.cron);
+    let mut previous_scheduled_func: Option<entity::scheduled_function::Model> = None;
 
-                existing_scheduled_func.update(transaction.deref()).await?
-            }
-            None => {
-                // No existing scheduled func, so we need to create one
-                entity::scheduled_function::ActiveModel {
-                    id: Set(Uuid::new_v4()),
-                    name: Set(payload.name),
-                    cron: Set(payload.cron),
-                    scope_id: Set(scope.uuid),
-                    content_hash: Set(domain::function::Function::hash(&payload.wasm_bytes)),
-                }
-                .insert(transaction.deref())
-                .await?
+    let scheduled_function: domain::function::ScheduledFunction = match existing_scheduled_func {
+        // Pinned functions keep their active revision, the new one can be rolled back to
+        Some(existing_scheduled_func) if is_pinned => existing_scheduled_func,
+        Some(existing_scheduled_func) => {
+            previous_scheduled_func = Some(existing_scheduled_func.clone());
+
+            let mut existing_scheduled_func = existing_scheduled_func.into_active_model();
+            existing_scheduled_func.scope_id = Set(scope.uuid);
+            existing_scheduled_func.cron = Set(payload.cron);
+            existing_scheduled_func.timezone = Set(payload.timezone);
+            existing_scheduled_func.content_hash = Set(content_hash.clone());
+            existing_scheduled_func.timeout_secs = Set(timeout_secs);
+            existing_scheduled_func.fuel = Set(fuel);
+            existing_scheduled_func.max_memory_mb = Set(max_memory_mb);
+            existing_scheduled_func.max_table_elements = Set(max_table_elements);
+            existing_scheduled_func.revision = Set(Some(revision));
+            existing_scheduled_func.run_history = Set(run_history);
+            existing_scheduled_func.retry_max_attempts = Set(retry_max_attempts);
+            existing_scheduled_func.retry_backoff_secs = Set(retry_backoff_secs);
+            existing_scheduled_func.retry_max_backoff_secs = Set(retry_max_backoff_secs);
+            existing_scheduled_func.retry_jitter = Set(retry_jitter);
+            existing_scheduled_func.overlap = Set(overlap);
+            existing_scheduled_func.run_timeout_secs = Set(run_timeout_secs);
+
+            existing_scheduled_func.update(transaction.deref()).await?
+        }
+        None => {
+            // No existing scheduled func, so we need to create one
+            entity::scheduled_function::ActiveModel {
+                id: Set(function_id),
+                name: Set(payload.name),
+                cron: Set(payload.cron),
+                timezone: Set(payload.timezone),
+                scope_id: Set(scope.uuid),
+                content_hash: Set(content_hash.clone()),
+                timeout_secs: Set(timeout_secs),
+                fuel: Set(fuel),
+                max_memory_mb: Set(max_memory_mb),
+                max_table_elements: Set(max_table_elements),
+                revision: Set(Some(revision)),
+                pinned: Set(false),
+                run_history: Set(run_history),
+                retry_max_attempts: Set(retry_max_attempts),
+                retry_backoff_secs: Set(retry_backoff_secs),
+                retry_max_backoff_secs: Set(retry_max_backoff_secs),
+                retry_jitter: Set(retry_jitter),
+                overlap: Set(overlap),
+                run_timeout_secs: Set(run_timeout_secs),
             }
+            .insert(transaction.deref())
+            .await?
         }
-        .into();
+    }
+    .into();
 
     storage_backend
-        .store_file(payload.wasm_bytes, &scheduled_function.related_wasm())
+        .store_file(
+            payload.wasm_bytes,
+            &domain::function::Function::wasm_file_name("scheduled", &function_id, &content_hash),
+        )
         .await?;
 
     transaction.commit().await;
 
-    if let Some(previous_scheduled_func) = previous_scheduled_func {
-        func_scheduler.remove(&previous_scheduled_func.id).await;
+    // The job of a pinned function keeps running its active revision
+    if !is_pinned {
+        if let Some(previous_scheduled_func) = previous_scheduled_func {
+            func_scheduler.remove(&previous_scheduled_func.id).await;
+        }
+        func_scheduler.add(&scheduled_function).await;
+    }
+    invalidator.publish(deployed).await;
+
+    // The deploy succeeded, failing to clean up old revisions is only logged
+    if let Err(err) = revision_service::prune_revisions(
+        db_pool,
+        storage_backend,
+        artifact_cache,
+        "scheduled",
+        &function_id,
+        scheduled_function.revision,
+        revision_limit,
+    )
+    .await
+    {
+        warn!("Failed to prune revisions of function '{function_id}': {err:?}");
     }
-
-    func_scheduler
-        .add(&scheduled_function.uuid, &scheduled_function.cron)
-        .await;
 
     Ok(scheduled_function)
 }
+
+/// Rejects a route that would shadow or be shadowed by another function of the scope
+async fn ensure_route_available(
+    db: &impl ConnectionTrait,
+    scope_id: &Uuid,
+    function_id: &Uuid,
+    path: &str,
+    method: &str,
+) -> Result<(), ServiceError> {
+    let others: Vec<domain::function::HttpFunction> = entity::http_function::Entity::find()
+        .filter(entity::http_function::Column::ScopeId.eq(*scope_id))
+        .filter(entity::http_function::Column::Id.ne(*function_id))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(domain::function::HttpFunction::from)
+        .collect();
+
+    routing::check_route_conflicts(&others, path, method).map_err(ServiceError::RouteConflict)
+}
+
+/// Other replicas learn about a deploy as created or updated function
+fn deploy_event(exists: bool, scope: &str, function_id: Uuid) -> InvalidationEvent {
+    let scope = scope.to_string();
+    if exists {
+        InvalidationEvent::FunctionUpdated { scope, function_id }
+    } else {
+        InvalidationEvent::FunctionCreated { scope, function_id }
+    }
+}
+
+/// Activates a previous revision of the function with the binary and configuration it was deployed with.
+/// A pinned revision stays active on later deploys until the function is unpinned.
+/// Returns `None` if the function or the revision doesn't exist.
+pub(crate) async fn rollback_func(
+    db_pool: &DbPool,
+    function_id: &Uuid,
+    revision: i32,
+    pin: bool,
+) -> Result<Option<domain::function::Function>, ServiceError> {
+    let transaction = db_pool.start_transaction().await;
+    let function = activate_revision(transaction.deref(), function_id, revision, pin).await?;
+    if function.is_some() {
+        transaction.commit().await;
+    }
+    Ok(function)
+}
+
+/// Activates the revision within the transaction of the caller, see [`rollback_func`]
+pub(crate) async fn activate_revision(
+    db: &impl ConnectionTrait,
+    function_id: &Uuid,
+    revision: i32,
+    pin: bool,
+) -> Result<Option<domain::function::Function>, ServiceError> {
+    let Some(target) = revision_service::find_revision(db, function_id, revision).await? else {
+        return Ok(None);
+    };
+    let manifest = toml::from_str::<domain::manifest::Manifest>(&target.manifest)
+        .map_err(|_| ServiceError::InvalidRevision)?;
+    let [timeout_secs, fuel, max_memory_mb, max_table_elements] =
+        manifest.limits.unwrap_or_default().to_columns();
+
+    let function = if let Some(http_function) =
+        entity::http_function::Entity::find_by_id(*function_id)
+            .one(db)
+            .await?
+    {
+        let http = manifest.http.ok_or(ServiceError::InvalidRevision)?;
+        let world = manifest
+            .function
+            .trigger
+            .http_world()
+            .ok_or(ServiceError::InvalidRevision)?;
+
+        ensure_route_available(
+            db,
+            &http_function.scope_id,
+            function_id,
+            &http.path,
+            &http.method_column(),
+        )
+        .await?;
+
+        let mut http_function = http_function.into_active_model();
+        http_function.method = Set(http.method_column());
+        http_function.path = Set(http.path);
+        http_function.is_public = Set(http.public);
+        http_function.world = Set(world.to_column());
+        http_function.content_hash = Set(target.content_hash);
+        http_function.timeout_secs = Set(timeout_secs);
+        http_function.fuel = Set(fuel);
+        http_function.max_memory_mb = Set(max_memory_mb);
+        http_function.max_table_elements = Set(max_table_elements);
+        http_function.revision = Set(Some(target.revision));
+        http_function.pinned = Set(pin);
+
+        // The activated revision receives all traffic
+        entity::function_rollout::Entity::delete_by_id(*function_id)
+            .exec(db)
+            .await?;
+
+        domain::function::Function::Http(http_function.update(db).await?.into())
+    } else if let Some(scheduled_function) =
+        entity::scheduled_function::Entity::find_by_id(*function_id)
+            .one(db)
+            .await?
+    {
+        let scheduled = manifest.scheduled.ok_or(ServiceError::InvalidRevision)?;
+
+        let mut scheduled_function = scheduled_function.into_active_model();
+        scheduled_function.cron = Set(scheduled.cron);
+        scheduled_function.timezone = Set(scheduled.timezone);
+        scheduled_function.content_hash = Set(target.content_hash);
+        scheduled_function.timeout_secs = Set(timeout_secs);
+        scheduled_function.fuel = Set(fuel);
+        scheduled_function.max_memory_mb = Set(max_memory_mb);
+        scheduled_function.max_table_elements = Set(max_table_elements);
+        scheduled_function.revision = Set(Some(target.revision));
+        scheduled_function.pinned = Set(pin);
+        scheduled_function.run_history = Set(run_history_column(scheduled.run_history));
+        let (retry_max_attempts, retry_backoff_secs, retry_max_backoff_secs, retry_jitter) =
+            RetryPolicy::to_columns(scheduled.retry.as_ref());
+        scheduled_function.retry_max_attempts = Set(retry_max_attempts);
+        scheduled_function.retry_backoff_secs = Set(retry_backoff_secs);
+        scheduled_function.retry_max_backoff_secs = Set(retry_max_backoff_secs);
+        scheduled_function.retry_jitter = Set(retry_jitter);
+        scheduled_function.overlap = Set(scheduled.overlap.to_column());
+        scheduled_function.run_timeout_secs = Set(run_timeout_column(scheduled.timeout_secs));
+
+        domain::function::Function::Scheduled(scheduled_function.update(db).await?.into())
+    } else {
+        return Ok(None);
+    };
+
+    Ok(Some(function))
+}
+
+/// Lets the next deploy activate its revision again. Returns `false` if the function doesn't exist.
+pub(crate) async fn unpin_func(db_pool: &DbPool, function_id: &Uuid) -> Result<bool, ServiceError> {
+    if let Some(http_function) = entity::http_function::Entity::find_by_id(*function_id)
+        .one(db_pool)
+        .await?
+    {
+        let mut http_function = http_function.into_active_model();
+        http_function.pinned = Set(false);
+        http_function.update(db_pool).await?;
+        Ok(true)
+    } else if let Some(scheduled_function) =
+        entity::scheduled_function::Entity::find_by_id(*function_id)
+            .one(db_pool)
+            .await?
+    {
+        let mut scheduled_function = scheduled_function.into_active_model();
+        scheduled_function.pinned = Set(false);
+        scheduled_function.update(db_pool).await?;
+        Ok(true)
+    } else {
+        Ok(false)
+    }
+}
+
+/// The manifest's run history limit as stored with the function
+fn run_history_column(run_history: Option<u32>) -> Option<i32> {
+    run_history.map(|run_history| run_history.try_into().unwrap_or(i32::MAX))
+}
+
+/// The manifest's run timeout as stored with the function
+fn run_timeout_column(run_timeout_secs: Option<u64>) -> Option<i64> {
+    run_timeout_secs.map(|run_timeout_secs| run_timeout_secs.try_into().unwrap_or(i64::MAX))
+}
@@ -0,0 +1,232 @@
+use chrono::{DateTime, Utc};
+use sea_orm::{prelude::*, NotSet, QueryOrder, QuerySelect, Select, Set};
+
+use super::errors::ServiceError;
+use crate::domain::log::{LogEntry, LogLevel};
+
+/// Lines kept per function, older lines are removed when new ones are stored
+pub(crate) const MAX_LOGS_PER_FUNCTION: u64 = 1000;
+
+pub(crate) async fn store_logs(
+    db_pool: &crate::db::DbPool,
+    function_id: &Uuid,
+    entries: Vec<LogEntry>,
+) -> Result<(), ServiceError> {
+    if entries.is_empty() {
+        return Ok(());
+    }
+
+    let models = entries
+        .into_iter()
+        .map(|entry| entity::function_log::ActiveModel {
+            sequence: NotSet,
+            id: Set(Uuid::new_v4()),
+            function_id: Set(entry.function_id),
+            request_id: Set(entry.request_id),
+            source: Set(entry.source.as_ref().to_string()),
+            level: Set(entry.level.as_ref().to_string()),
+            message: Set(entry.message),
+            fields: Set((!entry.fields.is_empty())
+                .then(|| serde_json::to_string(&entry.fields).ok())
+                .flatten()),
+            created_at: Set(entry.timestamp.into()),
+        });
+    entity::function_log::Entity::insert_many(models)
+        .exec(db_pool)
+        .await?;
+
+    // Keep the log store bounded per function
+    let expired_ids: Vec<Uuid> = entity::function_log::Entity::find()
+        .select_only()
+        .column(entity::function_log::Column::Id)
+        .filter(entity::function_log::Column::FunctionId.eq(*function_id))
+        .order_by_desc(entity::function_log::Column::Sequence)
+        .offset(MAX_LOGS_PER_FUNCTION)
+        // SQLite only supports an offset together with a limit
+        .limit(i64::MAX as u64)
+        .into_tuple()
+        .all(db_pool)
+        .await?;
+    if !expired_ids.is_empty() {
+        entity::function_log::Entity::delete_many()
+            .filter(entity::function_log::Column::Id.is_in(expired_ids))
+            .exec(db_pool)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Optional restrictions applied when reading logs
+#[derive(Debug, Default, Clone)]
+pub(crate) struct LogFilter {
+    pub request_id: Option<String>,
+    /// Minimum severity of the returned lines
+    pub level: Option<LogLevel>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl LogFilter {
+    fn apply(
+        &self,
+        mut query: Select<entity::function_log::Entity>,
+    ) -> Select<entity::function_log::Entity> {
+        if let Some(request_id) = &self.request_id {
+            query = query.filter(entity::function_log::Column::RequestId.eq(request_id));
+        }
+        if let Some(level) = self.level {
+            query = query.filter(
+                entity::function_log::Column::Level
+                    .is_in(level.at_least().map(|level| level.as_ref().to_owned())),
+            );
+        }
+        if let Some(since) = self.since {
+            query = query.filter(entity::function_log::Column::CreatedAt.gte(since));
+        }
+        if let Some(until) = self.until {
+            query = query.filter(entity::function_log::Column::CreatedAt.lte(until));
+        }
+        query
+    }
+}
+
+/// Returns the latest logs of the function, ordered from old to new
+pub(crate) async fn find_logs(
+    db_pool: &crate::db::DbPool,
+    function_id: &Uuid,
+    filter: &LogFilter,
+    limit: u64,
+) -> Result<Vec<LogEntry>, ServiceError> {
+    let query = entity::function_log::Entity::find()
+        .filter(entity::function_log::Column::FunctionId.eq(*function_id));
+
+    let mut logs: Vec<LogEntry> = filter
+        .apply(query)
+        .order_by_desc(entity::function_log::Column::CreatedAt)
+        .limit(limit)
+        .all(db_pool)
+        .await?
+        .into_iter()
+        .map(|model| model.into())
+        .collect();
+    logs.reverse();
+
+    Ok(logs)
+}
+
+/// Position of the latest stored log line of the function, tailing from it returns only new lines
+pub(crate) async fn latest_log_sequence(
+    db_pool: &crate::db::DbPool,
+    function_id: &Uuid,
+) -> Result<i64, ServiceError> {
+    let latest: Option<i64> = entity::function_log::Entity::find()
+        .select_only()
+        .column(entity::function_log::Column::Sequence)
+        .filter(entity::function_log::Column::FunctionId.eq(*function_id))
+        .order_by_desc(entity::function_log::Column::Sequence)
+        .into_tuple()
+        .one(db_pool)
+        .await?;
+    Ok(latest.unwrap_or_default())
+}
+
+/// Returns the logs stored after the given position together with their position, ordered by insertion.
+/// Lines are persisted after the invocation, so their timestamps may be older than lines stored before.
+pub(crate) async fn find_logs_after(
+    db_pool: &crate::db::DbPool,
+    function_id: &Uuid,
+    filter: &LogFilter,
+    after_sequence: i64,
+    limit: u64,
+) -> Result<Vec<(i64, LogEntry)>, ServiceError> {
+    let query = entity::function_log::Entity::find()
+        .filter(entity::function_log::Column::FunctionId.eq(*function_id))
+        .filter(entity::function_log::Column::Sequence.gt(after_sequence));
+
+    Ok(filter
+        .apply(query)
+        .order_by_asc(entity::function_log::Column::Sequence)
+        .limit(limit)
+        .all(db_pool)
+        .await?
+        .into_iter()
+        .map(|model| (model.sequence, model.into()))
+        .collect())
+}
+
+pub(crate) async fn delete_logs(
+    db_pool: &crate::db::DbPool,
+    function_ids: Vec<Uuid>,
+) -> Result<(), ServiceError> {
+    entity::function_log::Entity::delete_many()
+        .filter(entity::function_log::Column::FunctionId.is_in(function_ids))
+        .exec(db_pool)
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::log::LogSource;
+
+    fn entry(function_id: Uuid, message: &str, timestamp: DateTime<Utc>) -> LogEntry {
+        LogEntry {
+            function_id,
+            request_id: None,
+            source: LogSource::Log,
+            level: LogLevel::Info,
+            message: message.to_string(),
+            fields: Vec::new(),
+            timestamp,
+        }
+    }
+
+    #[tokio::test]
+    async fn tail_lines_in_insertion_order() {
+        let db_pool = crate::db::init_pool("sqlite::memory:").await;
+        crate::db::run_migrations(&db_pool).await;
+        let function_id = Uuid::new_v4();
+        let now = Utc::now();
+        let messages = |logs: Vec<(i64, LogEntry)>| {
+            logs.into_iter()
+                .map(|(_, entry)| entry.message)
+                .collect::<Vec<String>>()
+        };
+
+        store_logs(
+            &db_pool,
+            &function_id,
+            vec![entry(function_id, "first", now)],
+        )
+        .await
+        .unwrap();
+        let cursor = latest_log_sequence(&db_pool, &function_id).await.unwrap();
+
+        // Logged before the cursor, but persisted after it
+        let earlier = now - chrono::Duration::seconds(5);
+        store_logs(
+            &db_pool,
+            &function_id,
+            vec![
+                entry(function_id, "late", earlier),
+                entry(function_id, "same-a", now),
+                entry(function_id, "same-b", now),
+            ],
+        )
+        .await
+        .unwrap();
+
+        let page = find_logs_after(&db_pool, &function_id, &LogFilter::default(), cursor, 2)
+            .await
+            .unwrap();
+        let next = page.last().unwrap().0;
+        assert_eq!(messages(page), vec!["late", "same-a"]);
+
+        let page = find_logs_after(&db_pool, &function_id, &LogFilter::default(), next, 2)
+            .await
+            .unwrap();
+        assert_eq!(messages(page), vec!["same-b"]);
+    }
+}
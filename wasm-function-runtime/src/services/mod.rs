@@ -1,4 +1,10 @@
+pub(crate) mod dead_letter_service;
 pub(crate) mod errors;
 pub(crate) mod function_service;
+pub(crate) mod log_service;
+pub(crate) mod revision_service;
+pub(crate) mod rollout_service;
+pub(crate) mod run_service;
 pub(crate) mod scope_service;
+pub(crate) mod secret_service;
 pub(crate) mod variable_service;
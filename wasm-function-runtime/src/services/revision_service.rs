@@ -0,0 +1,222 @@
+use std::collections::HashSet;
+
+use sea_orm::{
+    prelude::*, ConnectionTrait, QueryOrder, QuerySelect, Set, SqlErr, TransactionTrait,
+};
+
+use super::errors::ServiceError;
+use crate::{
+    component::artifact::ArtifactCache,
+    domain::{function::Function, revision::FunctionRevision},
+};
+
+/// Revisions kept per function if not configured otherwise
+pub(crate) const DEFAULT_REVISION_LIMIT: u64 = 10;
+
+/// Attempts to number a revision before concurrent deploys of the function fail
+const RECORD_REVISION_ATTEMPTS: usize = 5;
+
+/// Stores a new revision of the function and returns its number.
+/// A concurrent deploy may take the same number, the revision is then numbered again.
+pub(crate) async fn record_revision(
+    db: &impl TransactionTrait,
+    function_id: &Uuid,
+    content_hash: &str,
+    manifest: &str,
+    deployed_by: Option<&str>,
+) -> Result<i32, ServiceError> {
+    let mut attempt = 1;
+    loop {
+        // A failed insert aborts the surrounding transaction in Postgres, unless it is rolled back to a savepoint
+        let savepoint = db.begin().await?;
+        let latest = entity::function_revision::Entity::find()
+            .filter(entity::function_revision::Column::FunctionId.eq(*function_id))
+            .order_by_desc(entity::function_revision::Column::Revision)
+            .one(&savepoint)
+            .await?;
+        let revision = latest.map_or(1, |latest| latest.revision + 1);
+
+        let inserted = entity::function_revision::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            function_id: Set(*function_id),
+            revision: Set(revision),
+            content_hash: Set(content_hash.to_string()),
+            manifest: Set(manifest.to_string()),
+            deployed_by: Set(deployed_by.map(str::to_string)),
+            created_at: Set(chrono::Utc::now().into()),
+        }
+        .insert(&savepoint)
+        .await;
+
+        match inserted {
+            Ok(_) => {
+                savepoint.commit().await?;
+                return Ok(revision);
+            }
+            Err(err)
+                if attempt < RECORD_REVISION_ATTEMPTS
+                    && matches!(err.sql_err(), Some(SqlErr::UniqueConstraintViolation(_))) =>
+            {
+                savepoint.rollback().await?;
+                attempt += 1;
+            }
+            Err(err) => return Err(err.into()),
+        }
+    }
+}
+
+/// Returns all revisions of the function, the latest first
+pub(crate) async fn find_revisions(
+    db_pool: &crate::db::DbPool,
+    function_id: &Uuid,
+) -> Result<Vec<FunctionRevision>, ServiceError> {
+    Ok(entity::function_revision::Entity::find()
+        .filter(entity::function_revision::Column::FunctionId.eq(*function_id))
+        .order_by_desc(entity::function_revision::Column::Revision)
+        .all(db_pool)
+        .await?
+        .into_iter()
+        .map(|model| model.into())
+        .collect())
+}
+
+pub(crate) async fn find_revision(
+    db: &impl ConnectionTrait,
+    function_id: &Uuid,
+    revision: i32,
+) -> Result<Option<FunctionRevision>, ServiceError> {
+    Ok(entity::function_revision::Entity::find()
+        .filter(entity::function_revision::Column::FunctionId.eq(*function_id))
+        .filter(entity::function_revision::Column::Revision.eq(revision))
+        .one(db)
+        .await?
+        .map(|model| model.into()))
+}
+
+/// Removes the oldest revisions beyond the limit together with binaries no remaining revision refers to.
+/// The active and the rolled out revision are always kept, even if they are older.
+pub(crate) async fn prune_revisions(
+    db_pool: &crate::db::DbPool,
+    storage_backend: &dyn crate::storage::StorageBackend,
+    artifact_cache: &ArtifactCache,
+    kind: &str,
+    function_id: &Uuid,
+    active_revision: Option<i32>,
+    limit: u64,
+) -> Result<(), ServiceError> {
+    let rollout_revision = entity::function_rollout::Entity::find_by_id(*function_id)
+        .one(db_pool)
+        .await?
+        .map(|rollout| rollout.revision);
+
+    let outdated: Vec<entity::function_revision::Model> = entity::function_revision::Entity::find()
+        .filter(entity::function_revision::Column::FunctionId.eq(*function_id))
+        .order_by_desc(entity::function_revision::Column::Revision)
+        // SQLite doesn't support an offset without a limit
+        .offset(limit)
+        .limit(i64::MAX as u64)
+        .all(db_pool)
+        .await?
+        .into_iter()
+        .filter(|revision| {
+            Some(revision.revision) != active_revision
+                && Some(revision.revision) != rollout_revision
+        })
+        .collect();
+    if outdated.is_empty() {
+        return Ok(());
+    }
+
+    entity::function_revision::Entity::delete_many()
+        .filter(
+            entity::function_revision::Column::Id
+                .is_in(outdated.iter().map(|revision| revision.id)),
+        )
+        .exec(db_pool)
+        .await?;
+
+    let kept_hashes: HashSet<String> = find_revisions(db_pool, function_id)
+        .await?
+        .into_iter()
+        .map(|revision| revision.content_hash)
+        .collect();
+    let outdated_hashes: HashSet<String> = outdated
+        .into_iter()
+        .map(|revision| revision.content_hash)
+        .filter(|content_hash| !kept_hashes.contains(content_hash))
+        .collect();
+    for content_hash in outdated_hashes {
+        let related_wasm = Function::wasm_file_name(kind, function_id, &content_hash);
+        storage_backend.delete_file(&related_wasm).await?;
+        artifact_cache.remove(&related_wasm).await?;
+    }
+
+    Ok(())
+}
+
+/// Removes all revisions of a deleted function together with their binaries
+pub(crate) async fn delete_revisions(
+    db_pool: &crate::db::DbPool,
+    storage_backend: &dyn crate::storage::StorageBackend,
+    artifact_cache: &ArtifactCache,
+    kind: &str,
+    function_id: &Uuid,
+    active_content_hash: &str,
+) -> Result<(), ServiceError> {
+    let content_hashes: HashSet<String> = find_revisions(db_pool, function_id)
+        .await?
+        .into_iter()
+        .map(|revision| revision.content_hash)
+        .filter(|content_hash| content_hash != active_content_hash)
+        .collect();
+
+    delete_revision_rows(db_pool, vec![*function_id]).await?;
+
+    for content_hash in content_hashes {
+        let related_wasm = Function::wasm_file_name(kind, function_id, &content_hash);
+        storage_backend.delete_file(&related_wasm).await?;
+        artifact_cache.remove(&related_wasm).await?;
+    }
+
+    Ok(())
+}
+
+/// Removes the revision history of the functions, their binaries are left untouched
+pub(crate) async fn delete_revision_rows(
+    db_pool: &crate::db::DbPool,
+    function_ids: Vec<Uuid>,
+) -> Result<(), ServiceError> {
+    entity::function_revision::Entity::delete_many()
+        .filter(entity::function_revision::Column::FunctionId.is_in(function_ids))
+        .exec(db_pool)
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ops::Deref;
+
+    #[tokio::test]
+    async fn number_revisions_within_the_deploy_transaction() {
+        let db_pool = crate::db::init_pool("sqlite::memory:").await;
+        crate::db::run_migrations(&db_pool).await;
+        let function_id = Uuid::new_v4();
+
+        let transaction = db_pool.start_transaction().await;
+        for expected in 1..=2 {
+            let revision =
+                record_revision(transaction.deref(), &function_id, "hash", "manifest", None)
+                    .await
+                    .unwrap();
+            assert_eq!(revision, expected);
+        }
+        transaction.commit().await;
+
+        assert_eq!(
+            find_revisions(&db_pool, &function_id).await.unwrap().len(),
+            2
+        );
+    }
+}
@@ -0,0 +1,279 @@
+use std::{collections::HashMap, ops::Deref};
+
+use sea_orm::{prelude::*, ConnectionTrait, IntoActiveModel, Set};
+use tracing::warn;
+
+use super::{errors::ServiceError, function_service, revision_service};
+use crate::{
+    db::DbPool,
+    domain::{
+        self,
+        function::HttpFunction,
+        rollout::{Rollout, RolloutConfig},
+    },
+    routing::rollout::Canary,
+};
+
+/// Resolves the canaries of the functions, the canary revision is loaded with its own binary and limits
+pub(crate) async fn find_canaries(
+    db_pool: &DbPool,
+    functions: &[HttpFunction],
+) -> Result<HashMap<Uuid, Canary>, ServiceError> {
+    let rollouts = entity::function_rollout::Entity::find()
+        .filter(
+            entity::function_rollout::Column::FunctionId
+                .is_in(functions.iter().map(|function| function.uuid)),
+        )
+        .all(db_pool)
+        .await?;
+
+    let mut canaries = HashMap::new();
+    for rollout in rollouts.into_iter().map(Rollout::from) {
+        let Some(function) = functions
+            .iter()
+            .find(|function| function.uuid == rollout.function_id)
+        else {
+            continue;
+        };
+
+        let manifest = revision_service::find_revision(db_pool, &function.uuid, rollout.revision)
+            .await?
+            .and_then(|revision| {
+                toml::from_str::<domain::manifest::Manifest>(&revision.manifest)
+                    .ok()
+                    .map(|manifest| (revision.content_hash, manifest))
+            });
+        let Some((content_hash, manifest)) = manifest else {
+            warn!(
+                "Ignoring rollout of function '{}', revision {} is unavailable",
+                function.uuid, rollout.revision
+            );
+            continue;
+        };
+
+        let mut canary_function = function.clone();
+        canary_function.content_hash = content_hash;
+        canary_function.limits = manifest.limits.unwrap_or_default();
+        canary_function.revision = Some(rollout.revision);
+        // A canary may be deployed with the other HTTP trigger
+        if let Some(world) = manifest.function.trigger.http_world() {
+            canary_function.world = world;
+        }
+
+        canaries.insert(
+            function.uuid,
+            Canary {
+                function: canary_function,
+                revision: rollout.revision,
+                config: rollout.config,
+            },
+        );
+    }
+
+    Ok(canaries)
+}
+
+pub(crate) async fn find_rollout(
+    db_pool: &DbPool,
+    function_id: &Uuid,
+) -> Result<Option<Rollout>, ServiceError> {
+    Ok(entity::function_rollout::Entity::find_by_id(*function_id)
+        .one(db_pool)
+        .await?
+        .map(Rollout::from))
+}
+
+/// Starts or updates the rollout of a revision of the function.
+/// Returns `None` if the revision doesn't exist.
+pub(crate) async fn set_rollout(
+    db_pool: &DbPool,
+    function_id: &Uuid,
+    revision: i32,
+    config: &RolloutConfig,
+) -> Result<Option<Rollout>, ServiceError> {
+    if revision_service::find_revision(db_pool, function_id, revision)
+        .await?
+        .is_none()
+    {
+        return Ok(None);
+    }
+
+    upsert_rollout(db_pool, function_id, revision, config)
+        .await
+        .map(Some)
+}
+
+/// Replaces the rollout of the function, a function has at most one canary
+pub(crate) async fn upsert_rollout(
+    db: &impl ConnectionTrait,
+    function_id: &Uuid,
+    revision: i32,
+    config: &RolloutConfig,
+) -> Result<Rollout, ServiceError> {
+    let existing_rollout = entity::function_rollout::Entity::find_by_id(*function_id)
+        .one(db)
+        .await?;
+
+    // The key of a new rollout is set, so it has to be inserted explicitly
+    let exists = existing_rollout.is_some();
+    let mut rollout = match existing_rollout {
+        Some(existing_rollout) => existing_rollout.into_active_model(),
+        None => entity::function_rollout::ActiveModel {
+            function_id: Set(*function_id),
+            ..Default::default()
+        },
+    };
+    rollout.revision = Set(revision);
+    rollout.weight = Set(i32::from(config.weight));
+    rollout.sticky_header = Set(config.sticky_header.clone());
+    rollout.sticky_cookie = Set(config.sticky_cookie.clone());
+    rollout.error_threshold = Set(config.error_threshold);
+    rollout.min_requests = Set(config.min_requests.try_into().unwrap_or(i32::MAX));
+    rollout.promote_after = Set(config
+        .promote_after
+        .map(|promote_after| promote_after.try_into().unwrap_or(i32::MAX)));
+    rollout.created_at = Set(chrono::Utc::now().into());
+
+    let rollout = if exists {
+        rollout.update(db).await?
+    } else {
+        rollout.insert(db).await?
+    };
+    Ok(rollout.into())
+}
+
+/// Stops the rollout, all traffic returns to the active revision.
+/// With a `revision`, only a rollout of that revision is stopped.
+/// Returns `false` if the function has no such rollout.
+pub(crate) async fn delete_rollout(
+    db_pool: &DbPool,
+    function_id: &Uuid,
+    revision: Option<i32>,
+) -> Result<bool, ServiceError> {
+    let mut delete = entity::function_rollout::Entity::delete_many()
+        .filter(entity::function_rollout::Column::FunctionId.eq(*function_id));
+    if let Some(revision) = revision {
+        delete = delete.filter(entity::function_rollout::Column::Revision.eq(revision));
+    }
+    let deleted = delete.exec(db_pool).await?;
+    Ok(deleted.rows_affected > 0)
+}
+
+/// Activates the canary revision for all traffic and ends the rollout.
+/// With a `revision`, only a rollout of that revision is promoted.
+/// Returns `None` if the function has no such rollout.
+pub(crate) async fn promote_rollout(
+    db_pool: &DbPool,
+    function_id: &Uuid,
+    revision: Option<i32>,
+) -> Result<Option<domain::function::Function>, ServiceError> {
+    let transaction = db_pool.start_transaction().await;
+
+    let Some(rollout) = entity::function_rollout::Entity::find_by_id(*function_id)
+        .one(transaction.deref())
+        .await?
+    else {
+        return Ok(None);
+    };
+    if revision.is_some_and(|revision| revision != rollout.revision) {
+        return Ok(None);
+    }
+    // Ends only the rollout just read, a rollout replacing it in the meantime stays in place
+    let deleted = entity::function_rollout::Entity::delete_many()
+        .filter(entity::function_rollout::Column::FunctionId.eq(*function_id))
+        .filter(entity::function_rollout::Column::Revision.eq(rollout.revision))
+        .exec(transaction.deref())
+        .await?;
+    if deleted.rows_affected == 0 {
+        return Ok(None);
+    }
+
+    let pinned = entity::http_function::Entity::find_by_id(*function_id)
+        .one(transaction.deref())
+        .await?
+        .is_some_and(|function| function.pinned);
+    let function = function_service::activate_revision(
+        transaction.deref(),
+        function_id,
+        rollout.revision,
+        pinned,
+    )
+    .await?;
+    if function.is_some() {
+        transaction.commit().await;
+    }
+    Ok(function)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::scope_service;
+
+    #[tokio::test]
+    async fn ignore_verdicts_on_a_replaced_rollout() {
+        let db_pool = crate::db::init_pool("sqlite::memory:").await;
+        crate::db::run_migrations(&db_pool).await;
+        let function_id = Uuid::new_v4();
+
+        let transaction = db_pool.start_transaction().await;
+        let scope = scope_service::create_or_find_scope(&transaction, "team-a")
+            .await
+            .unwrap();
+        transaction.commit().await;
+        entity::http_function::ActiveModel {
+            id: Set(function_id),
+            scope_id: Set(scope.uuid),
+            name: Set("canary".to_string()),
+            path: Set("/canary".to_string()),
+            method: Set("GET".to_string()),
+            is_public: Set(true),
+            content_hash: Set("hash".to_string()),
+            timeout_secs: Set(None),
+            fuel: Set(None),
+            max_memory_mb: Set(None),
+            max_table_elements: Set(None),
+            revision: Set(Some(1)),
+            pinned: Set(false),
+            world: Set(None),
+        }
+        .insert(&db_pool)
+        .await
+        .unwrap();
+        let config = RolloutConfig {
+            weight: 50,
+            sticky_header: None,
+            sticky_cookie: None,
+            error_threshold: None,
+            min_requests: 1,
+            promote_after: None,
+        };
+        upsert_rollout(&db_pool, &function_id, 3, &config)
+            .await
+            .unwrap();
+
+        // Verdicts on the canary of revision 2 arrive after revision 3 was rolled out
+        assert!(promote_rollout(&db_pool, &function_id, Some(2))
+            .await
+            .unwrap()
+            .is_none());
+        assert!(!delete_rollout(&db_pool, &function_id, Some(2))
+            .await
+            .unwrap());
+        assert_eq!(
+            find_rollout(&db_pool, &function_id)
+                .await
+                .unwrap()
+                .map(|rollout| rollout.revision),
+            Some(3)
+        );
+
+        assert!(delete_rollout(&db_pool, &function_id, Some(3))
+            .await
+            .unwrap());
+        assert!(find_rollout(&db_pool, &function_id)
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
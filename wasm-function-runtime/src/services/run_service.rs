@@ -0,0 +1,148 @@
+use sea_orm::{prelude::*, QueryOrder, QuerySelect, Set};
+
+use super::errors::ServiceError;
+use crate::domain::run::{RunOutcome, ScheduledRun};
+
+/// Runs kept per scheduled function unless its manifest sets `run_history`
+pub(crate) const DEFAULT_RUN_HISTORY_LIMIT: u64 = 100;
+
+/// Stores the run and removes the runs exceeding the history limit of the function.
+/// Skipped ticks are limited on their own, so a long run skipping many ticks doesn't push out the real runs.
+pub(crate) async fn record_run(
+    db_pool: &crate::db::DbPool,
+    run: &ScheduledRun,
+    history_limit: u64,
+) -> Result<(), ServiceError> {
+    entity::scheduled_run::ActiveModel {
+        id: Set(run.id),
+        function_id: Set(run.function_id),
+        revision: Set(run.revision),
+        attempt: Set(run.attempt.try_into().unwrap_or(i32::MAX)),
+        started_at: Set(run.started_at.into()),
+        finished_at: Set(run.finished_at.into()),
+        duration_ms: Set(run.duration_ms),
+        outcome: Set(run.outcome.as_ref().to_string()),
+        error: Set(run.error.clone()),
+    }
+    .insert(db_pool)
+    .await?;
+
+    let skipped = RunOutcome::Skipped.as_ref();
+    let same_kind = if run.outcome == RunOutcome::Skipped {
+        entity::scheduled_run::Column::Outcome.eq(skipped)
+    } else {
+        entity::scheduled_run::Column::Outcome.ne(skipped)
+    };
+    let expired_ids: Vec<Uuid> = entity::scheduled_run::Entity::find()
+        .select_only()
+        .column(entity::scheduled_run::Column::Id)
+        .filter(entity::scheduled_run::Column::FunctionId.eq(run.function_id))
+        .filter(same_kind)
+        .order_by_desc(entity::scheduled_run::Column::StartedAt)
+        // Functions deployed before a history of 0 was rejected still keep the recorded run
+        .offset(history_limit.max(1))
+        // SQLite only supports an offset together with a limit
+        .limit(i64::MAX as u64)
+        .into_tuple()
+        .all(db_pool)
+        .await?;
+    if !expired_ids.is_empty() {
+        entity::scheduled_run::Entity::delete_many()
+            .filter(entity::scheduled_run::Column::Id.is_in(expired_ids))
+            .exec(db_pool)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Returns the latest runs of the function, ordered from new to old
+pub(crate) async fn find_runs(
+    db_pool: &crate::db::DbPool,
+    function_id: &Uuid,
+    limit: u64,
+) -> Result<Vec<ScheduledRun>, ServiceError> {
+    Ok(entity::scheduled_run::Entity::find()
+        .filter(entity::scheduled_run::Column::FunctionId.eq(*function_id))
+        .order_by_desc(entity::scheduled_run::Column::StartedAt)
+        .limit(limit)
+        .all(db_pool)
+        .await?
+        .into_iter()
+        .map(ScheduledRun::from)
+        .collect())
+}
+
+pub(crate) async fn find_run(
+    db_pool: &crate::db::DbPool,
+    function_id: &Uuid,
+    run_id: &Uuid,
+) -> Result<Option<ScheduledRun>, ServiceError> {
+    Ok(entity::scheduled_run::Entity::find_by_id(*run_id)
+        .filter(entity::scheduled_run::Column::FunctionId.eq(*function_id))
+        .one(db_pool)
+        .await?
+        .map(ScheduledRun::from))
+}
+
+pub(crate) async fn delete_runs(
+    db_pool: &crate::db::DbPool,
+    function_ids: Vec<Uuid>,
+) -> Result<(), ServiceError> {
+    entity::scheduled_run::Entity::delete_many()
+        .filter(entity::scheduled_run::Column::FunctionId.is_in(function_ids))
+        .exec(db_pool)
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn keep_runs_next_to_skipped_ticks() {
+        let db_pool = crate::db::init_pool("sqlite::memory:").await;
+        crate::db::run_migrations(&db_pool).await;
+        let function_id = Uuid::new_v4();
+        let succeeded = |started_at| {
+            ScheduledRun::finished::<String>(
+                Uuid::new_v4(),
+                function_id,
+                None,
+                1,
+                started_at,
+                &Ok(()),
+            )
+        };
+        let now = chrono::Utc::now();
+
+        record_run(&db_pool, &succeeded(now - chrono::Duration::minutes(2)), 2)
+            .await
+            .unwrap();
+        record_run(&db_pool, &succeeded(now - chrono::Duration::minutes(1)), 2)
+            .await
+            .unwrap();
+        // A long run skips more ticks than the history keeps
+        for _ in 0..3 {
+            let skipped = ScheduledRun::skipped(Uuid::new_v4(), function_id, None);
+            record_run(&db_pool, &skipped, 2).await.unwrap();
+        }
+
+        let outcomes: Vec<RunOutcome> = find_runs(&db_pool, &function_id, 10)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|run| run.outcome)
+            .collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                RunOutcome::Skipped,
+                RunOutcome::Skipped,
+                RunOutcome::Success,
+                RunOutcome::Success,
+            ]
+        );
+    }
+}
@@ -32,6 +32,16 @@ pub(crate) async fn get_scope_by_name(
         .map(|scope| scope.into()))
 }
 
+pub(crate) async fn get_scope_by_id(
+    db_pool: &crate::db::DbPool,
+    scope_id: &Uuid,
+) -> Result<Option<crate::domain::scope::FunctionScope>, ServiceError> {
+    Ok(entity::scope::Entity::find_by_id(*scope_id)
+        .one(db_pool)
+        .await?
+        .map(|scope| scope.into()))
+}
+
 pub(crate) async fn create_or_find_scope(
     db_transaction: &crate::db::DbTransaction,
     scope_name: &str,
@@ -55,16 +65,43 @@ pub(crate) async fn create_or_find_scope(
 
 pub(crate) async fn delete_scope(
     db_pool: &crate::db::DbPool,
+    key_value_backend: &dyn crate::keyvalue::KeyValueBackend,
     scope_name: &str,
-) -> Result<(), ServiceError> {
+) -> Result<Vec<Uuid>, ServiceError> {
     let scope_to_delete = entity::scope::Entity::find()
         .filter(entity::scope::Column::Name.eq(scope_name))
         .one(db_pool)
         .await?;
 
     if let Some(scope) = scope_to_delete {
+        let scope_id = scope.id;
+
+        // Logs are not bound to the functions by a foreign key
+        let mut function_ids: Vec<Uuid> = entity::http_function::Entity::find()
+            .filter(entity::http_function::Column::ScopeId.eq(scope_id))
+            .all(db_pool)
+            .await?
+            .into_iter()
+            .map(|function| function.id)
+            .collect();
+        function_ids.extend(
+            entity::scheduled_function::Entity::find()
+                .filter(entity::scheduled_function::Column::ScopeId.eq(scope_id))
+                .all(db_pool)
+                .await?
+                .into_iter()
+                .map(|function| function.id),
+        );
+
         scope.delete(db_pool).await?;
+        super::log_service::delete_logs(db_pool, function_ids.clone()).await?;
+        super::run_service::delete_runs(db_pool, function_ids.clone()).await?;
+        super::dead_letter_service::delete_dead_letters(db_pool, function_ids.clone()).await?;
+        super::revision_service::delete_revision_rows(db_pool, function_ids.clone()).await?;
+        // Stores outside of the database are not cleaned up by the cascade
+        key_value_backend.clear(&scope_id).await?;
+        return Ok(function_ids);
     }
 
-    Ok(())
+    Ok(Vec::new())
 }
@@ -0,0 +1,238 @@
+use sea_orm::{prelude::*, IntoActiveModel, Set};
+use tracing::info;
+
+use super::{errors::ServiceError, scope_service};
+use crate::{
+    domain::secret::{Secret, SecretValue},
+    secrets::SecretCipher,
+};
+
+pub(crate) async fn find_all_secrets(
+    db_pool: &crate::db::DbPool,
+    scope_name: &str,
+) -> Result<Vec<Secret>, ServiceError> {
+    if let Some(scope) = scope_service::get_scope_by_name(db_pool, scope_name).await? {
+        let mut secrets: Vec<Secret> = entity::secret::Entity::find()
+            .filter(entity::secret::Column::ScopeId.eq(scope.uuid))
+            .all(db_pool)
+            .await?
+            .into_iter()
+            .map(|secret| secret.into())
+            .collect();
+
+        // Sort the secrets by name
+        secrets.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(secrets)
+    } else {
+        Ok(vec![])
+    }
+}
+
+/// Decrypts all secrets of the scope to hand them to a function.
+/// A secret that can't be decrypted is reported by its name.
+pub(crate) async fn find_secret_values(
+    db_pool: &crate::db::DbPool,
+    cipher: &SecretCipher,
+    scope_id: &Uuid,
+) -> Result<Vec<SecretValue>, ServiceError> {
+    entity::secret::Entity::find()
+        .filter(entity::secret::Column::ScopeId.eq(*scope_id))
+        .all(db_pool)
+        .await?
+        .into_iter()
+        .map(
+            |secret| match cipher.decrypt(&secret.value, scope_id, &secret.name) {
+                Ok(value) => Ok(SecretValue {
+                    value,
+                    name: secret.name,
+                }),
+                Err(source) => Err(ServiceError::UndecryptableSecret {
+                    name: secret.name,
+                    source,
+                }),
+            },
+        )
+        .collect()
+}
+
+/// Stores the secret encrypted, an existing secret with the same name is overwritten.
+/// Returns `None` if the scope doesn't exist.
+pub(crate) async fn set_secret(
+    db_pool: &crate::db::DbPool,
+    cipher: &SecretCipher,
+    scope_name: &str,
+    name: &str,
+    value: &str,
+) -> Result<Option<Secret>, ServiceError> {
+    let Some(scope) = scope_service::get_scope_by_name(db_pool, scope_name).await? else {
+        return Ok(None);
+    };
+    let encrypted_value = cipher.encrypt(value, &scope.uuid, name)?;
+
+    let existing = entity::secret::Entity::find()
+        .filter(entity::secret::Column::ScopeId.eq(scope.uuid))
+        .filter(entity::secret::Column::Name.eq(name))
+        .one(db_pool)
+        .await?;
+
+    let secret = match existing {
+        Some(secret) => {
+            let mut secret = secret.into_active_model();
+            secret.value = Set(encrypted_value);
+            secret.update(db_pool).await?
+        }
+        None => {
+            entity::secret::ActiveModel {
+                id: Set(Uuid::new_v4()),
+                scope_id: Set(scope.uuid),
+                name: Set(name.to_string()),
+                value: Set(encrypted_value),
+            }
+            .insert(db_pool)
+            .await?
+        }
+    };
+
+    Ok(Some(secret.into()))
+}
+
+/// Deletes the secret if it belongs to the scope.
+/// Returns `false` if the scope or a secret with the id in it doesn't exist.
+pub(crate) async fn delete_secret(
+    db_pool: &crate::db::DbPool,
+    scope_name: &str,
+    secret_id: &Uuid,
+) -> Result<bool, ServiceError> {
+    let Some(scope) = scope_service::get_scope_by_name(db_pool, scope_name).await? else {
+        return Ok(false);
+    };
+
+    let deleted = entity::secret::Entity::delete_many()
+        .filter(entity::secret::Column::Id.eq(*secret_id))
+        .filter(entity::secret::Column::ScopeId.eq(scope.uuid))
+        .exec(db_pool)
+        .await?;
+    Ok(deleted.rows_affected > 0)
+}
+
+/// Whether any scope has secrets, which a runtime without encryption key can't pass on
+pub(crate) async fn has_secrets(db_pool: &crate::db::DbPool) -> Result<bool, ServiceError> {
+    Ok(entity::secret::Entity::find().count(db_pool).await? > 0)
+}
+
+/// Rewraps all secrets that are not encrypted with the primary key yet
+pub(crate) async fn rotate_secrets(
+    db_pool: &crate::db::DbPool,
+    cipher: &SecretCipher,
+) -> Result<(), ServiceError> {
+    let mut rotated = 0;
+    for secret in entity::secret::Entity::find().all(db_pool).await? {
+        if !cipher.needs_rotation(&secret.value)? {
+            continue;
+        }
+        let value = cipher.rotate(&secret.value)?;
+        let mut secret = secret.into_active_model();
+        secret.value = Set(value);
+        secret.update(db_pool).await?;
+        rotated += 1;
+    }
+
+    if rotated > 0 {
+        info!("Rotated the encryption key of {rotated} secrets");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn create_scope(db_pool: &crate::db::DbPool, name: &str) {
+        let transaction = db_pool.start_transaction().await;
+        scope_service::create_or_find_scope(&transaction, name)
+            .await
+            .unwrap();
+        transaction.commit().await;
+    }
+
+    #[tokio::test]
+    async fn delete_secrets_only_within_their_scope() {
+        let db_pool = crate::db::init_pool("sqlite::memory:").await;
+        crate::db::run_migrations(&db_pool).await;
+        let key = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, [1; 32]);
+        let cipher = SecretCipher::new(&key, &[]).unwrap();
+        create_scope(&db_pool, "team-a").await;
+        create_scope(&db_pool, "team-b").await;
+
+        let secret = set_secret(&db_pool, &cipher, "team-a", "TOKEN", "hunter2")
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert!(!delete_secret(&db_pool, "team-b", &secret.uuid)
+            .await
+            .unwrap());
+        assert!(!delete_secret(&db_pool, "missing", &secret.uuid)
+            .await
+            .unwrap());
+        assert_eq!(find_all_secrets(&db_pool, "team-a").await.unwrap().len(), 1);
+
+        assert!(delete_secret(&db_pool, "team-a", &secret.uuid)
+            .await
+            .unwrap());
+        assert!(find_all_secrets(&db_pool, "team-a")
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn name_the_secret_that_fails_to_decrypt() {
+        let db_pool = crate::db::init_pool("sqlite::memory:").await;
+        crate::db::run_migrations(&db_pool).await;
+        let key = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, [1; 32]);
+        let cipher = SecretCipher::new(&key, &[]).unwrap();
+        create_scope(&db_pool, "team-a").await;
+
+        let token = set_secret(&db_pool, &cipher, "team-a", "TOKEN", "hunter2")
+            .await
+            .unwrap()
+            .unwrap();
+        let password = set_secret(&db_pool, &cipher, "team-a", "PASSWORD", "letmein")
+            .await
+            .unwrap()
+            .unwrap();
+        let scope = scope_service::get_scope_by_name(&db_pool, "team-a")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            find_secret_values(&db_pool, &cipher, &scope.uuid)
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+
+        // Copy the value of another secret over the token
+        let stored = entity::secret::Entity::find_by_id(password.uuid)
+            .one(&db_pool)
+            .await
+            .unwrap()
+            .unwrap();
+        let mut copied = entity::secret::Entity::find_by_id(token.uuid)
+            .one(&db_pool)
+            .await
+            .unwrap()
+            .unwrap()
+            .into_active_model();
+        copied.value = Set(stored.value);
+        copied.update(&db_pool).await.unwrap();
+
+        assert!(matches!(
+            find_secret_values(&db_pool, &cipher, &scope.uuid).await,
+            Err(ServiceError::UndecryptableSecret { ref name, .. }) if name == "TOKEN"
+        ));
+    }
+}
@@ -20,13 +20,79 @@ pub(crate) async fn run_server() {
     db::run_migrations(&db_pool).await;
 
     // Setup Cache based on configuration
+    let redis_cache = match &app_config.redis_cache {
+        Some(redis_config) => Some(std::sync::Arc::new(
+            crate::cache::RedisCache::new(&redis_config.connection_str).await,
+        )),
+        None => None,
+    };
     let cache_backend: std::sync::Arc<dyn crate::cache::CacheBackend> =
-        if let Some(redis_config) = &app_config.redis_cache {
-            std::sync::Arc::new(crate::cache::RedisCache::new(&redis_config.connection_str).await)
+        if let Some(redis_cache) = &redis_cache {
+            redis_cache.clone()
         } else {
             std::sync::Arc::new(crate::cache::LocalCache::default())
         };
 
+    // Setup the key-value store of functions, Redis is preferred if configured
+    let key_value_backend: std::sync::Arc<dyn crate::keyvalue::KeyValueBackend> =
+        if let Some(redis_cache) = &redis_cache {
+            redis_cache.clone()
+        } else {
+            std::sync::Arc::new(crate::keyvalue::DbKeyValueStore::new(db_pool.clone()))
+        };
+
+    // Tell other replicas about changes, Redis is required to reach them
+    let invalidation_bus: std::sync::Arc<dyn crate::invalidation::InvalidationBus> =
+        if let Some(redis_config) = &app_config.redis_cache {
+            std::sync::Arc::new(
+                crate::invalidation::RedisBus::new(&redis_config.connection_str).await,
+            )
+        } else {
+            std::sync::Arc::new(crate::invalidation::LocalBus::default())
+        };
+
+    // Elect the replica running scheduled functions, Redis is preferred if configured
+    let leader_election = app_config.leader_election.then(|| {
+        let lease_backend: std::sync::Arc<dyn scheduler::lease::LeaseBackend> =
+            if let Some(redis_cache) = redis_cache {
+                redis_cache
+            } else {
+                std::sync::Arc::new(scheduler::lease::DbLease::new(db_pool.clone()))
+            };
+        let leader_election = std::sync::Arc::new(scheduler::lease::LeaderElection::new(
+            lease_backend,
+            std::time::Duration::from_secs(app_config.lease_ttl_secs),
+        ));
+        leader_election.spawn();
+        leader_election
+    });
+
+    // Setup encryption of secrets and move secrets of former keys to the current key
+    let secret_cipher = std::sync::Arc::new(match &app_config.secret_encryption.key {
+        Some(key) => {
+            let secret_cipher =
+                crate::secrets::SecretCipher::new(key, &app_config.secret_encryption.previous_keys)
+                    .expect("Secret encryption key is invalid");
+            crate::services::secret_service::rotate_secrets(&db_pool, &secret_cipher)
+                .await
+                .expect("Failed to rotate secrets");
+            secret_cipher
+        }
+        None => {
+            // Functions of scopes with secrets would fail on every call without the key
+            if crate::services::secret_service::has_secrets(&db_pool)
+                .await
+                .expect("Failed to look up secrets")
+            {
+                panic!("Scopes have secrets, but SECRET_ENCRYPTION_KEY is not set");
+            }
+            tracing::warn!(
+                "SECRET_ENCRYPTION_KEY is not set, scope secrets can't be stored or passed to functions"
+            );
+            crate::secrets::SecretCipher::disabled()
+        }
+    });
+
     // Setup storage backend based on configuration
     let storage_backend: Box<dyn crate::storage::StorageBackend> =
         if let Some(minio_config) = &app_config.minio_storage {
@@ -63,13 +129,27 @@ pub(crate) async fn run_server() {
     ));
 
     // Setup WASI engine
-    let wasm_engine = component::setup_engine();
+    let wasm_engine = component::setup_engine(app_config.pooling_allocator);
+    component::limits::spawn_epoch_ticker(wasm_engine.clone());
+
+    // Compiled functions are shared by HTTP and scheduled functions
+    let artifact_cache = std::sync::Arc::new(component::artifact::ArtifactCache::new(
+        wasm_engine.clone(),
+        cache_backend.clone(),
+        storage_backend.clone(),
+        app_config.persist_artifacts,
+    ));
 
     // Setup function scheduler
     let func_scheduler = scheduler::FunctionSchedulerImpl::new(
         db_pool.clone(),
         wasm_engine.clone(),
-        storage_backend.clone(),
+        artifact_cache.clone(),
+        app_config.function_limits.clone(),
+        key_value_backend.clone(),
+        secret_cipher.clone(),
+        app_config.run_history_limit,
+        leader_election,
     )
     .await;
     scheduler::run_scheduler(&func_scheduler, &db_pool).await;
@@ -82,8 +162,21 @@ pub(crate) async fn run_server() {
         Box::new(func_scheduler),
         storage_backend,
         cache_backend,
+        artifact_cache,
+        key_value_backend,
+        secret_cipher,
+        crate::invalidation::Invalidator::new(invalidation_bus),
     ));
 
+    // Apply the changes of other replicas to the caches and the scheduler
+    crate::invalidation::spawn_reconciler(runtime_state.clone());
+    if runtime_state.app_config.leader_election && runtime_state.app_config.redis_cache.is_none() {
+        crate::invalidation::spawn_schedule_resync(
+            runtime_state.clone(),
+            std::time::Duration::from_secs(runtime_state.app_config.lease_ttl_secs),
+        );
+    }
+
     // Setup server with handlers and middlewares
     let app = crate::routes::create_routes(runtime_state.clone())
         .with_state(runtime_state)
@@ -7,12 +7,76 @@ pub mod http {
         path: "./wit-http/",
         pub_export_macro: true,
         default_bindings_module: "wasm_function_sdk::blocking::http",
+        with: {
+            "jontze:keyvalue/store@0.1.0": crate::keyvalue::bindings::jontze::keyvalue::store,
+            "jontze:log/logging@0.1.0": crate::log::bindings::jontze::log::logging,
+        },
         export_macro_name: "export",
     });
 
     pub use self::Guest as Function;
 }
 
+#[allow(clippy::too_many_arguments)]
+#[cfg(feature = "http")]
+pub mod http_streaming {
+
+    wit_bindgen::generate!({
+        world: "function-http-streaming",
+        path: "./wit-http/",
+        pub_export_macro: true,
+        default_bindings_module: "wasm_function_sdk::blocking::http_streaming",
+        with: {
+            "wasi:io/error@0.2.3": generate,
+            "wasi:io/poll@0.2.3": generate,
+            "wasi:io/streams@0.2.3": generate,
+            "jontze:keyvalue/store@0.1.0": crate::keyvalue::bindings::jontze::keyvalue::store,
+            "jontze:log/logging@0.1.0": crate::log::bindings::jontze::log::logging,
+        },
+        export_macro_name: "export",
+    });
+
+    pub use self::wasi::io::streams::StreamError;
+    pub use self::Guest as Function;
+
+    /// Largest chunk read from or written to a body at once
+    const CHUNK_BYTES: usize = 4096;
+
+    impl std::io::Read for InputStream {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            match self.blocking_read(buf.len().min(CHUNK_BYTES) as u64) {
+                Ok(chunk) => {
+                    buf[..chunk.len()].copy_from_slice(&chunk);
+                    Ok(chunk.len())
+                }
+                Err(StreamError::Closed) => Ok(0),
+                Err(StreamError::LastOperationFailed(err)) => {
+                    Err(std::io::Error::other(err.to_debug_string()))
+                }
+            }
+        }
+    }
+
+    impl std::io::Write for OutputStream {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            let chunk = &buf[..buf.len().min(CHUNK_BYTES)];
+            self.blocking_write_and_flush(chunk).map_err(stream_error)?;
+            Ok(chunk.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            self.blocking_flush().map_err(stream_error)
+        }
+    }
+
+    fn stream_error(err: StreamError) -> std::io::Error {
+        match err {
+            StreamError::Closed => std::io::ErrorKind::BrokenPipe.into(),
+            StreamError::LastOperationFailed(err) => std::io::Error::other(err.to_debug_string()),
+        }
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 #[cfg(feature = "scheduled")]
 pub mod scheduled {
@@ -22,8 +86,24 @@ pub mod scheduled {
         path: "./wit-scheduled/",
         pub_export_macro: true,
         default_bindings_module: "wasm_function_sdk::blocking::scheduled",
+        with: {
+            "jontze:keyvalue/store@0.1.0": crate::keyvalue::bindings::jontze::keyvalue::store,
+            "jontze:log/logging@0.1.0": crate::log::bindings::jontze::log::logging,
+        },
         export_macro_name: "export",
     });
 
     pub use self::Guest as Function;
+
+    impl JobContext {
+        /// When the run was due, idempotent jobs derive their processing window from it
+        pub fn scheduled_time(&self) -> std::time::SystemTime {
+            crate::unix_time(self.scheduled_at_ms)
+        }
+
+        /// When this attempt started
+        pub fn start_time(&self) -> std::time::SystemTime {
+            crate::unix_time(self.started_at_ms)
+        }
+    }
 }
@@ -7,6 +7,10 @@ pub mod http_async {
          path: "./wit-http/",
          pub_export_macro: true,
          default_bindings_module: "wasm_function_sdk::future::http",
+         with: {
+             "jontze:keyvalue/store@0.1.0": crate::keyvalue::bindings::jontze::keyvalue::store,
+             "jontze:log/logging@0.1.0": crate::log::bindings::jontze::log::logging,
+         },
          async: true,
     });
 
@@ -22,8 +26,24 @@ pub mod scheduled_async {
         path: "./wit-scheduled/",
         pub_export_macro: true,
         default_bindings_module: "wasm_function_sdk::future::scheduled",
+        with: {
+            "jontze:keyvalue/store@0.1.0": crate::keyvalue::bindings::jontze::keyvalue::store,
+            "jontze:log/logging@0.1.0": crate::log::bindings::jontze::log::logging,
+        },
         async: true,
     });
 
     pub use self::Guest as Function;
+
+    impl JobContext {
+        /// When the run was due, idempotent jobs derive their processing window from it
+        pub fn scheduled_time(&self) -> std::time::SystemTime {
+            crate::unix_time(self.scheduled_at_ms)
+        }
+
+        /// When this attempt started
+        pub fn start_time(&self) -> std::time::SystemTime {
+            crate::unix_time(self.started_at_ms)
+        }
+    }
 }
@@ -0,0 +1,73 @@
+//! Key-value store shared by all functions of a scope.
+//!
+//! Values are kept between invocations, e.g. to remember the cursor of a scheduled job.
+
+#[doc(hidden)]
+#[allow(clippy::too_many_arguments)]
+pub mod bindings {
+    wit_bindgen::generate!({
+        world: "jontze:keyvalue/imports@0.1.0",
+        path: "./wit-http/",
+    });
+}
+
+use bindings::jontze::keyvalue::store;
+
+pub use store::Error;
+
+/// Returns the raw value of the key, if it exists
+pub fn get(key: &str) -> Result<Option<Vec<u8>>, Error> {
+    store::get(key)
+}
+
+/// Creates or replaces the raw value of the key
+pub fn set(key: &str, value: &[u8]) -> Result<(), Error> {
+    store::set(key, value)
+}
+
+/// Removes the key, succeeds if it does not exist
+pub fn delete(key: &str) -> Result<(), Error> {
+    store::delete(key)
+}
+
+/// Lists all keys starting with the prefix, or all keys of the scope without a prefix
+pub fn list_keys(prefix: Option<&str>) -> Result<Vec<String>, Error> {
+    store::list_keys(prefix)
+}
+
+/// Failures of the typed helpers, which (de)serialize values as JSON
+#[cfg(feature = "json")]
+#[derive(Debug)]
+pub enum JsonError {
+    Store(Error),
+    Json(serde_json::Error),
+}
+
+#[cfg(feature = "json")]
+impl std::fmt::Display for JsonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JsonError::Store(err) => write!(f, "Key-value store failed: {err}"),
+            JsonError::Json(err) => write!(f, "Value is not valid JSON: {err}"),
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+impl std::error::Error for JsonError {}
+
+/// Returns the value of the key deserialized from JSON, if it exists
+#[cfg(feature = "json")]
+pub fn get_json<T: serde::de::DeserializeOwned>(key: &str) -> Result<Option<T>, JsonError> {
+    get(key)
+        .map_err(JsonError::Store)?
+        .map(|value| serde_json::from_slice(&value).map_err(JsonError::Json))
+        .transpose()
+}
+
+/// Serializes the value as JSON and stores it under the key
+#[cfg(feature = "json")]
+pub fn set_json<T: serde::Serialize>(key: &str, value: &T) -> Result<(), JsonError> {
+    let value = serde_json::to_vec(value).map_err(JsonError::Json)?;
+    set(key, &value).map_err(JsonError::Store)
+}
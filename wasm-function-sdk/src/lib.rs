@@ -3,3 +3,15 @@ pub mod blocking;
 
 #[cfg(feature = "async")]
 pub mod future;
+
+#[cfg(any(feature = "http", feature = "scheduled"))]
+pub mod keyvalue;
+
+#[cfg(any(feature = "http", feature = "scheduled"))]
+pub mod log;
+
+/// Converts milliseconds since the Unix epoch, as the runtime passes points in time
+#[cfg(feature = "scheduled")]
+fn unix_time(millis: u64) -> std::time::SystemTime {
+    std::time::UNIX_EPOCH + std::time::Duration::from_millis(millis)
+}
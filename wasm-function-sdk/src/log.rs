@@ -0,0 +1,49 @@
+//! Structured logs of a function.
+//!
+//! Lines are stored by the runtime, tagged with the function and the request.
+//! Output written to stdout and stderr is captured as well.
+
+#[doc(hidden)]
+#[allow(clippy::too_many_arguments)]
+pub mod bindings {
+    wit_bindgen::generate!({
+        world: "jontze:log/imports@0.1.0",
+        path: "./wit-http/",
+    });
+}
+
+use bindings::jontze::log::logging;
+
+pub use logging::Level;
+
+/// Logs the message with additional key-value fields, e.g. `[("user-id", "42")]`
+pub fn log(level: Level, message: &str, fields: &[(&str, &str)]) {
+    let fields = fields
+        .iter()
+        .map(|(key, value)| logging::Field {
+            key: key.to_string(),
+            value: value.to_string(),
+        })
+        .collect::<Vec<logging::Field>>();
+    logging::log(level, message, &fields);
+}
+
+pub fn trace(message: &str) {
+    log(Level::Trace, message, &[]);
+}
+
+pub fn debug(message: &str) {
+    log(Level::Debug, message, &[]);
+}
+
+pub fn info(message: &str) {
+    log(Level::Info, message, &[]);
+}
+
+pub fn warn(message: &str) {
+    log(Level::Warn, message, &[]);
+}
+
+pub fn error(message: &str) {
+    log(Level::Error, message, &[]);
+}